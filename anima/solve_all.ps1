@@ -1,4 +1,4 @@
-Get-ChildItem ".\puzzles" |
+Get-ChildItem "..\puzzles\anima" |
 Foreach-Object {
     cargo run --release $_.FullName >> solutions.txt
 }
@@ -0,0 +1,91 @@
+use crate::{Actor, Color, Data, Goal, State, Tile, MAX_ACTORS};
+use arrayvec::ArrayVec;
+use quickcheck::{Arbitrary, Gen};
+use solver_common::{Direction, MoveSet, Vec2};
+
+/// A randomly generated puzzle whose actors all start on distinct passable
+/// tiles in sorted order, and whose goals only use colors that have an actor.
+#[derive(Clone, Debug)]
+pub struct Puzzle {
+    pub state: State,
+    pub data: Data,
+}
+
+fn range(g: &mut Gen, min: i32, max: i32) -> i32 {
+    min + (u32::arbitrary(g) % (max - min + 1) as u32) as i32
+}
+
+impl Arbitrary for Puzzle {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let size = Vec2::new(range(g, 2, 5), range(g, 2, 5));
+        let mut tiles = (0..size.x * size.y)
+            .map(|_| {
+                *g.choose(&[
+                    Tile::Passable,
+                    Tile::Passable,
+                    Tile::Passable,
+                    Tile::Impassable,
+                    Tile::OneWay(Direction::Right),
+                    Tile::OneWay(Direction::Up),
+                    Tile::Hazard,
+                ])
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        let mut actors = ArrayVec::<Actor, MAX_ACTORS>::new();
+        for _ in 0..range(g, 1, 3) {
+            let actor = Actor {
+                position: Vec2::new(range(g, 0, size.x - 1), range(g, 0, size.y - 1)),
+                color: *g.choose(&[Color::Red, Color::Blue]).unwrap(),
+            };
+            if actors.iter().all(|a| a.position != actor.position) {
+                tiles[(actor.position.x + actor.position.y * size.x) as usize] = Tile::Passable;
+                actors.push(actor);
+            }
+        }
+        actors.sort_unstable();
+
+        let mut goals = Vec::<Goal>::new();
+        for actor in actors.iter() {
+            let position = Vec2::new(range(g, 0, size.x - 1), range(g, 0, size.y - 1));
+            if goals.iter().all(|g| g.position != position) {
+                tiles[(position.x + position.y * size.x) as usize] = Tile::Passable;
+                goals.push(Goal {
+                    position,
+                    color: actor.color,
+                    latched: bool::arbitrary(g),
+                });
+            }
+        }
+
+        let mut teleporters = Vec::new();
+        if bool::arbitrary(g) {
+            let a = Vec2::new(range(g, 0, size.x - 1), range(g, 0, size.y - 1));
+            let b = Vec2::new(range(g, 0, size.x - 1), range(g, 0, size.y - 1));
+            if a != b {
+                tiles[(a.x + a.y * size.x) as usize] = Tile::Passable;
+                tiles[(b.x + b.y * size.x) as usize] = Tile::Passable;
+                teleporters.push((a, b));
+            }
+        }
+
+        let mut data = Data::new(
+            size,
+            Vec2::default(),
+            tiles,
+            goals,
+            MoveSet {
+                wait: bool::arbitrary(g),
+                diagonal: bool::arbitrary(g),
+            },
+            teleporters,
+        );
+        data.survive = bool::arbitrary(g);
+        data.actor_count = actors.len();
+        Puzzle {
+            state: State::new(actors, &data),
+            data,
+        }
+    }
+}
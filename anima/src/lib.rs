@@ -1,133 +1,630 @@
 use arrayvec::ArrayVec;
 use core::{fmt, num::ParseIntError};
-use solver_common::{Direction, Vec2};
+use solver_common::{Direction, Move, MoveSet, ParseMoveSetError, Solution, Status, Vec2};
+
+#[cfg(any(test, feature = "arbitrary"))]
+mod arbitrary;
+#[cfg(any(test, feature = "arbitrary"))]
+pub use crate::arbitrary::Puzzle;
 
 #[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
-enum Color {
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum Color {
     Red,
     Blue,
 }
 
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Color::Red => write!(f, "red"),
+            Color::Blue => write!(f, "blue"),
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
-enum Tile {
+pub enum Tile {
     Passable,
     Impassable,
+    /// Can only be entered by moving in the given direction.
+    OneWay(Direction),
+    /// Removes any actor that ends a move on it.
+    Hazard,
+}
+
+impl Tile {
+    fn to_char(self) -> char {
+        match self {
+            Tile::Passable => '.',
+            Tile::Impassable => ' ',
+            Tile::OneWay(Direction::Right) => '>',
+            Tile::OneWay(Direction::Up) => '^',
+            Tile::OneWay(Direction::Left) => '<',
+            Tile::OneWay(Direction::Down) => 'v',
+            Tile::Hazard => 'x',
+        }
+    }
+}
+
+/// The most goals a puzzle can have if any of them are latched, since
+/// touched goals are tracked with one bit each.
+const MAX_LATCHED_GOALS: usize = 64;
+
+/// The most actors a puzzle can start with. States keep their actors inline,
+/// so raising this makes every state bigger.
+pub const MAX_ACTORS: usize = 8;
+
+/// One bit for each tile of a puzzle, by tile index.
+#[derive(Clone, Debug)]
+struct TileSet {
+    bits: Vec<u64>,
+}
+
+impl TileSet {
+    fn new(len: usize) -> TileSet {
+        TileSet {
+            bits: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    #[inline]
+    fn contains(&self, index: usize) -> bool {
+        self.bits[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    /// Adds a tile, returning whether it wasn't in the set already.
+    #[inline]
+    fn insert(&mut self, index: usize) -> bool {
+        let inserted = !self.contains(index);
+        self.bits[index / 64] |= 1 << (index % 64);
+        inserted
+    }
 }
 
-struct Goal {
+#[derive(Clone, Debug)]
+pub struct Goal {
     position: Vec2,
     color: Color,
+    latched: bool,
+}
+
+impl Goal {
+    pub fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    /// The color of actor that has to end up on the goal.
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// Whether the goal stays covered once an actor of its color has touched
+    /// it, instead of needing one on it when the puzzle is solved.
+    pub fn latched(&self) -> bool {
+        self.latched
+    }
 }
 
+#[derive(Clone, Debug)]
 pub struct Data {
     size: Vec2,
+    /// The position of the bottom left tile, which lets boards cut out of a
+    /// bigger level keep its coordinates.
+    origin: Vec2,
     tiles: Vec<Tile>,
+    /// The tiles any move can enter, which is every tile but walls and
+    /// one-way tiles.
+    open: TileSet,
     goals: Vec<Goal>,
+    moves: MoveSet,
+    teleporters: Vec<(Vec2, Vec2)>,
+    /// The fewest moves between teleporting into one endpoint and stepping
+    /// into another, ignoring walls, indexed by endpoint pairs. Endpoint
+    /// `2 * i` is the first tile of teleporter pair `i` and `2 * i + 1` the
+    /// second.
+    hops: Vec<usize>,
+    /// For each goal, the tiles an actor could reach it from if the other
+    /// actors never got in the way.
+    reaches: Vec<TileSet>,
+    /// The move count the game shows as par, if known.
+    par: Option<usize>,
+    /// Whether the puzzle fails as soon as any actor is lost to a hazard.
+    survive: bool,
+    /// How many actors the puzzle starts with.
+    actor_count: usize,
+    /// Tiles some actor has to stand on, in order, before the puzzle counts
+    /// as solved.
+    waypoints: Vec<Vec2>,
 }
 
 impl Data {
-    fn tile(&self, position: Vec2) -> Tile {
-        if position.x < 0
-            || position.x >= self.size.x
-            || position.y < 0
-            || position.y >= self.size.y
-        {
-            Tile::Impassable
+    fn new(
+        size: Vec2,
+        origin: Vec2,
+        tiles: Vec<Tile>,
+        goals: Vec<Goal>,
+        moves: MoveSet,
+        teleporters: Vec<(Vec2, Vec2)>,
+    ) -> Data {
+        let mut open = TileSet::new(tiles.len());
+        for (i, tile) in tiles.iter().enumerate() {
+            if matches!(tile, Tile::Passable | Tile::Hazard) {
+                open.insert(i);
+            }
+        }
+        let mut result = Data {
+            size,
+            origin,
+            tiles,
+            open,
+            goals,
+            moves,
+            teleporters,
+            hops: Vec::new(),
+            reaches: Vec::new(),
+            par: None,
+            survive: false,
+            actor_count: 0,
+            waypoints: Vec::new(),
+        };
+        result.hops = result.teleporter_hops();
+        result.reaches = result
+            .goals
+            .iter()
+            .map(|g| result.tiles_reaching(g.position))
+            .collect();
+        result
+    }
+
+    fn index(&self, position: Vec2) -> Option<usize> {
+        let local = position - self.origin;
+        if local.x < 0 || local.x >= self.size.x || local.y < 0 || local.y >= self.size.y {
+            None
         } else {
-            self.tiles[(position.x + position.y * self.size.x) as usize]
+            Some((local.x + local.y * self.size.x) as usize)
         }
     }
 
-    fn is_solved_by(&self, state: &State) -> bool {
-        self.goals.iter().all(|g| {
-            state
+    pub fn size(&self) -> Vec2 {
+        self.size
+    }
+
+    pub fn origin(&self) -> Vec2 {
+        self.origin
+    }
+
+    /// The tile at `position`, which is `Impassable` anywhere off the puzzle.
+    pub fn tile(&self, position: Vec2) -> Tile {
+        match self.index(position) {
+            Some(index) => self.tiles[index],
+            None => Tile::Impassable,
+        }
+    }
+
+    /// Every tile in the puzzle along with its position, row by row from the
+    /// bottom.
+    pub fn tiles(&self) -> impl Iterator<Item = (Vec2, Tile)> + '_ {
+        let width = self.size.x;
+        let origin = self.origin;
+        self.tiles
+            .iter()
+            .enumerate()
+            .map(move |(i, &tile)| (origin + Vec2::new(i as i32 % width, i as i32 / width), tile))
+    }
+
+    pub fn goals(&self) -> &[Goal] {
+        &self.goals
+    }
+
+    pub fn moves(&self) -> MoveSet {
+        self.moves
+    }
+
+    /// The pairs of teleporter tiles, each of which sends actors to the other.
+    pub fn teleporters(&self) -> &[(Vec2, Vec2)] {
+        &self.teleporters
+    }
+
+    pub fn par(&self) -> Option<usize> {
+        self.par
+    }
+
+    /// Whether every actor has to survive for the puzzle to be solved.
+    pub fn survive(&self) -> bool {
+        self.survive
+    }
+
+    /// Whether an actor can step onto `position` by moving by `offset`.
+    /// Diagonal steps may enter a one-way tile if either of their components
+    /// goes the right way.
+    #[inline]
+    fn can_enter(&self, position: Vec2, offset: Vec2) -> bool {
+        let index = match self.index(position) {
+            Some(index) if self.open.contains(index) => return true,
+            Some(index) => index,
+            None => return false,
+        };
+        match self.tiles[index] {
+            Tile::Passable | Tile::Hazard => true,
+            Tile::Impassable => false,
+            Tile::OneWay(direction) => {
+                let allowed = direction.to_vec2();
+                allowed.x * offset.x + allowed.y * offset.y > 0
+            }
+        }
+    }
+
+    fn twin(&self, position: Vec2) -> Option<Vec2> {
+        self.teleporters.iter().find_map(|&(a, b)| {
+            if a == position {
+                Some(b)
+            } else if b == position {
+                Some(a)
+            } else {
+                None
+            }
+        })
+    }
+
+    fn endpoint(&self, e: usize) -> Vec2 {
+        let (a, b) = self.teleporters[e / 2];
+        if e & 1 == 0 {
+            a
+        } else {
+            b
+        }
+    }
+
+    /// Works out `hops` with Floyd-Warshall. Stepping into endpoint `e`
+    /// leaves the actor on endpoint `e ^ 1`, so chains of teleporters cost
+    /// only the moves between them.
+    fn teleporter_hops(&self) -> Vec<usize> {
+        let n = self.teleporters.len() * 2;
+        let mut hops = vec![0; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                if i != j {
+                    let exit = self.endpoint(i ^ 1);
+                    hops[i * n + j] = self.moves.distance(self.endpoint(j) - exit);
+                }
+            }
+        }
+        for k in 0..n {
+            for i in 0..n {
+                for j in 0..n {
+                    let through = hops[i * n + k] + hops[k * n + j];
+                    if through < hops[i * n + j] {
+                        hops[i * n + j] = through;
+                    }
+                }
+            }
+        }
+        hops
+    }
+
+    /// The fewest moves an actor needs to get from one tile to another,
+    /// ignoring walls but taking any teleporters along the way.
+    fn distance(&self, from: Vec2, to: Vec2) -> usize {
+        let mut result = self.moves.distance(to - from);
+        let n = self.teleporters.len() * 2;
+        for i in 0..n {
+            let enter = self.moves.distance(self.endpoint(i) - from);
+            if enter >= result {
+                continue;
+            }
+            for j in 0..n {
+                let leave = self.moves.distance(to - self.endpoint(j ^ 1));
+                result = usize::min(result, enter + self.hops[i * n + j] + leave);
+            }
+        }
+        result
+    }
+
+    /// Whether goal `g` is covered, either by an actor of its color standing
+    /// on it or by having been latched earlier.
+    fn is_covered(&self, state: &State, g: usize) -> bool {
+        let goal = &self.goals[g];
+        state.has_latched(g)
+            || state
                 .actors
                 .iter()
-                .any(|a| a.position == g.position && a.color == g.color)
-        })
+                .any(|a| a.position == goal.position && a.color == goal.color)
+    }
+
+    fn is_solved_by(&self, state: &State) -> bool {
+        (0..self.goals.len()).all(|g| self.is_covered(state, g))
+    }
+
+    /// Walks backwards from `target` to find every tile an actor could reach
+    /// it from. Other actors only ever stop moves, so ignoring them can only
+    /// find more tiles. Move sets are symmetric, so the same tiles work for
+    /// both colors even though blue actors move opposite to red ones.
+    fn tiles_reaching(&self, target: Vec2) -> TileSet {
+        let offsets = self
+            .moves
+            .moves()
+            .map(Move::to_vec2)
+            .filter(|&o| o != Vec2::new(0, 0))
+            .collect::<ArrayVec<_, 8>>();
+
+        let mut reaches = TileSet::new(self.tiles.len());
+        let mut open = Vec::new();
+        if let Some(index) = self.index(target) {
+            reaches.insert(index);
+            open.push(target);
+        }
+        while let Some(position) = open.pop() {
+            // Actors arrive by stepping onto a tile, or by stepping onto its
+            // twin and teleporting over
+            let entrances = Some(position).into_iter().chain(self.twin(position));
+            for entrance in entrances {
+                for &offset in offsets.iter() {
+                    if !self.can_enter(entrance, offset) {
+                        continue;
+                    }
+                    // Actors never get to move off a hazard
+                    let from = entrance - offset;
+                    if matches!(self.tile(from), Tile::Impassable | Tile::Hazard) {
+                        continue;
+                    }
+                    if reaches.insert(self.index(from).unwrap()) {
+                        open.push(from);
+                    }
+                }
+            }
+        }
+        reaches
+    }
+
+    /// The first goal which no actor of its color can reach anymore. Goals
+    /// that were already latched don't need reaching again.
+    fn unreachable_goal(&self, state: &State) -> Option<&Goal> {
+        (0..self.goals.len())
+            .filter(|&g| !state.has_latched(g))
+            .find(|&g| !state.actors.iter().any(|a| self.can_reach(a, g)))
+            .map(|g| &self.goals[g])
+    }
+
+    /// Whether `actor` could ever get to goal `g`.
+    fn can_reach(&self, actor: &Actor, g: usize) -> bool {
+        actor.color == self.goals[g].color
+            && self
+                .index(actor.position)
+                .is_some_and(|i| self.reaches[g].contains(i))
+    }
+
+    /// Finds a goal left without an actor when every goal is given its own
+    /// actor of its color that can reach it. Goals which aren't latched need
+    /// an actor on each of them at once, so there must be some way to pair
+    /// them up. One actor can visit any number of latched goals in turn.
+    fn unmatched_goal(&self, state: &State) -> Option<&Goal> {
+        let mut assigned = [None; MAX_ACTORS];
+        (0..self.goals.len())
+            .filter(|&g| !self.goals[g].latched)
+            .find(|&g| {
+                let mut visited = [false; MAX_ACTORS];
+                !self.assign(state, g, &mut assigned, &mut visited)
+            })
+            .map(|g| &self.goals[g])
+    }
+
+    /// Gives goal `g` an actor, moving earlier goals onto other actors if
+    /// needed, and returns whether it succeeded.
+    fn assign(
+        &self,
+        state: &State,
+        g: usize,
+        assigned: &mut [Option<usize>],
+        visited: &mut [bool],
+    ) -> bool {
+        for (a, actor) in state.actors.iter().enumerate() {
+            if visited[a] || !self.can_reach(actor, g) {
+                continue;
+            }
+            visited[a] = true;
+            if assigned[a].is_none_or(|other| self.assign(state, other, assigned, visited)) {
+                assigned[a] = Some(g);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Puzzles fail once the actors left can't cover the goals, which happens
+    /// when they get shut out by one-way tiles or lost to hazards, or as soon
+    /// as any actor is lost if every actor has to survive.
+    fn status_of(&self, state: &State) -> Status {
+        if self.survive && state.actors.len() < self.actor_count {
+            Status::Failed
+        } else if self.is_solved_by(state) {
+            Status::Solved(())
+        } else if self.unreachable_goal(state).is_some() || self.unmatched_goal(state).is_some() {
+            Status::Failed
+        } else {
+            Status::Unsolved
+        }
     }
 }
 
 #[derive(Debug, Clone, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 struct Actor {
     position: Vec2,
     color: Color,
 }
 
 #[derive(Debug, Clone, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct State {
-    actors: ArrayVec<Actor, 8>,
+    actors: ArrayVec<Actor, MAX_ACTORS>,
+    /// One bit for each latched goal an actor has touched, by goal index.
+    latched: u64,
 }
 
 impl State {
-    fn transition(&self, data: &Data, direction: &Direction) -> State {
+    fn new(actors: ArrayVec<Actor, MAX_ACTORS>, data: &Data) -> State {
+        let mut result = State { actors, latched: 0 };
+        result.latch(data);
+        result
+    }
+
+    fn has_latched(&self, g: usize) -> bool {
+        // Only the first `MAX_LATCHED_GOALS` goals can be latched
+        g < MAX_LATCHED_GOALS && self.latched & (1 << g) != 0
+    }
+
+    /// Latches every latched goal with an actor of its color on it.
+    fn latch(&mut self, data: &Data) {
+        for (g, goal) in data.goals.iter().enumerate() {
+            let touched = self
+                .actors
+                .iter()
+                .any(|a| a.position == goal.position && a.color == goal.color);
+            if goal.latched && touched {
+                self.latched |= 1 << g;
+            }
+        }
+    }
+
+    fn transition(&self, data: &Data, action: Move) -> State {
         let mut result = self.clone();
 
         for actor in result.actors.iter_mut() {
-            let next_position = match actor.color {
-                Color::Red => actor.position + direction.to_vec2(),
-                Color::Blue => actor.position - direction.to_vec2(),
+            let offset = match actor.color {
+                Color::Red => action.to_vec2(),
+                Color::Blue => action.to_vec2() * -1,
             };
+            let next_position = actor.position + offset;
 
-            if data.tile(next_position) == Tile::Passable {
+            if data.can_enter(next_position, offset) {
                 actor.position = next_position;
             }
         }
 
-        let mut done = false;
-        while !done {
-            done = true;
-            for i in 0..result.actors.len() {
-                for j in i + 1..result.actors.len() {
-                    if result.actors[i].position == result.actors[j].position {
-                        result.actors[i].position = self.actors[i].position;
-                        result.actors[j].position = self.actors[j].position;
-                        done = false;
-                    }
+        // Every actor sharing a tile goes back to where it started at the same
+        // time, and that repeats until nobody overlaps. Reverting whole groups
+        // at once instead of pair by pair means the outcome only depends on
+        // where the actors are, never on the order they're stored in.
+        loop {
+            // Sorting by position puts actors sharing a tile next to each
+            // other
+            let mut order = (0..result.actors.len()).collect::<ArrayVec<_, MAX_ACTORS>>();
+            order.sort_unstable_by_key(|&i| result.actors[i].position);
+            let mut blocked = ArrayVec::<_, MAX_ACTORS>::new();
+            for (k, &i) in order.iter().enumerate() {
+                let position = result.actors[i].position;
+                let shared = (k > 0 && result.actors[order[k - 1]].position == position)
+                    || order
+                        .get(k + 1)
+                        .is_some_and(|&j| result.actors[j].position == position);
+                if shared {
+                    blocked.push(i);
                 }
             }
+            if blocked.is_empty() {
+                break;
+            }
+            for i in blocked {
+                result.actors[i].position = self.actors[i].position;
+            }
         }
 
+        result.teleport(data, self);
+        result
+            .actors
+            .retain(|actor| data.tile(actor.position) != Tile::Hazard);
         result.actors.sort_unstable();
+        result.latch(data);
 
         result
     }
+
+    /// Sends actors that moved onto a teleporter to its twin. An actor stays
+    /// put if the twin is occupied or another actor is teleporting there too.
+    fn teleport(&mut self, data: &Data, previous: &State) {
+        let destinations = self
+            .actors
+            .iter()
+            .zip(previous.actors.iter())
+            .map(|(actor, previous)| {
+                if actor.position == previous.position {
+                    None
+                } else {
+                    data.twin(actor.position)
+                        .filter(|&twin| data.tile(twin) != Tile::Impassable)
+                }
+            })
+            .collect::<ArrayVec<_, MAX_ACTORS>>();
+
+        for (i, destination) in destinations.iter().enumerate() {
+            if let Some(destination) = *destination {
+                let occupied = self.actors.iter().any(|a| a.position == destination);
+                let contested = destinations
+                    .iter()
+                    .enumerate()
+                    .any(|(j, d)| j != i && *d == Some(destination));
+                if !occupied && !contested {
+                    self.actors[i].position = destination;
+                }
+            }
+        }
+    }
+}
+
+impl brutalize::Waypoints for State {
+    fn waypoint_count(data: &Data) -> usize {
+        data.waypoints.len()
+    }
+
+    fn reaches_waypoint(&self, data: &Data, index: usize) -> bool {
+        let waypoint = data.waypoints[index];
+        self.actors.iter().any(|a| a.position == waypoint)
+    }
 }
 
 impl brutalize::State for State {
     type Data = Data;
-    type Action = Direction;
-    type Transitions = [(Self::Action, brutalize::Transition<Self>); 4];
+    type Action = Move;
+    type Outcome = ();
+    type Transitions = ArrayVec<(Self::Action, brutalize::Transition<Self>), 9>;
     type Heuristic = usize;
 
     fn transitions(&self, data: &Self::Data) -> Self::Transitions {
         let mut result = ArrayVec::new();
-        for direction in [
-            Direction::Right,
-            Direction::Up,
-            Direction::Left,
-            Direction::Down,
-        ]
-        .iter()
-        {
-            let state = self.transition(data, direction);
-            if data.is_solved_by(&state) {
-                result.push((*direction, brutalize::Transition::Success));
-            } else {
-                result.push((*direction, brutalize::Transition::Indeterminate(state)));
+        for action in data.moves.moves() {
+            let state = self.transition(data, action);
+            if let Some(transition) = data.status_of(&state).into_transition(|| state) {
+                result.push((action, transition));
             }
         }
-        unsafe { result.into_inner_unchecked() }
+        result
+    }
+
+    fn is_goal(&self, data: &Self::Data) -> Option<Self::Outcome> {
+        data.status_of(self).outcome()
+    }
+
+    fn infeasible(&self, data: &Self::Data) -> Option<String> {
+        data.unmatched_goal(self).map(|goal| {
+            format!(
+                "{} goals outnumber the {} actors that can reach them, including the goal at {} {}",
+                goal.color, goal.color, goal.position.x, goal.position.y
+            )
+        })
     }
 
     fn heuristic(&self, data: &Self::Data) -> Self::Heuristic {
         let mut max_distance = 0;
 
-        for goal in data.goals.iter() {
+        for (g, goal) in data.goals.iter().enumerate() {
+            if self.has_latched(g) {
+                continue;
+            }
             let mut min_distance = usize::MAX;
             for actor in self.actors.iter().filter(|a| a.color == goal.color) {
-                let d = (goal.position - actor.position).abs();
-                min_distance = usize::min(min_distance, (d.x + d.y) as usize);
+                let d = data.distance(actor.position, goal.position);
+                min_distance = usize::min(min_distance, d);
             }
             max_distance = usize::max(max_distance, min_distance);
         }
@@ -171,11 +668,126 @@ pub enum ParseError {
         line_number: usize,
         parse_error: ParseIntError,
     },
+    InvalidActions {
+        line_number: usize,
+        parse_error: ParseMoveSetError,
+    },
+    TeleportersAlreadyDefined {
+        line_number: usize,
+    },
+    MissingTeleportersCount {
+        line_number: usize,
+    },
+    InvalidTeleportersCount {
+        line_number: usize,
+        parse_error: ParseIntError,
+    },
+    UnexpectedEndOfTeleporters {
+        expected_lines: usize,
+        found_lines: usize,
+    },
+    MissingTeleporterCoordinate {
+        line_number: usize,
+    },
+    InvalidTeleporterCoordinate {
+        line_number: usize,
+        parse_error: ParseIntError,
+    },
+    DuplicateTeleporter {
+        line_number: usize,
+    },
+    ImpassableTeleporter {
+        line_number: usize,
+    },
+    OriginAlreadyDefined {
+        line_number: usize,
+    },
+    MissingOriginCoordinate {
+        line_number: usize,
+    },
+    InvalidOriginCoordinate {
+        line_number: usize,
+        parse_error: ParseIntError,
+    },
+    ParAlreadyDefined {
+        line_number: usize,
+    },
+    MissingPar {
+        line_number: usize,
+    },
+    InvalidPar {
+        line_number: usize,
+        parse_error: ParseIntError,
+    },
+    MissingLatchedCoordinate {
+        line_number: usize,
+    },
+    InvalidLatchedCoordinate {
+        line_number: usize,
+        parse_error: ParseIntError,
+    },
+    NoGoalToLatch {
+        line_number: usize,
+    },
+    DuplicateLatchedGoal {
+        line_number: usize,
+    },
+    TooManyLatchedGoals {
+        line_number: usize,
+    },
+    TooManyActors {
+        line_number: usize,
+    },
+    MissingWaypointCoordinate {
+        line_number: usize,
+    },
+    InvalidWaypointCoordinate {
+        line_number: usize,
+        parse_error: ParseIntError,
+    },
 }
 
 impl brutalize_cli::State for State {
     type ParseError = ParseError;
 
+    const LEGEND: &'static str = concat!(
+        ". passable    r/b red/blue goal     R/B red/blue actor\n",
+        "o teleporter  >^<v one-way entry    * latched goal\n",
+        "x hazard\n",
+    );
+
+    const GLYPHS: &'static [(char, &'static str)] = &[
+        (' ', "🧱"),
+        ('.', "░░"),
+        ('r', "🟥"),
+        ('b', "🟦"),
+        ('R', "🔴"),
+        ('B', "🔵"),
+        ('o', "🌀"),
+        ('x', "💣"),
+        ('*', "✅"),
+        ('>', "→ "),
+        ('^', "↑ "),
+        ('<', "← "),
+        ('v', "↓ "),
+    ];
+
+    const COLORS: &'static [(char, &'static str)] = &[
+        (' ', "#444"),
+        ('.', "#eee"),
+        ('r', "#f99"),
+        ('b', "#99f"),
+        ('R', "#d22"),
+        ('B', "#22d"),
+        ('o', "#b7f"),
+        ('x', "#fa0"),
+        ('*', "#8d8"),
+        ('>', "#ccc"),
+        ('^', "#ccc"),
+        ('<', "#ccc"),
+        ('v', "#ccc"),
+    ];
+
     fn parse(s: &str) -> Result<(State, Data), ParseError> {
         let size_x = s.lines().next().ok_or(ParseError::NoRows)?.len();
         let size_y = s
@@ -185,9 +797,17 @@ impl brutalize_cli::State for State {
             .ok_or(ParseError::NoLineBreakAfterRows)?
             .0;
 
-        let mut tiles = vec![Tile::Impassable; size_x * size_y as usize];
+        let mut tiles = vec![Tile::Impassable; size_x * size_y];
         let mut goals = Vec::new();
         let mut actors = ArrayVec::new();
+        let mut moves = MoveSet::default();
+        let mut teleporters = None;
+        let mut teleporter_lines = Vec::new();
+        let mut origin = None;
+        let mut par = None;
+        let mut latched = Vec::new();
+        let mut waypoints = Vec::new();
+        let mut survive = false;
 
         let mut lines = s.lines().enumerate();
         for y in (0..size_y).rev() {
@@ -205,10 +825,16 @@ impl brutalize_cli::State for State {
                 let tile = match c {
                     '.' => Ok(Tile::Passable),
                     ' ' => Ok(Tile::Impassable),
+                    '>' => Ok(Tile::OneWay(Direction::Right)),
+                    '^' => Ok(Tile::OneWay(Direction::Up)),
+                    '<' => Ok(Tile::OneWay(Direction::Left)),
+                    'v' => Ok(Tile::OneWay(Direction::Down)),
+                    'x' => Ok(Tile::Hazard),
                     'r' => {
                         goals.push(Goal {
                             position: Vec2::new(x as i32, y as i32),
                             color: Color::Red,
+                            latched: false,
                         });
                         Ok(Tile::Passable)
                     }
@@ -216,6 +842,7 @@ impl brutalize_cli::State for State {
                         goals.push(Goal {
                             position: Vec2::new(x as i32, y as i32),
                             color: Color::Blue,
+                            latched: false,
                         });
                         Ok(Tile::Passable)
                     }
@@ -231,7 +858,153 @@ impl brutalize_cli::State for State {
 
         lines.next();
 
-        for (line_number, line) in lines {
+        while let Some((line_number, line)) = lines.next() {
+            if let Some(count) = line.strip_prefix("teleporters ") {
+                if teleporters.is_some() {
+                    return Err(ParseError::TeleportersAlreadyDefined { line_number });
+                }
+
+                let count =
+                    count
+                        .parse()
+                        .map_err(|parse_error| ParseError::InvalidTeleportersCount {
+                            line_number,
+                            parse_error,
+                        })?;
+
+                // The count comes from the file, so it can't size an allocation
+                let mut pairs = Vec::<(Vec2, Vec2)>::new();
+                for i in 0..count {
+                    let (line_number, line) =
+                        lines.next().ok_or(ParseError::UnexpectedEndOfTeleporters {
+                            expected_lines: count,
+                            found_lines: i,
+                        })?;
+
+                    let mut pieces = line.split(' ');
+                    let mut coordinate = || -> Result<i32, ParseError> {
+                        pieces
+                            .next()
+                            .ok_or(ParseError::MissingTeleporterCoordinate { line_number })?
+                            .parse()
+                            .map_err(|parse_error| ParseError::InvalidTeleporterCoordinate {
+                                line_number,
+                                parse_error,
+                            })
+                    };
+                    let a = Vec2::new(coordinate()?, coordinate()?);
+                    let b = Vec2::new(coordinate()?, coordinate()?);
+
+                    // Each tile can only lead to one twin
+                    let reused = pairs
+                        .iter()
+                        .any(|&(c, d)| c == a || c == b || d == a || d == b);
+                    if a == b || reused {
+                        return Err(ParseError::DuplicateTeleporter { line_number });
+                    }
+                    pairs.push((a, b));
+                    teleporter_lines.push(line_number);
+                }
+
+                teleporters = Some(pairs);
+                continue;
+            }
+            if line == "teleporters" {
+                return Err(ParseError::MissingTeleportersCount { line_number });
+            }
+
+            if let Some(coordinates) = line.strip_prefix("origin ") {
+                if origin.is_some() {
+                    return Err(ParseError::OriginAlreadyDefined { line_number });
+                }
+
+                let mut pieces = coordinates.split(' ');
+                let mut coordinate = || -> Result<i32, ParseError> {
+                    pieces
+                        .next()
+                        .ok_or(ParseError::MissingOriginCoordinate { line_number })?
+                        .parse()
+                        .map_err(|parse_error| ParseError::InvalidOriginCoordinate {
+                            line_number,
+                            parse_error,
+                        })
+                };
+                origin = Some(Vec2::new(coordinate()?, coordinate()?));
+                continue;
+            }
+
+            if let Some(count) = line.strip_prefix("par ") {
+                if par.is_some() {
+                    return Err(ParseError::ParAlreadyDefined { line_number });
+                }
+
+                par = Some(
+                    count
+                        .parse()
+                        .map_err(|parse_error| ParseError::InvalidPar {
+                            line_number,
+                            parse_error,
+                        })?,
+                );
+                continue;
+            }
+            if line == "par" {
+                return Err(ParseError::MissingPar { line_number });
+            }
+
+            if line == "survive" {
+                survive = true;
+                continue;
+            }
+
+            if let Some(coordinates) = line.strip_prefix("latched ") {
+                let mut pieces = coordinates.split(' ');
+                let mut coordinate = || -> Result<i32, ParseError> {
+                    pieces
+                        .next()
+                        .ok_or(ParseError::MissingLatchedCoordinate { line_number })?
+                        .parse()
+                        .map_err(|parse_error| ParseError::InvalidLatchedCoordinate {
+                            line_number,
+                            parse_error,
+                        })
+                };
+                latched.push((line_number, Vec2::new(coordinate()?, coordinate()?)));
+                continue;
+            }
+            if line == "latched" {
+                return Err(ParseError::MissingLatchedCoordinate { line_number });
+            }
+
+            if let Some(coordinates) = line.strip_prefix("waypoint ") {
+                let mut pieces = coordinates.split(' ');
+                let mut coordinate = || -> Result<i32, ParseError> {
+                    pieces
+                        .next()
+                        .ok_or(ParseError::MissingWaypointCoordinate { line_number })?
+                        .parse()
+                        .map_err(|parse_error| ParseError::InvalidWaypointCoordinate {
+                            line_number,
+                            parse_error,
+                        })
+                };
+                waypoints.push(Vec2::new(coordinate()?, coordinate()?));
+                continue;
+            }
+            if line == "waypoint" {
+                return Err(ParseError::MissingWaypointCoordinate { line_number });
+            }
+
+            if let Some(flags) = line.strip_prefix("actions ") {
+                moves = flags
+                    .parse()
+                    .map_err(|parse_error| ParseError::InvalidActions {
+                        line_number,
+                        parse_error,
+                    })?;
+                continue;
+            }
+
             let mut pieces = line.split(' ');
             let color = match pieces
                 .next()
@@ -263,71 +1036,301 @@ impl brutalize_cli::State for State {
                     parse_error,
                 })?;
 
-            actors.push(Actor {
-                position: Vec2::new(actor_x, actor_y),
-                color,
-            });
+            actors
+                .try_push(Actor {
+                    position: Vec2::new(actor_x, actor_y),
+                    color,
+                })
+                .map_err(|_| ParseError::TooManyActors { line_number })?;
+        }
+
+        // Goals were found while reading the board, before the origin
+        let origin = origin.unwrap_or_default();
+        for goal in goals.iter_mut() {
+            goal.position += origin;
+        }
+
+        // Latched goals are given in puzzle coordinates like teleporters
+        for (line_number, position) in latched {
+            let g = goals
+                .iter()
+                .position(|g| g.position == position)
+                .ok_or(ParseError::NoGoalToLatch { line_number })?;
+            if goals[g].latched {
+                return Err(ParseError::DuplicateLatchedGoal { line_number });
+            }
+            if goals.len() > MAX_LATCHED_GOALS {
+                return Err(ParseError::TooManyLatchedGoals { line_number });
+            }
+            goals[g].latched = true;
+        }
+
+        // Teleporters are checked once the origin is known, and anything off
+        // the board counts as a wall
+        let teleporters = teleporters.unwrap_or_default();
+        let passable = |position: Vec2| {
+            let local = position - origin;
+            local.x >= 0
+                && (local.x as usize) < size_x
+                && local.y >= 0
+                && (local.y as usize) < size_y
+                && tiles[local.x as usize + local.y as usize * size_x] != Tile::Impassable
+        };
+        for (&line_number, &(a, b)) in teleporter_lines.iter().zip(teleporters.iter()) {
+            if !passable(a) || !passable(b) {
+                return Err(ParseError::ImpassableTeleporter { line_number });
+            }
+        }
+
+        let mut data = Data::new(
+            Vec2::new(size_x as i32, size_y as i32),
+            origin,
+            tiles,
+            goals,
+            moves,
+            teleporters,
+        );
+        data.par = par;
+        data.survive = survive;
+        data.actor_count = actors.len();
+        data.waypoints = waypoints;
+
+        Ok((State::new(actors, &data), data))
+    }
+
+    fn validate(&self, data: &Self::Data) -> Result<(), String> {
+        match data.unreachable_goal(self) {
+            Some(goal) => Err(format!(
+                "no {} actor can reach the goal at {} {}",
+                goal.color, goal.position.x, goal.position.y
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn par(data: &Self::Data) -> Option<usize> {
+        data.par
+    }
+
+    fn demo() -> Option<&'static str> {
+        brutalize_puzzles::demo("anima")
+    }
+
+    fn solve_waypoints(&self, data: &Self::Data, global: bool) -> Option<Option<Vec<Move>>> {
+        if data.waypoints.is_empty() {
+            None
+        } else {
+            Some(brutalize::solve_waypoints(self.clone(), data, global))
+        }
+    }
+
+    fn write_solution(solution: &[Self::Action]) -> Option<String> {
+        Some(Solution(solution.to_vec()).to_string())
+    }
+
+    fn parse_solution(s: &str) -> Result<Vec<Self::Action>, String> {
+        s.parse::<Solution<_>>()
+            .map(|s| s.0)
+            .map_err(|e| e.to_string())
+    }
+
+    fn write(&self, data: &Self::Data, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut board = data.tiles.iter().map(|t| t.to_char()).collect::<Vec<_>>();
+
+        for goal in data.goals.iter() {
+            board[data.index(goal.position).unwrap()] = match goal.color {
+                Color::Red => 'r',
+                Color::Blue => 'b',
+            };
+        }
+
+        for y in (0..data.size.y).rev() {
+            let begin = y * data.size.x;
+            let end = begin + data.size.x;
+            for c in &board[begin as usize..end as usize] {
+                write!(f, "{}", c)?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f)?;
+
+        if data.origin != Vec2::default() {
+            writeln!(f, "origin {} {}", data.origin.x, data.origin.y)?;
+        }
+
+        if !data.moves.is_default() {
+            writeln!(f, "actions {}", data.moves)?;
+        }
+
+        if let Some(par) = data.par {
+            writeln!(f, "par {}", par)?;
+        }
+
+        if data.survive {
+            writeln!(f, "survive")?;
+        }
+
+        for goal in data.goals.iter().filter(|g| g.latched) {
+            writeln!(f, "latched {} {}", goal.position.x, goal.position.y)?;
+        }
+
+        for waypoint in data.waypoints.iter() {
+            writeln!(f, "waypoint {} {}", waypoint.x, waypoint.y)?;
+        }
+
+        if !data.teleporters.is_empty() {
+            writeln!(f, "teleporters {}", data.teleporters.len())?;
+            for (a, b) in data.teleporters.iter() {
+                writeln!(f, "{} {} {} {}", a.x, a.y, b.x, b.y)?;
+            }
+        }
+
+        for actor in self.actors.iter() {
+            let color = match actor.color {
+                Color::Red => 'R',
+                Color::Blue => 'B',
+            };
+            writeln!(f, "{} {} {}", color, actor.position.x, actor.position.y)?;
         }
 
-        Ok((
-            State { actors },
-            Data {
-                size: Vec2::new(size_x as i32, size_y as i32),
-                tiles,
-                goals,
-            },
-        ))
+        Ok(())
     }
 
     fn display(&self, data: &Self::Data, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.board(data) {
+            writeln!(f, "{}", row.into_iter().collect::<String>())?;
+        }
+
+        Ok(())
+    }
+
+    fn cells(&self, data: &Self::Data) -> Option<Vec<Vec<char>>> {
+        Some(self.board(data))
+    }
+
+    #[cfg(feature = "json")]
+    fn to_json(&self) -> Option<String> {
+        serde_json::to_string(self).ok()
+    }
+}
+
+impl State {
+    /// The characters `display` draws for each tile, as rows from the top.
+    fn board(&self, data: &Data) -> Vec<Vec<char>> {
         let board_width = data.size.x + 2;
         let board_height = data.size.y + 2;
         let size = board_width * board_height;
         let mut board = vec![' '; size as usize];
+        // The board has a one tile border around the puzzle
+        let corner = data.origin - Vec2::new(1, 1);
+        let index_of = |position: Vec2| {
+            let position = position - corner;
+            (position.x + position.y * board_width) as usize
+        };
 
         for y in 0..board_height {
             for x in 0..board_width {
                 let index = x + y * board_width;
-                let position = Vec2::new(x, y);
-                board[index as usize] = match data.tile(position) {
-                    Tile::Passable => '.',
-                    Tile::Impassable => ' ',
-                };
+                board[index as usize] = data.tile(corner + Vec2::new(x, y)).to_char();
             }
         }
 
-        for goal in data.goals.iter() {
-            let index = goal.position.x + goal.position.y * board_width;
-            board[index as usize] = match goal.color {
+        for &(a, b) in data.teleporters.iter() {
+            for &position in [a, b].iter() {
+                board[index_of(position)] = 'o';
+            }
+        }
+
+        for (g, goal) in data.goals.iter().enumerate() {
+            board[index_of(goal.position)] = match goal.color {
+                _ if self.has_latched(g) => '*',
                 Color::Red => 'r',
                 Color::Blue => 'b',
             };
         }
 
         for actor in self.actors.iter() {
-            let index = actor.position.x + actor.position.y * board_width;
-            board[index as usize] = match actor.color {
+            board[index_of(actor.position)] = match actor.color {
                 Color::Red => 'R',
                 Color::Blue => 'B',
             };
         }
 
-        for y in (0..board_height).rev() {
-            let begin = y * board_width;
-            let end = begin + board_width;
-            for c in &board[begin as usize..end as usize] {
-                write!(f, "{}", c)?;
-            }
-            writeln!(f)?;
-        }
-
-        Ok(())
+        board
+            .chunks(board_width as usize)
+            .rev()
+            .map(|row| row.to_vec())
+            .collect()
     }
 }
 
+#[cfg(feature = "wasm")]
+const _: () = {
+    use brutalize_cli::State;
+    use wasm_bindgen::prelude::*;
+
+    #[wasm_bindgen]
+    pub fn solve(puzzle: &str) -> Option<Vec<i32>> {
+        use crate::State;
+
+        let (initial_state, data) = State::parse(puzzle).unwrap();
+        brutalize::solve::<State>(initial_state, &data).map(|actions| {
+            actions
+                .iter()
+                .map(|a| match a {
+                    Move::Right => 0,
+                    Move::Up => 1,
+                    Move::Left => 2,
+                    Move::Down => 3,
+                    Move::UpRight => 4,
+                    Move::UpLeft => 5,
+                    Move::DownLeft => 6,
+                    Move::DownRight => 7,
+                    Move::Wait => 8,
+                })
+                .collect()
+        })
+    }
+};
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use brutalize_cli::test_util::{self, Invariants, Rng};
+    use quickcheck::{Arbitrary, Gen};
+
+    impl Invariants for State {
+        fn check_invariants(&self, data: &Data) -> Result<(), String> {
+            for (i, actor) in self.actors.iter().enumerate() {
+                if data.tile(actor.position) == Tile::Impassable {
+                    return Err(format!("actor {} is on an impassable tile", i));
+                }
+                if data.tile(actor.position) == Tile::Hazard {
+                    return Err(format!("actor {} survived a hazard", i));
+                }
+                for (j, other) in self.actors.iter().enumerate().skip(i + 1) {
+                    if actor.position == other.position {
+                        return Err(format!("actors {} and {} overlap", i, j));
+                    }
+                }
+            }
+            if self.actors.windows(2).any(|w| w[0] > w[1]) {
+                return Err("actors are not sorted".to_string());
+            }
+            Ok(())
+        }
+    }
+
+    fn generate_puzzle(rng: &mut Rng) -> String {
+        let mut g = Gen::from_size_and_seed(8, rng.next_u64());
+        let puzzle = Puzzle::arbitrary(&mut g);
+        brutalize_cli::write_puzzle(&puzzle.state, &puzzle.data)
+    }
+
+    #[test]
+    fn random_puzzles_keep_invariants() {
+        test_util::check::<State, _>(&test_util::Settings::default(), generate_puzzle);
+    }
 
     fn solve_validate(initial_state: State, data: &Data, length: Option<usize>) {
         let solution = brutalize::solve(initial_state.clone(), data);
@@ -338,8 +1341,8 @@ mod tests {
             assert_eq!(solution.len(), l);
 
             let mut state = initial_state.clone();
-            for direction in solution.iter() {
-                state = state.transition(data, direction);
+            for action in solution.iter() {
+                state = state.transition(data, *action);
             }
 
             assert!(data.is_solved_by(&state));
@@ -348,6 +1351,76 @@ mod tests {
         }
     }
 
+    #[test]
+    fn write_round_trip() {
+        const PUZZLE: &str = " . \nbr.\n b \n\nR 1 1\nB 2 1\nB 1 2\n";
+
+        let (initial_state, data) = <State as brutalize_cli::State>::parse(PUZZLE).unwrap();
+        let written = brutalize_cli::write_puzzle(&initial_state, &data);
+        assert_eq!(written, PUZZLE);
+
+        let (reparsed_state, _) = <State as brutalize_cli::State>::parse(&written).unwrap();
+        assert_eq!(reparsed_state, initial_state);
+    }
+
+    #[test]
+    fn data_accessors() {
+        const PUZZLE: &str = "b.\n>r\n\nR 1 1\nteleporters 1\n0 1 1 1\n";
+
+        let (_, data) = <State as brutalize_cli::State>::parse(PUZZLE).unwrap();
+        assert_eq!(data.size(), Vec2::new(2, 2));
+        let goals = data
+            .goals()
+            .iter()
+            .map(|g| (g.position(), g.color()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            goals,
+            vec![
+                (Vec2::new(0, 1), Color::Blue),
+                (Vec2::new(1, 0), Color::Red)
+            ]
+        );
+        assert_eq!(data.teleporters(), &[(Vec2::new(0, 1), Vec2::new(1, 1))]);
+
+        let tiles = data.tiles().collect::<Vec<_>>();
+        assert_eq!(tiles[0], (Vec2::new(0, 0), Tile::OneWay(Direction::Right)));
+        assert!(tiles
+            .iter()
+            .all(|&(position, tile)| data.tile(position) == tile));
+    }
+
+    #[test]
+    fn negative_origin() {
+        const PUZZLE: &str = "r..\n.  \n\nteleporters 1\n0 0 2 1\nR 1 1\n";
+        const SHIFTED: &str = "r..\n.  \n\norigin -5 3\nteleporters 1\n-5 3 -3 4\nR -4 4\n";
+
+        let (state, data) = <State as brutalize_cli::State>::parse(SHIFTED).unwrap();
+        assert_eq!(brutalize_cli::write_puzzle(&state, &data), SHIFTED);
+        assert_eq!(data.goals()[0].position(), Vec2::new(-5, 4));
+        assert_eq!(data.tile(Vec2::new(-4, 3)), Tile::Impassable);
+        assert_eq!(data.tile(Vec2::new(0, 0)), Tile::Impassable);
+
+        let (original_state, original_data) =
+            <State as brutalize_cli::State>::parse(PUZZLE).unwrap();
+        assert_eq!(
+            brutalize_cli::display_state(&state, &data),
+            brutalize_cli::display_state(&original_state, &original_data)
+        );
+        assert_eq!(
+            brutalize::solve(state, &data),
+            brutalize::solve(original_state, &original_data)
+        );
+    }
+
+    #[test]
+    fn solve_already_solved() {
+        const PUZZLE: &str = "r.\n\nR 0 0";
+
+        let (initial_state, data) = <State as brutalize_cli::State>::parse(PUZZLE).unwrap();
+        solve_validate(initial_state, &data, Some(0));
+    }
+
     #[test]
     fn parse_solve_spiral() {
         const PUZZLE: &str = ".....\n.   .\n... .\n    .\nr....\n\nR 2 2";
@@ -379,26 +1452,385 @@ mod tests {
         let (initial_state, data) = <State as brutalize_cli::State>::parse(PUZZLE).unwrap();
         solve_validate(initial_state, &data, Some(11));
     }
-}
 
-#[cfg(feature = "wasm")]
-const _: () = {
-    use brutalize_cli::State;
-    use wasm_bindgen::prelude::*;
+    #[test]
+    fn write_round_trip_actions() {
+        const PUZZLE: &str = "r..
+...
+..b
 
-    #[wasm_bindgen]
-    pub fn solve(puzzle: &str) -> Option<Vec<i32>> {
-        use crate::State;
+actions wait diagonal
+R 0 0
+B 2 2
+";
 
-        let (initial_state, data) = State::parse(puzzle).unwrap();
-        brutalize::solve::<State>(initial_state, &data)
-            .map(|dirs| dirs.iter().map(|d| {
-                match d {
-                    Direction::Right => 0,
-                    Direction::Up => 1,
-                    Direction::Left => 2,
-                    Direction::Down => 3,
+        let (initial_state, data) = <State as brutalize_cli::State>::parse(PUZZLE).unwrap();
+        assert_eq!(
+            data.moves,
+            MoveSet {
+                wait: true,
+                diagonal: true
+            }
+        );
+        let written = brutalize_cli::write_puzzle(&initial_state, &data);
+        assert_eq!(written, PUZZLE);
+    }
+
+    #[test]
+    fn write_round_trip_teleporters() {
+        const PUZZLE: &str = "r..\n...\n...\n\nteleporters 1\n0 0 2 2\nR 1 0\n";
+
+        let (initial_state, data) = <State as brutalize_cli::State>::parse(PUZZLE).unwrap();
+        let written = brutalize_cli::write_puzzle(&initial_state, &data);
+        assert_eq!(written, PUZZLE);
+    }
+
+    #[test]
+    fn write_round_trip_par() {
+        const PUZZLE: &str = "r..\n\npar 2\nR 2 0\n";
+
+        let (initial_state, data) = <State as brutalize_cli::State>::parse(PUZZLE).unwrap();
+        assert_eq!(data.par(), Some(2));
+        let written = brutalize_cli::write_puzzle(&initial_state, &data);
+        assert_eq!(written, PUZZLE);
+    }
+
+    #[test]
+    fn bundled_puzzles_are_valid() {
+        for path in brutalize_puzzles::list_puzzles("anima") {
+            let source = std::fs::read_to_string(&path).unwrap();
+            let (state, data) = <State as brutalize_cli::State>::parse(&source).unwrap();
+            let validated = brutalize_cli::State::validate(&state, &data);
+            assert_eq!(validated, Ok(()), "{}", path.display());
+        }
+    }
+
+    #[test]
+    fn solve_teleporter() {
+        const PUZZLE: &str = "r. \n   \n...\n\nteleporters 1\n2 0 1 2\nR 0 0";
+
+        let (initial_state, data) = <State as brutalize_cli::State>::parse(PUZZLE).unwrap();
+        solve_validate(initial_state, &data, Some(3));
+    }
+
+    #[test]
+    fn solve_chained_teleporters() {
+        // Taking every teleporter in a row is far shorter than walking, which
+        // the heuristic mustn't overestimate
+        let puzzle = format!(
+            "{}\n{}r\n\nteleporters 3\n0 0 10 0\n11 0 21 0\n22 0 32 0\nR 2 0\n",
+            ".".repeat(34),
+            ".".repeat(33)
+        );
+
+        let (initial_state, data) = <State as brutalize_cli::State>::parse(&puzzle).unwrap();
+        let dijkstra = brutalize::solve_dijkstra(initial_state.clone(), &data);
+        assert_eq!(dijkstra.solution.as_ref().map(Vec::len), Some(5));
+        solve_validate(initial_state, &data, Some(5));
+    }
+
+    #[test]
+    fn teleporter_collisions() {
+        const PUZZLE: &str = ".....\n\nteleporters 1\n1 0 3 0\nR 0 0\nB 4 0";
+
+        let (initial_state, data) = <State as brutalize_cli::State>::parse(PUZZLE).unwrap();
+
+        // Both actors step onto opposite ends of the pair, so neither twin is
+        // free and nobody teleports.
+        let state = initial_state.transition(&data, Move::Right);
+        let positions = state.actors.iter().map(|a| a.position).collect::<Vec<_>>();
+        assert_eq!(positions, vec![Vec2::new(1, 0), Vec2::new(3, 0)]);
+
+        // Actors that stay on a teleporter don't bounce back and forth.
+        let stay = state.transition(&data, Move::Up);
+        assert_eq!(stay, state);
+    }
+
+    #[test]
+    fn pileup_reverts_everyone() {
+        const PUZZLE: &str = "..<\n\nR 0 0\nR 1 0\nB 2 0";
+
+        let (initial_state, data) = <State as brutalize_cli::State>::parse(PUZZLE).unwrap();
+
+        // The middle actor can't enter the one-way tile, and both of the others
+        // move onto it, so all three end up back where they started.
+        let state = initial_state.transition(&data, Move::Right);
+        assert_eq!(state, initial_state);
+    }
+
+    #[test]
+    fn big_board() {
+        let mut puzzle = String::new();
+        for y in (0..40).rev() {
+            let row = if y == 39 {
+                ".".repeat(39) + "r"
+            } else {
+                ".".repeat(40)
+            };
+            puzzle.push_str(&row);
+            puzzle.push('\n');
+        }
+        puzzle.push('\n');
+        for x in 0..MAX_ACTORS {
+            puzzle.push_str(&format!("R {} 0\n", x));
+        }
+
+        let (state, data) = <State as brutalize_cli::State>::parse(&puzzle).unwrap();
+        assert_eq!(state.actors.len(), MAX_ACTORS);
+        // The rightmost actor walks across the rest of the board and up
+        let solution = brutalize::solve(state, &data).unwrap();
+        assert_eq!(solution.len(), 40 - MAX_ACTORS + 39);
+
+        puzzle.push_str("R 0 1\n");
+        assert!(matches!(
+            <State as brutalize_cli::State>::parse(&puzzle),
+            Err(ParseError::TooManyActors { line_number: 49 })
+        ));
+    }
+
+    #[test]
+    fn many_goals() {
+        let puzzle = format!("{}\n{}\n\nR 0 0\n", "r".repeat(70), ".".repeat(70));
+        let (state, data) = <State as brutalize_cli::State>::parse(&puzzle).unwrap();
+        assert_eq!(data.goals.len(), 70);
+        // One actor can't cover every goal
+        assert_eq!(brutalize::solve(state, &data), None);
+    }
+
+    #[test]
+    fn actor_order_does_not_change_transitions() {
+        let mut rng = Rng::new(0x5eed);
+        for _ in 0..500 {
+            let mut g = Gen::from_size_and_seed(8, rng.next_u64());
+            let Puzzle { state, data } = Puzzle::arbitrary(&mut g);
+
+            for action in data.moves.moves() {
+                let expected = state.transition(&data, action);
+
+                let mut shuffled = state.clone();
+                for i in (1..shuffled.actors.len()).rev() {
+                    shuffled.actors.swap(i, rng.below(i + 1));
                 }
-            }).collect())
+                assert_eq!(shuffled.transition(&data, action), expected);
+            }
+        }
     }
-};
+
+    #[test]
+    fn parse_duplicate_teleporter() {
+        const PUZZLE: &str = "...\n\nteleporters 2\n0 0 1 0\n1 0 2 0\nR 0 0";
+
+        assert!(matches!(
+            <State as brutalize_cli::State>::parse(PUZZLE),
+            Err(ParseError::DuplicateTeleporter { line_number: 4 })
+        ));
+    }
+
+    #[test]
+    fn parse_impassable_teleporter() {
+        const OFF_BOARD: &str = "...\n\nteleporters 1\n0 0 5 0\nR 0 0";
+        const ON_WALL: &str = ". .\n\norigin 2 2\nteleporters 2\n2 2 4 2\n3 2 2 3\nR 2 2";
+
+        assert!(matches!(
+            <State as brutalize_cli::State>::parse(OFF_BOARD),
+            Err(ParseError::ImpassableTeleporter { line_number: 3 })
+        ));
+        assert!(matches!(
+            <State as brutalize_cli::State>::parse(ON_WALL),
+            Err(ParseError::ImpassableTeleporter { line_number: 5 })
+        ));
+    }
+
+    #[test]
+    fn parse_huge_teleporter_count() {
+        const PUZZLE: &str = "...\n\nteleporters 99999999999999999\n0 0 2 0\n";
+
+        assert!(matches!(
+            <State as brutalize_cli::State>::parse(PUZZLE),
+            Err(ParseError::UnexpectedEndOfTeleporters {
+                expected_lines: 99999999999999999,
+                found_lines: 1,
+            })
+        ));
+    }
+
+    #[test]
+    fn infeasible_without_enough_actors() {
+        // Either goal can be reached, but not both by the one actor
+        const PUZZLE: &str = "r.r\n\nR 1 0";
+
+        let (initial_state, data) = <State as brutalize_cli::State>::parse(PUZZLE).unwrap();
+        assert_eq!(
+            brutalize_cli::State::validate(&initial_state, &data),
+            Ok(())
+        );
+
+        let report = brutalize::solve_with_report(initial_state, &data);
+        assert_eq!(report.solution, None);
+        assert_eq!(report.expanded, 0);
+        assert_eq!(
+            report.infeasible.as_deref(),
+            Some(
+                "red goals outnumber the red actors that can reach them, including the goal at 2 0"
+            )
+        );
+    }
+
+    #[test]
+    fn solve_latched_goals() {
+        // The one actor has to touch both goals, but only latched goals let
+        // it leave the first one behind
+        const PUZZLE: &str = "r.r\n\nlatched 0 0\nlatched 2 0\nR 1 0\n";
+
+        let (initial_state, data) = <State as brutalize_cli::State>::parse(PUZZLE).unwrap();
+        assert_eq!(brutalize_cli::write_puzzle(&initial_state, &data), PUZZLE);
+        assert!(data.goals().iter().all(Goal::latched));
+        assert_eq!(brutalize::State::infeasible(&initial_state, &data), None);
+        solve_validate(initial_state.clone(), &data, Some(3));
+
+        let state = initial_state.transition(&data, Move::Left);
+        assert!(state.has_latched(0));
+        let state = state.transition(&data, Move::Right);
+        assert!(state.has_latched(0));
+        assert!(brutalize_cli::display_state(&state, &data).contains("*Rr"));
+
+        // An unlatched goal still needs an actor on it at the end
+        const MIXED: &str = "r.r\n\nlatched 2 0\nR 1 0\n";
+        let (initial_state, data) = <State as brutalize_cli::State>::parse(MIXED).unwrap();
+        solve_validate(initial_state, &data, Some(3));
+    }
+
+    #[test]
+    fn parse_latched_goals() {
+        assert!(matches!(
+            <State as brutalize_cli::State>::parse("r..\n\nlatched 1 0\nR 0 0"),
+            Err(ParseError::NoGoalToLatch { line_number: 2 })
+        ));
+        assert!(matches!(
+            <State as brutalize_cli::State>::parse("r..\n\nlatched 0 0\nlatched 0 0\nR 0 0"),
+            Err(ParseError::DuplicateLatchedGoal { line_number: 3 })
+        ));
+        assert!(matches!(
+            <State as brutalize_cli::State>::parse("r..\n\nlatched 0\nR 0 0"),
+            Err(ParseError::MissingLatchedCoordinate { line_number: 2 })
+        ));
+    }
+
+    #[test]
+    fn solve_through_waypoints() {
+        // Going straight for the goal takes two moves, but the actor has to
+        // stop by the top left corner first
+        const PUZZLE: &str = "..r\n...\n\nwaypoint 0 1\nR 1 0\n";
+
+        let (initial_state, data) = <State as brutalize_cli::State>::parse(PUZZLE).unwrap();
+        assert_eq!(brutalize_cli::write_puzzle(&initial_state, &data), PUZZLE);
+        assert_eq!(
+            brutalize::solve(initial_state.clone(), &data)
+                .unwrap()
+                .len(),
+            2
+        );
+        for &global in [false, true].iter() {
+            let solution = brutalize_cli::State::solve_waypoints(&initial_state, &data, global);
+            assert_eq!(solution.flatten().map(|s| s.len()), Some(4));
+        }
+
+        assert!(matches!(
+            <State as brutalize_cli::State>::parse("..r\n\nwaypoint 0\nR 0 0"),
+            Err(ParseError::MissingWaypointCoordinate { line_number: 2 })
+        ));
+    }
+
+    #[test]
+    fn hazards_remove_actors() {
+        const PUZZLE: &str = ".x.r\n\nR 0 0\nR 2 0\n";
+
+        let (initial_state, data) = <State as brutalize_cli::State>::parse(PUZZLE).unwrap();
+        let state = initial_state.transition(&data, Move::Right);
+        let positions = state.actors.iter().map(|a| a.position).collect::<Vec<_>>();
+        assert_eq!(positions, vec![Vec2::new(3, 0)]);
+        solve_validate(initial_state, &data, Some(1));
+
+        // Sending the other actor into the hazard is the only way to the goal
+        const SURVIVE: &str = ".x.r\n\nsurvive\nR 0 0\nR 2 0\n";
+        let (initial_state, data) = <State as brutalize_cli::State>::parse(SURVIVE).unwrap();
+        assert_eq!(brutalize_cli::write_puzzle(&initial_state, &data), SURVIVE);
+        assert!(data.status_of(&state) == Status::Failed);
+        solve_validate(initial_state, &data, None);
+    }
+
+    #[test]
+    fn fail_with_too_few_actors() {
+        // Both goals need a red actor at once, so losing either one to the
+        // hazard leaves the puzzle unsolvable
+        const PUZZLE: &str = "r.r\n.x.\n\nR 1 1\nR 0 0\n";
+
+        let (initial_state, data) = <State as brutalize_cli::State>::parse(PUZZLE).unwrap();
+        let state = initial_state.transition(&data, Move::Right);
+        assert_eq!(state.actors.len(), 1);
+        assert!(data.status_of(&state) == Status::Failed);
+    }
+
+    #[test]
+    fn one_way_tiles() {
+        const PUZZLE: &str = ".>.\n\nR 0 0\nB 2 0\n";
+
+        let (initial_state, data) = <State as brutalize_cli::State>::parse(PUZZLE).unwrap();
+        assert_eq!(brutalize_cli::write_puzzle(&initial_state, &data), PUZZLE);
+
+        // Red moves right onto the tile, blue would enter it moving left
+        let state = initial_state.transition(&data, Move::Right);
+        let positions = state.actors.iter().map(|a| a.position).collect::<Vec<_>>();
+        assert_eq!(positions, vec![Vec2::new(1, 0), Vec2::new(2, 0)]);
+    }
+
+    #[test]
+    fn shut_out_by_one_way_tile() {
+        const PUZZLE: &str = "r.>.\n\nR 1 0\n";
+
+        let (initial_state, data) = <State as brutalize_cli::State>::parse(PUZZLE).unwrap();
+        assert!(data.status_of(&initial_state) == Status::Unsolved);
+
+        // Once red steps off the far side of the one-way tile it can never
+        // come back
+        let passing = initial_state.transition(&data, Move::Right);
+        assert!(data.status_of(&passing) == Status::Unsolved);
+        let trapped = passing.transition(&data, Move::Right);
+        assert!(data.status_of(&trapped) == Status::Failed);
+        assert_eq!(
+            brutalize_cli::State::validate(&trapped, &data),
+            Err("no red actor can reach the goal at 0 0".to_string())
+        );
+        assert_eq!(brutalize::solve(trapped, &data), None);
+        solve_validate(initial_state, &data, Some(1));
+    }
+
+    #[test]
+    fn solve_diagonal() {
+        const PUZZLE: &str = "..r
+...
+...
+
+R 0 0";
+
+        let (initial_state, data) = <State as brutalize_cli::State>::parse(PUZZLE).unwrap();
+        solve_validate(initial_state, &data, Some(4));
+
+        const DIAGONAL: &str = "..r
+...
+...
+
+actions diagonal
+R 0 0";
+
+        let (initial_state, data) = <State as brutalize_cli::State>::parse(DIAGONAL).unwrap();
+        solve_validate(initial_state, &data, Some(2));
+
+        match <State as brutalize_cli::State>::parse("..r\n\nactions wait jump\nR 0 0") {
+            Err(ParseError::InvalidActions { parse_error, .. }) => {
+                assert_eq!(parse_error.to_string(), "unknown action flag 'jump'")
+            }
+            _ => panic!("the actions should be invalid"),
+        }
+    }
+}
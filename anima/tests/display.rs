@@ -0,0 +1,38 @@
+//! Compares `display` output against the expected boards checked into
+//! `tests/display`, one `.txt` file for each `.puzzle` file. Run with `BLESS`
+//! set to write the current output as the expected boards instead.
+
+use anima::State;
+use std::{env, fs, path::Path};
+
+#[test]
+fn display_matches_golden_files() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/display");
+    let mut paths = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "puzzle"))
+        .collect::<Vec<_>>();
+    paths.sort();
+    assert!(!paths.is_empty());
+
+    for path in paths {
+        let source = fs::read_to_string(&path).unwrap();
+        let (state, data) = <State as brutalize_cli::State>::parse(&source).unwrap();
+        let displayed = brutalize_cli::display_state(&state, &data);
+
+        let expected_path = path.with_extension("txt");
+        if env::var_os("BLESS").is_some() {
+            fs::write(&expected_path, &displayed).unwrap();
+            continue;
+        }
+
+        let expected = fs::read_to_string(&expected_path).unwrap_or_default();
+        assert!(
+            displayed == expected,
+            "{} doesn't match, got:\n{}",
+            expected_path.display(),
+            displayed
+        );
+    }
+}
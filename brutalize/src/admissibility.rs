@@ -0,0 +1,60 @@
+use crate::{Cost, HeuristicContext, State, Transition};
+use alloc::vec::Vec;
+
+/// A state along a solution whose heuristic estimated more than the cost
+/// actually left to reach the goal.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Overestimate<H> {
+    /// How many actions into the solution the state was reached.
+    pub step: usize,
+    pub heuristic: H,
+    /// The cost of the rest of the solution from this state.
+    pub remaining: usize,
+}
+
+/// Checks a game's heuristic against an optimal `solution`, returning every
+/// state along it whose heuristic exceeded the true remaining cost. Any
+/// overestimate means the heuristic isn't admissible and solutions found with
+/// it may not be the shortest. Returns `None` if the solution doesn't replay
+/// or doesn't finish the puzzle.
+pub fn check_admissible<S: State>(
+    initial_state: S,
+    data: &S::Data,
+    solution: &[S::Action],
+) -> Option<Vec<Overestimate<S::Heuristic>>>
+where
+    S::Action: PartialEq,
+{
+    let mut remaining = solution.iter().map(S::action_cost).sum::<usize>();
+    let mut overestimates = Vec::new();
+    let mut state = initial_state;
+    let mut context = HeuristicContext {
+        depth: 0,
+        parent_action: None,
+    };
+    for (step, action) in solution.iter().enumerate() {
+        let heuristic = state.heuristic_ctx(data, &context);
+        if heuristic > S::Heuristic::default().combine(remaining) {
+            overestimates.push(Overestimate {
+                step,
+                heuristic,
+                remaining,
+            });
+        }
+
+        remaining -= S::action_cost(action);
+        context.depth += S::action_cost(action);
+        context.parent_action = Some(action);
+        let (_, transition) = state
+            .transitions(data)
+            .into_iter()
+            .find(|(a, _)| a == action)?;
+        match transition {
+            Transition::Indeterminate(next) => state = next,
+            // Only the last action can finish the puzzle
+            Transition::Success(_) => return (step + 1 == solution.len()).then_some(overestimates),
+        }
+    }
+    // Solutions have to finish the puzzle unless it starts out solved
+    solution.is_empty().then_some(overestimates)
+}
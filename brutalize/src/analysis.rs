@@ -0,0 +1,95 @@
+use crate::{estimate, solve, Cost, State, Transition};
+use alloc::collections::BTreeMap;
+use hashbrown::HashMap;
+
+/// Statistics about how hard a puzzle is to solve by hand.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Analysis {
+    /// The cost of an optimal solution.
+    pub length: usize,
+    /// How many different optimal solutions there are.
+    pub optimal_solutions: u64,
+    /// The average number of moves available from the states explored.
+    pub branching_factor: f64,
+    /// How many explored states have no moves left at all.
+    pub dead_ends: usize,
+    /// How many states could still lie on an optimal solution.
+    pub explored: usize,
+}
+
+impl Analysis {
+    /// Combines the statistics into a single score for ordering puzzles.
+    /// Longer solutions, more choices at each step and more dead ends make a
+    /// puzzle harder, while having several optimal solutions makes one easier
+    /// to stumble on.
+    #[cfg(feature = "std")]
+    pub fn difficulty(&self) -> f64 {
+        let dead_end_fraction = self.dead_ends as f64 / self.explored.max(1) as f64;
+        self.length as f64 * self.branching_factor * (1.0 + dead_end_fraction)
+            / (1.0 + (self.optimal_solutions.max(1) as f64).ln())
+    }
+}
+
+/// Analyzes a puzzle's optimal solutions, returning `None` if it has none.
+///
+/// After finding the optimal cost, this explores every state which could
+/// still be on an optimal solution according to the heuristic, counting the
+/// paths that reach a goal at exactly that cost.
+pub fn analyze<S: State + Clone>(initial_state: S, data: &S::Data) -> Option<Analysis> {
+    let solution = solve(initial_state.clone(), data)?;
+    let length = solution.iter().map(S::action_cost).sum::<usize>();
+    let bound = S::Heuristic::default().combine(length);
+
+    let mut analysis = Analysis {
+        length,
+        optimal_solutions: 0,
+        branching_factor: 0.0,
+        dead_ends: 0,
+        explored: 0,
+    };
+    if initial_state.is_goal(data).is_some() {
+        analysis.optimal_solutions = 1;
+        return Some(analysis);
+    }
+
+    // Paths are counted per state and distance, so each layer only has to be
+    // expanded once no matter how many paths lead into it
+    let mut layers = BTreeMap::new();
+    layers.insert(0, HashMap::from([(initial_state, 1u64)]));
+    let mut moves = 0;
+    while let Some((distance, layer)) = layers.pop_first() {
+        for (state, paths) in layer {
+            analysis.explored += 1;
+            let mut any_moves = false;
+            for (action, transition) in state.transitions(data) {
+                moves += 1;
+                any_moves = true;
+                let next_distance = distance + S::action_cost(&action);
+                match transition {
+                    Transition::Success(_) => {
+                        if next_distance == length {
+                            analysis.optimal_solutions += paths;
+                        }
+                    }
+                    Transition::Indeterminate(next) => {
+                        if next_distance < length
+                            && estimate(&next, data, next_distance, Some(&action)) <= bound
+                        {
+                            *layers
+                                .entry(next_distance)
+                                .or_insert_with(HashMap::new)
+                                .entry(next)
+                                .or_insert(0) += paths;
+                        }
+                    }
+                }
+            }
+            if !any_moves {
+                analysis.dead_ends += 1;
+            }
+        }
+    }
+
+    analysis.branching_factor = moves as f64 / analysis.explored as f64;
+    Some(analysis)
+}
@@ -0,0 +1,172 @@
+use crate::{estimate, search::path_to, State, Transition};
+use alloc::{vec, vec::Vec};
+use core::hash::BuildHasher;
+use hashbrown::{DefaultHashBuilder, HashSet};
+
+/// How a beam search ended.
+#[derive(Debug, PartialEq)]
+pub enum Beam<A, O> {
+    /// A solution was found, though not necessarily the shortest one.
+    Solved(Vec<A>, O),
+    /// Every state left in the beam was a dead end, or a guarded search hit
+    /// its depth limit. States pruned from the beam might still have led to a
+    /// solution, so the puzzle may yet be solvable.
+    Unknown,
+}
+
+/// Searches layer by layer, keeping only the `width` most promising states of
+/// each layer by their heuristic estimate. Solutions aren't guaranteed to be
+/// the shortest and may be missed entirely, but memory only grows with the
+/// width and the depth reached. That makes it a quick way to tell whether a
+/// huge puzzle is plausibly solvable before committing to a full search.
+pub fn solve_beam<S: State + Clone>(
+    initial_state: S,
+    data: &S::Data,
+    width: usize,
+) -> Beam<S::Action, S::Outcome> {
+    if let Some(outcome) = initial_state.is_goal(data) {
+        return Beam::Solved(Vec::new(), outcome);
+    }
+
+    let width = width.max(1);
+    let mut parents = Vec::new();
+    let mut seen = HashSet::new();
+    seen.insert(initial_state.clone());
+    let mut layer = vec![(initial_state, 0usize, 0)];
+    while !layer.is_empty() {
+        let mut candidates = Vec::new();
+        for (state, distance, index) in layer {
+            for (action, transition) in state.transitions(data) {
+                match transition {
+                    Transition::Indeterminate(next) => {
+                        if !seen.contains(&next) {
+                            let distance = distance.saturating_add(S::action_cost(&action));
+                            let estimate = estimate(&next, data, distance, Some(&action));
+                            candidates.push((estimate, next, distance, index, action));
+                        }
+                    }
+                    Transition::Success(outcome) => {
+                        return Beam::Solved(path_to(&parents, index, action), outcome);
+                    }
+                }
+            }
+        }
+
+        // Sorting is stable, so ties keep the order the moves were found in
+        candidates.sort_by(|a, b| a.0.cmp(&b.0));
+        layer = Vec::with_capacity(width);
+        for (_, state, distance, parent_index, action) in candidates {
+            if layer.len() == width {
+                break;
+            }
+            if seen.insert(state.clone()) {
+                parents.push((parent_index, action));
+                layer.push((state, distance, parents.len()));
+            }
+        }
+    }
+    Beam::Unknown
+}
+
+/// The most recent states a `CycleGuard` can remember per path.
+pub const MAX_RECENT: usize = 8;
+
+/// Settings for a beam search which forgets the states it has seen, only
+/// ruling out the last few states along each path.
+#[derive(Clone, Copy, Debug)]
+pub struct CycleGuard {
+    /// How many of the latest states on a path are ruled out, counting the
+    /// one being expanded. Two is enough to stop moves straight back to the
+    /// previous state. Clamped to `1..=MAX_RECENT`.
+    pub recent: usize,
+    /// The most layers to search. Without a set of every state seen, a path
+    /// can go around a longer cycle forever.
+    pub max_depth: usize,
+}
+
+/// The hashes of the latest states along a path, oldest first overwritten.
+#[derive(Clone, Copy, Default)]
+struct Recent {
+    hashes: [u64; MAX_RECENT],
+    len: usize,
+    next: usize,
+}
+
+impl Recent {
+    fn contains(&self, hash: u64) -> bool {
+        self.hashes[..self.len].contains(&hash)
+    }
+
+    fn push(mut self, hash: u64, capacity: usize) -> Self {
+        self.hashes[self.next] = hash;
+        self.next = (self.next + 1) % capacity;
+        self.len = usize::min(self.len + 1, capacity);
+        self
+    }
+}
+
+/// Searches like `solve_beam`, but instead of remembering every state seen it
+/// only rules out the states in `guard`'s window along each path. Memory then
+/// stays proportional to the width, which suits games whose transitions never
+/// fail and so lean on the closed set to stop going in circles. States are
+/// compared by hash, so a collision can rarely prune a state that hadn't
+/// been seen.
+pub fn solve_beam_guarded<S: State + Clone>(
+    initial_state: S,
+    data: &S::Data,
+    width: usize,
+    guard: CycleGuard,
+) -> Beam<S::Action, S::Outcome> {
+    if let Some(outcome) = initial_state.is_goal(data) {
+        return Beam::Solved(Vec::new(), outcome);
+    }
+
+    let width = width.max(1);
+    let capacity = guard.recent.clamp(1, MAX_RECENT);
+    // Each builder hashes differently, so one is shared by the whole search
+    let hasher = DefaultHashBuilder::default();
+    let mut parents = Vec::new();
+    let recent = Recent::default().push(hasher.hash_one(&initial_state), capacity);
+    let mut layer = vec![(initial_state, 0usize, 0, recent)];
+    for _ in 0..guard.max_depth {
+        if layer.is_empty() {
+            break;
+        }
+
+        let mut candidates = Vec::new();
+        for (state, distance, index, recent) in layer {
+            for (action, transition) in state.transitions(data) {
+                match transition {
+                    Transition::Indeterminate(next) => {
+                        let hash = hasher.hash_one(&next);
+                        if !recent.contains(hash) {
+                            let distance = distance.saturating_add(S::action_cost(&action));
+                            let estimate = estimate(&next, data, distance, Some(&action));
+                            let recent = recent.push(hash, capacity);
+                            candidates
+                                .push((estimate, hash, next, distance, index, recent, action));
+                        }
+                    }
+                    Transition::Success(outcome) => {
+                        return Beam::Solved(path_to(&parents, index, action), outcome);
+                    }
+                }
+            }
+        }
+
+        // Only states within the same layer are deduplicated
+        candidates.sort_by(|a, b| a.0.cmp(&b.0));
+        layer = Vec::with_capacity(width);
+        let mut kept = HashSet::new();
+        for (_, hash, state, distance, parent_index, recent, action) in candidates {
+            if layer.len() == width {
+                break;
+            }
+            if kept.insert(hash) {
+                parents.push((parent_index, action));
+                layer.push((state, distance, parents.len(), recent));
+            }
+        }
+    }
+    Beam::Unknown
+}
@@ -0,0 +1,101 @@
+use crate::{
+    limits::run_limited,
+    search::{Node, Search},
+    Cost, Frontier, LimitExceeded, Limits, SolveReport, StampedClosedSet, State,
+};
+use alloc::vec::Vec;
+use core::mem;
+
+/// Storage which searches can borrow instead of allocating their own, for
+/// solving many puzzles one after another like an editor or server does.
+/// Between searches the closed set is emptied by starting a new generation
+/// rather than clearing it, and everything else keeps its capacity.
+pub struct SearchBuffers<S: State> {
+    closed: StampedClosedSet<S>,
+    parents: Vec<(usize, S::Action)>,
+    queue: <S::Heuristic as Cost>::Queue<Node<S>>,
+}
+
+impl<S: State> Default for SearchBuffers<S> {
+    fn default() -> Self {
+        Self {
+            closed: StampedClosedSet::default(),
+            parents: Vec::new(),
+            queue: Default::default(),
+        }
+    }
+}
+
+impl<S: State> SearchBuffers<S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Empties the buffers for the next search.
+    fn reset(&mut self) {
+        self.closed.reset();
+        self.parents.clear();
+        // Searches can finish with states still queued
+        while self.queue.pop().is_some() {}
+    }
+}
+
+/// Finds the shortest solution like `solve_with_report`, using `buffers` for
+/// its storage so that solving again with them skips most allocations.
+pub fn solve_with_buffers<S: State>(
+    initial_state: S,
+    data: &S::Data,
+    buffers: &mut SearchBuffers<S>,
+) -> SolveReport<S::Action, S::Outcome> {
+    with_search(initial_state, data, buffers, |search| loop {
+        if let Some(report) = search.run(usize::MAX) {
+            break report;
+        }
+    })
+}
+
+/// Finds the shortest solution like `solve_limited`, using `buffers` for its
+/// storage. The buffers can be used again even after a search gives up.
+pub fn solve_limited_with_buffers<S: State>(
+    initial_state: S,
+    data: &S::Data,
+    limits: &Limits,
+    buffers: &mut SearchBuffers<S>,
+) -> Result<SolveReport<S::Action, S::Outcome>, LimitExceeded> {
+    with_search(initial_state, data, buffers, |search| {
+        run_limited(search, limits)
+    })
+}
+
+type Accept<S> = fn(&<S as State>::Outcome) -> bool;
+
+/// Starts a search on `buffers` and hands it to `run`, putting its storage
+/// back into `buffers` afterwards.
+fn with_search<S: State, R>(
+    initial_state: S,
+    data: &S::Data,
+    buffers: &mut SearchBuffers<S>,
+    run: impl FnOnce(&mut Search<'_, S, Accept<S>, StampedClosedSet<S>>) -> R,
+) -> R {
+    buffers.reset();
+    let SearchBuffers {
+        closed,
+        parents,
+        queue,
+    } = mem::take(buffers);
+    let mut search = Search::with_parts(
+        initial_state,
+        data,
+        (|_| true) as Accept<S>,
+        None,
+        (closed, parents, queue),
+    );
+    let result = run(&mut search);
+    let (closed, parents, queue) = search.into_parts();
+    *buffers = SearchBuffers {
+        closed,
+        parents,
+        queue,
+    };
+    result
+}
@@ -0,0 +1,156 @@
+use crate::{
+    search::{AcceptAll, Search},
+    SolveReport, State,
+};
+use alloc::{boxed::Box, sync::Arc};
+use core::{
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicBool, Ordering},
+    task::{Context, Poll},
+};
+
+/// How many states a `SolveFuture` expands before yielding to the executor,
+/// unless told otherwise.
+pub const EXPANSIONS_PER_POLL: usize = 1024;
+
+/// A flag shared between a search and whoever wants to stop it early.
+#[derive(Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks every search holding this token to stop the next time it checks.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A search running as a future. Each poll expands a batch of states and then
+/// yields, so it can be raced against a timeout and dropped at any point.
+/// Resolves to `None` if its token was cancelled before the search finished.
+pub struct SolveFuture<'a, S: State> {
+    search: Box<Search<'a, S, AcceptAll<S>>>,
+    token: CancelToken,
+    batch: usize,
+}
+
+impl<'a, S: State> SolveFuture<'a, S> {
+    /// Sets how many states are expanded between checks of the token. Smaller
+    /// batches respond to cancellation sooner at the cost of more polls.
+    pub fn expansions_per_poll(mut self, batch: usize) -> Self {
+        self.batch = batch.max(1);
+        self
+    }
+}
+
+impl<S: State> Future for SolveFuture<'_, S> {
+    type Output = Option<SolveReport<S::Action, S::Outcome>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if this.token.is_cancelled() {
+            return Poll::Ready(None);
+        }
+        match this.search.run(this.batch) {
+            Some(report) => Poll::Ready(Some(report)),
+            None => {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Finds the shortest solution like `solve_with_report`, but as a future which
+/// checks `token` between batches of expansions.
+pub fn solve_async<S: State>(
+    initial_state: S,
+    data: &S::Data,
+    token: CancelToken,
+) -> SolveFuture<'_, S> {
+    SolveFuture {
+        search: Box::new(Search::new(initial_state, data, |_| true, None)),
+        token,
+        batch: EXPANSIONS_PER_POLL,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Transition;
+    use std::task::Waker;
+
+    #[derive(Eq, Hash, PartialEq)]
+    struct Counter(i32);
+
+    impl State for Counter {
+        type Data = i32;
+        type Action = i32;
+        type Outcome = ();
+        type Transitions = Vec<(i32, Transition<Self>)>;
+        type Heuristic = usize;
+        const HAS_HEURISTIC: bool = false;
+
+        fn transitions(&self, goal: &i32) -> Self::Transitions {
+            [1, -1]
+                .iter()
+                .map(|&step| {
+                    let next = self.0 + step;
+                    let transition = if next == *goal {
+                        Transition::Success(())
+                    } else {
+                        Transition::Indeterminate(Counter(next))
+                    };
+                    (step, transition)
+                })
+                .collect()
+        }
+
+        fn heuristic(&self, _: &i32) -> usize {
+            0
+        }
+    }
+
+    fn poll_until_ready<F: Future + Unpin>(mut future: F) -> (F::Output, usize) {
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut polls = 1;
+        loop {
+            if let Poll::Ready(output) = Pin::new(&mut future).poll(&mut cx) {
+                return (output, polls);
+            }
+            polls += 1;
+        }
+    }
+
+    #[test]
+    fn solves_across_polls() {
+        let (report, polls) = poll_until_ready(
+            solve_async(Counter(0), &20, CancelToken::new()).expansions_per_poll(4),
+        );
+        assert_eq!(report.unwrap().solution, Some(vec![1; 20]));
+        assert!(polls > 1);
+    }
+
+    #[test]
+    fn cancelled_between_polls() {
+        let token = CancelToken::new();
+        let mut future = solve_async(Counter(0), &20, token.clone()).expansions_per_poll(4);
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(Pin::new(&mut future).poll(&mut cx).is_pending());
+
+        token.cancel();
+        assert!(matches!(
+            Pin::new(&mut future).poll(&mut cx),
+            Poll::Ready(None)
+        ));
+    }
+}
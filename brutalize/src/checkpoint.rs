@@ -0,0 +1,194 @@
+use crate::{limits::EXPANSIONS_PER_CHECK, search::Search, SolveReport, State};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// The bytes every checkpoint file starts with.
+const MAGIC: &[u8; 8] = b"BRUTCKP1";
+
+fn to_io(error: bincode::ErrorKind) -> io::Error {
+    match error {
+        bincode::ErrorKind::Io(error) => error,
+        error => io::Error::new(io::ErrorKind::InvalidData, error),
+    }
+}
+
+/// Saves a search to `path`. The checkpoint is written next to it first and
+/// then moved into place, so a crash partway through leaves the last one
+/// intact.
+fn save<S, F>(search: &mut Search<'_, S, F>, path: &Path) -> io::Result<()>
+where
+    S: State + Serialize + DeserializeOwned,
+    S::Action: Serialize + DeserializeOwned,
+    S::Outcome: Serialize + DeserializeOwned,
+    F: FnMut(&S::Outcome) -> bool,
+{
+    let mut partial = OsString::from(path.as_os_str());
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+
+    let file = File::create(&partial)?;
+    let mut writer = BufWriter::new(file);
+    writer.write_all(MAGIC)?;
+    search.save(&mut writer).map_err(|e| to_io(*e))?;
+    writer
+        .into_inner()
+        .map_err(|e| e.into_error())?
+        .sync_all()?;
+    fs::rename(&partial, path)
+}
+
+/// Runs a search to completion, saving it to `path` every `interval`. The
+/// checkpoint is removed once the search finishes.
+fn run<S, F>(
+    mut search: Search<'_, S, F>,
+    path: &Path,
+    interval: Duration,
+) -> io::Result<SolveReport<S::Action, S::Outcome>>
+where
+    S: State + Serialize + DeserializeOwned,
+    S::Action: Serialize + DeserializeOwned,
+    S::Outcome: Serialize + DeserializeOwned,
+    F: FnMut(&S::Outcome) -> bool,
+{
+    let mut last_saved = Instant::now();
+    loop {
+        if let Some(report) = search.run(EXPANSIONS_PER_CHECK) {
+            match fs::remove_file(path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => return Ok(report),
+            }
+        }
+        if last_saved.elapsed() >= interval {
+            save(&mut search, path)?;
+            last_saved = Instant::now();
+        }
+    }
+}
+
+/// Finds the shortest solution like `solve_with_report`, saving the search to
+/// `path` every `interval`. If it's interrupted, `resume_from` carries on
+/// from the last checkpoint instead of starting over.
+///
+/// Checkpoints hold every state the search has seen, so they can take about
+/// as much space on disk as the search does in memory.
+pub fn solve_checkpointed<S>(
+    initial_state: S,
+    data: &S::Data,
+    path: impl AsRef<Path>,
+    interval: Duration,
+) -> io::Result<SolveReport<S::Action, S::Outcome>>
+where
+    S: State + Serialize + DeserializeOwned,
+    S::Action: Serialize + DeserializeOwned,
+    S::Outcome: Serialize + DeserializeOwned,
+{
+    let search = Search::new(initial_state, data, |_| true, None);
+    run(search, path.as_ref(), interval)
+}
+
+/// Carries on with a search saved by `solve_checkpointed`, which must have
+/// been given the same `data`. The search keeps saving to `path` every
+/// `interval` until it finishes.
+pub fn resume_from<S>(
+    path: impl AsRef<Path>,
+    data: &S::Data,
+    interval: Duration,
+) -> io::Result<SolveReport<S::Action, S::Outcome>>
+where
+    S: State + Serialize + DeserializeOwned,
+    S::Action: Serialize + DeserializeOwned,
+    S::Outcome: Serialize + DeserializeOwned,
+{
+    let path = path.as_ref();
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut magic = [0; MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is not a checkpoint", path.display()),
+        ));
+    }
+    let search = Search::<S, _>::load(reader, data, |_| true, None).map_err(|e| to_io(*e))?;
+    run(search, path, interval)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{solve_with_report, Transition};
+    use serde::Deserialize;
+    use std::{env, process};
+
+    #[derive(Deserialize, Eq, Hash, PartialEq, Serialize)]
+    struct Line(i32);
+
+    impl State for Line {
+        type Data = i32;
+        type Action = i32;
+        type Outcome = ();
+        type Transitions = Vec<(i32, Transition<Self>)>;
+        type Heuristic = usize;
+
+        fn transitions(&self, target: &i32) -> Self::Transitions {
+            [1, -1, 3]
+                .iter()
+                .map(|&step| match self.0 + step {
+                    next if next == *target => (step, Transition::Success(())),
+                    next => (step, Transition::Indeterminate(Line(next))),
+                })
+                .collect()
+        }
+
+        fn heuristic(&self, target: &i32) -> usize {
+            (target - self.0).unsigned_abs().div_ceil(3) as usize
+        }
+    }
+
+    fn checkpoint_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("brutalize-{}-{}", name, process::id()))
+    }
+
+    #[test]
+    fn resume_after_interrupting() {
+        let path = checkpoint_path("resume");
+        let mut search = Search::new(Line(0), &100, |_| true, None);
+        assert!(search.run(10).is_none());
+        save(&mut search, &path).unwrap();
+        let fresh = solve_with_report(Line(0), &100);
+        // Saving leaves the search as it was
+        let finished = search.finish();
+        assert_eq!(finished.solution, fresh.solution);
+        assert_eq!(finished.expanded, fresh.expanded);
+
+        let resumed = resume_from::<Line>(&path, &100, Duration::ZERO).unwrap();
+        assert_eq!(resumed.solution, fresh.solution);
+        assert_eq!(resumed.expanded, fresh.expanded);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn checkpoint_every_run() {
+        let path = checkpoint_path("every");
+        let report = solve_checkpointed(Line(0), &5000, &path, Duration::ZERO).unwrap();
+        let fresh = solve_with_report(Line(0), &5000);
+        assert!(fresh.expanded > EXPANSIONS_PER_CHECK);
+        assert_eq!(report.solution, fresh.solution);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn reject_other_files() {
+        let path = checkpoint_path("reject");
+        fs::write(&path, b"not a checkpoint").unwrap();
+        let result = resume_from::<Line>(&path, &100, Duration::ZERO);
+        assert!(matches!(result, Err(e) if e.kind() == io::ErrorKind::InvalidData));
+        fs::remove_file(&path).unwrap();
+    }
+}
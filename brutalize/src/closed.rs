@@ -0,0 +1,311 @@
+use crate::State;
+use alloc::vec::Vec;
+use core::{hash::BuildHasher, mem};
+use hashbrown::{hash_map, DefaultHashBuilder, HashMap, HashSet};
+#[cfg(feature = "checkpoint")]
+use serde::{Deserialize, Serialize};
+
+/// The set of states which have already been expanded.
+pub trait ClosedSet<S: State> {
+    /// Marks a state as expanded, calling `expand` with it if it hadn't been
+    /// expanded before.
+    fn visit<R>(&mut self, state: S, expand: impl FnOnce(&S) -> R) -> Option<R>;
+
+    /// Estimates the bytes of memory used by the set.
+    fn memory(&self) -> usize;
+}
+
+/// A closed set kept in memory. States which can be packed are stored by their
+/// packed key, and all others are stored in full. Since whether a state packs
+/// depends only on the state itself, equal states always end up in the same
+/// set.
+#[cfg_attr(feature = "checkpoint", derive(Deserialize, Serialize))]
+#[cfg_attr(
+    feature = "checkpoint",
+    serde(bound(
+        serialize = "S: Serialize + Eq + core::hash::Hash",
+        deserialize = "S: Deserialize<'de> + Eq + core::hash::Hash"
+    ))
+)]
+pub struct HashClosedSet<S> {
+    states: HashMap<S, ()>,
+    packed: HashSet<u128>,
+}
+
+impl<S> Default for HashClosedSet<S> {
+    fn default() -> Self {
+        Self {
+            states: HashMap::new(),
+            packed: HashSet::new(),
+        }
+    }
+}
+
+impl<S: State> ClosedSet<S> for HashClosedSet<S> {
+    #[inline]
+    fn visit<R>(&mut self, state: S, expand: impl FnOnce(&S) -> R) -> Option<R> {
+        if let Some(key) = state.pack() {
+            if self.packed.insert(key) {
+                Some(expand(&state))
+            } else {
+                None
+            }
+        } else if let hash_map::Entry::Vacant(vacant) = self.states.entry(state) {
+            let result = expand(vacant.key());
+            vacant.insert(());
+            Some(result)
+        } else {
+            None
+        }
+    }
+
+    /// Estimates the bytes used by the set from its capacity, counting one
+    /// control byte per slot on top of the entries themselves.
+    fn memory(&self) -> usize {
+        self.states.capacity() * (mem::size_of::<S>() + 1)
+            + self.packed.capacity() * (mem::size_of::<u128>() + 1)
+    }
+}
+
+/// The number of shards a `ShardedClosedSet` uses by default.
+pub const DEFAULT_SHARDS: usize = 16;
+
+/// A closed set split into several smaller in-memory sets, picking the shard
+/// for each state from the top bits of its hash. Each shard stays small enough
+/// to grow without rehashing the whole set at once, and shards can later be
+/// handed out to separate threads.
+pub struct ShardedClosedSet<S> {
+    shards: Vec<HashClosedSet<S>>,
+    /// How far to shift a hash right to leave just the shard index.
+    shift: u32,
+    /// Hashes states which can't be packed. Each builder hashes differently,
+    /// so the set keeps its own.
+    hasher: DefaultHashBuilder,
+}
+
+impl<S> ShardedClosedSet<S> {
+    /// Creates a set with `shards` shards, rounded up to a power of two.
+    pub fn new(shards: usize) -> Self {
+        let shards = shards.max(1).next_power_of_two();
+        Self {
+            shards: (0..shards).map(|_| HashClosedSet::default()).collect(),
+            shift: 64 - shards.trailing_zeros(),
+            hasher: DefaultHashBuilder::default(),
+        }
+    }
+
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+}
+
+impl<S> Default for ShardedClosedSet<S> {
+    fn default() -> Self {
+        Self::new(DEFAULT_SHARDS)
+    }
+}
+
+impl<S: State> ShardedClosedSet<S> {
+    /// Packed keys are mixed with a multiply instead of being fully hashed,
+    /// since the shard's own set hashes them again anyway.
+    fn shard(&self, state: &S) -> usize {
+        let hash = match state.pack() {
+            Some(key) => ((key >> 64) as u64 ^ key as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15),
+            None => self.hasher.hash_one(state),
+        };
+        hash.checked_shr(self.shift).unwrap_or(0) as usize
+    }
+}
+
+impl<S: State> ClosedSet<S> for ShardedClosedSet<S> {
+    #[inline]
+    fn visit<R>(&mut self, state: S, expand: impl FnOnce(&S) -> R) -> Option<R> {
+        let shard = self.shard(&state);
+        self.shards[shard].visit(state, expand)
+    }
+
+    fn memory(&self) -> usize {
+        self.shards.iter().map(ClosedSet::<S>::memory).sum()
+    }
+}
+
+/// How many times over the busiest search's states a `StampedClosedSet` may
+/// hold before stale entries are cleared out.
+const MAX_STALE_FACTOR: usize = 4;
+
+/// An in-memory closed set which can be emptied in constant time, for running
+/// many searches one after another. Each state is stamped with the
+/// generation it was visited in, and `reset` starts a new generation so that
+/// older stamps no longer count. Stale entries are only cleared out when the
+/// generation wraps around or they pile up past a few times the most states
+/// any one search has visited.
+pub struct StampedClosedSet<S> {
+    states: HashMap<S, u32>,
+    packed: HashMap<u128, u32>,
+    generation: u32,
+    /// How many states have been visited in the current generation.
+    visited: usize,
+    /// The most states visited in any one generation.
+    peak: usize,
+}
+
+impl<S> Default for StampedClosedSet<S> {
+    fn default() -> Self {
+        Self {
+            states: HashMap::new(),
+            packed: HashMap::new(),
+            generation: 0,
+            visited: 0,
+            peak: 0,
+        }
+    }
+}
+
+impl<S> StampedClosedSet<S> {
+    /// Forgets every visited state while keeping the memory allocated for
+    /// them.
+    pub fn reset(&mut self) {
+        self.peak = self.peak.max(self.visited);
+        self.visited = 0;
+        self.generation = self.generation.wrapping_add(1);
+        let len = self.states.len() + self.packed.len();
+        if self.generation == 0 || len > self.peak.saturating_mul(MAX_STALE_FACTOR) {
+            self.states.clear();
+            self.packed.clear();
+        }
+    }
+}
+
+impl<S: State> ClosedSet<S> for StampedClosedSet<S> {
+    #[inline]
+    fn visit<R>(&mut self, state: S, expand: impl FnOnce(&S) -> R) -> Option<R> {
+        let generation = self.generation;
+        let result = if let Some(key) = state.pack() {
+            match self.packed.entry(key) {
+                hash_map::Entry::Occupied(mut stamped) => {
+                    if *stamped.get() == generation {
+                        return None;
+                    }
+                    stamped.insert(generation);
+                }
+                hash_map::Entry::Vacant(vacant) => {
+                    vacant.insert(generation);
+                }
+            }
+            expand(&state)
+        } else {
+            match self.states.entry(state) {
+                hash_map::Entry::Occupied(mut stamped) => {
+                    if *stamped.get() == generation {
+                        return None;
+                    }
+                    stamped.insert(generation);
+                    expand(stamped.key())
+                }
+                hash_map::Entry::Vacant(vacant) => {
+                    let result = expand(vacant.key());
+                    vacant.insert(generation);
+                    result
+                }
+            }
+        };
+        self.visited += 1;
+        Some(result)
+    }
+
+    fn memory(&self) -> usize {
+        self.states.capacity() * (mem::size_of::<(S, u32)>() + 1)
+            + self.packed.capacity() * (mem::size_of::<(u128, u32)>() + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{solve_with_closed, solve_with_report, Transition};
+
+    #[derive(Eq, Hash, PartialEq)]
+    struct Line(i32);
+
+    impl State for Line {
+        type Data = i32;
+        type Action = i32;
+        type Outcome = ();
+        type Transitions = Vec<(i32, Transition<Self>)>;
+        type Heuristic = usize;
+
+        fn transitions(&self, target: &i32) -> Self::Transitions {
+            [1, -1, 3]
+                .iter()
+                .map(|&step| match self.0 + step {
+                    next if next == *target => (step, Transition::Success(())),
+                    next => (step, Transition::Indeterminate(Line(next))),
+                })
+                .collect()
+        }
+
+        fn heuristic(&self, target: &i32) -> usize {
+            ((target - self.0).unsigned_abs() as usize).div_ceil(3)
+        }
+    }
+
+    #[test]
+    fn visit_sharded() {
+        let mut closed = ShardedClosedSet::new(5);
+        assert_eq!(closed.shards(), 8);
+        for i in 0..1000 {
+            assert_eq!(closed.visit(Line(i), |_| ()), Some(()));
+        }
+        for i in 0..1000 {
+            assert_eq!(closed.visit(Line(i), |_| ()), None);
+        }
+        assert!(closed.shards.iter().all(|s| !s.states.is_empty()));
+
+        // A single shard covers every hash
+        let mut closed = ShardedClosedSet::new(1);
+        assert_eq!(closed.visit(Line(-1), |_| ()), Some(()));
+        assert_eq!(closed.visit(Line(-1), |_| ()), None);
+    }
+
+    #[test]
+    fn solve_sharded() {
+        let sharded = solve_with_closed(Line(0), &100, ShardedClosedSet::default());
+        let monolithic = solve_with_report(Line(0), &100);
+        assert_eq!(sharded.solution, monolithic.solution);
+        assert_eq!(sharded.expanded, monolithic.expanded);
+    }
+
+    #[test]
+    fn visit_stamped() {
+        let mut closed = StampedClosedSet::default();
+        for _ in 0..3 {
+            for i in 0..100 {
+                assert_eq!(closed.visit(Line(i), |_| ()), Some(()));
+                assert_eq!(closed.visit(Line(i), |_| ()), None);
+            }
+            closed.reset();
+            // Stale states are kept around for the next generation
+            assert_eq!(closed.states.len(), 100);
+        }
+
+        // Searches over different states leave stale entries behind until
+        // they outnumber the busiest search a few times over
+        let mut closed = StampedClosedSet::default();
+        for generation in 0..5 {
+            for i in 0..10 {
+                closed.visit(Line(generation * 10 + i), |_| ());
+            }
+            closed.reset();
+        }
+        assert!(closed.states.is_empty());
+        for i in 0..10 {
+            closed.visit(Line(i), |_| ());
+        }
+        closed.reset();
+        assert_eq!(closed.states.len(), 10);
+
+        closed.generation = u32::MAX;
+        closed.reset();
+        assert!(closed.states.is_empty());
+    }
+}
@@ -0,0 +1,56 @@
+use crate::{solve_with_report, SolveReport, State, Transition};
+use alloc::vec::Vec;
+
+/// A state searched without its game's heuristic.
+#[derive(Eq, Hash, PartialEq)]
+struct Blind<S>(S);
+
+impl<S: State> State for Blind<S> {
+    type Data = S::Data;
+    type Action = S::Action;
+    type Outcome = S::Outcome;
+    type Transitions = Vec<(S::Action, Transition<Self>)>;
+    type Heuristic = usize;
+
+    const HAS_HEURISTIC: bool = false;
+
+    fn transitions(&self, data: &Self::Data) -> Self::Transitions {
+        self.0
+            .transitions(data)
+            .into_iter()
+            .map(|(action, transition)| match transition {
+                Transition::Indeterminate(state) => {
+                    (action, Transition::Indeterminate(Blind(state)))
+                }
+                Transition::Success(outcome) => (action, Transition::Success(outcome)),
+            })
+            .collect()
+    }
+
+    fn action_cost(action: &Self::Action) -> usize {
+        S::action_cost(action)
+    }
+
+    fn commutes(first: &Self::Action, second: &Self::Action) -> bool {
+        S::commutes(first, second)
+    }
+
+    fn is_goal(&self, data: &Self::Data) -> Option<Self::Outcome> {
+        self.0.is_goal(data)
+    }
+
+    fn pack(&self) -> Option<u128> {
+        self.0.pack()
+    }
+}
+
+/// Finds the shortest solution while ignoring the game's heuristic, which
+/// makes the search Dijkstra's algorithm. It expands many more states, but
+/// stays optimal even when the heuristic overestimates, so it's useful for
+/// checking heuristics.
+pub fn solve_dijkstra<S: State>(
+    initial_state: S,
+    data: &S::Data,
+) -> SolveReport<S::Action, S::Outcome> {
+    solve_with_report(Blind(initial_state), data)
+}
@@ -0,0 +1,265 @@
+use crate::{ClosedSet, HashClosedSet, State};
+use memmap2::MmapMut;
+use std::{
+    convert::TryInto,
+    fs::{self, File, OpenOptions},
+    io, mem,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// How many slots the table starts with. It's kept a power of two.
+const INITIAL_SLOTS: usize = 1 << 16;
+/// The bytes taken by each slot, which holds one packed key.
+const SLOT_SIZE: usize = mem::size_of::<u128>();
+
+/// Numbers the files of sets created by this process so they don't collide.
+static NEXT_FILE: AtomicUsize = AtomicUsize::new(0);
+
+/// An open addressing hash table of packed keys in a memory-mapped file.
+struct Table {
+    path: PathBuf,
+    map: MmapMut,
+    slots: usize,
+}
+
+impl Table {
+    fn create(dir: &Path, slots: usize) -> io::Result<Self> {
+        let path = dir.join(format!(
+            "brutalize-closed-{}-{}",
+            process::id(),
+            NEXT_FILE.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        // A new file reads as zeroes, so every slot starts out empty
+        let map = file
+            .set_len((slots * SLOT_SIZE) as u64)
+            .and_then(|()| Self::map(&file));
+        match map {
+            Ok(map) => Ok(Self { path, map, slots }),
+            Err(e) => {
+                let _ = fs::remove_file(&path);
+                Err(e)
+            }
+        }
+    }
+
+    fn map(file: &File) -> io::Result<MmapMut> {
+        // SAFETY: the file was just created with a name unique to this set,
+        // so nothing else should be modifying it while it's mapped.
+        unsafe { MmapMut::map_mut(file) }
+    }
+
+    #[inline]
+    fn key(&self, slot: usize) -> u128 {
+        let bytes = &self.map[slot * SLOT_SIZE..(slot + 1) * SLOT_SIZE];
+        u128::from_le_bytes(bytes.try_into().unwrap())
+    }
+
+    /// Inserts a key other than zero, returning whether it was new.
+    #[inline]
+    fn insert(&mut self, key: u128) -> bool {
+        // Packed keys are often small or patterned, so they're mixed before
+        // picking a slot
+        let folded = (key as u64) ^ ((key >> 64) as u64);
+        let mut slot =
+            (folded.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 32) as usize & (self.slots - 1);
+        loop {
+            match self.key(slot) {
+                0 => {
+                    self.map[slot * SLOT_SIZE..(slot + 1) * SLOT_SIZE]
+                        .copy_from_slice(&key.to_le_bytes());
+                    return true;
+                }
+                existing if existing == key => return false,
+                _ => slot = (slot + 1) & (self.slots - 1),
+            }
+        }
+    }
+}
+
+impl Drop for Table {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// A closed set which keeps packed states in a hash table in a memory-mapped
+/// file, for searches too big to fit their closed set in memory. As the table
+/// outgrows memory the operating system pages it out, so the search slows
+/// down instead of running out of memory. States which don't pack are kept in
+/// memory.
+///
+/// The file is created in the directory given to `new` and removed when the
+/// set is dropped. Visiting panics if the file can't be grown, such as when
+/// the disk is full.
+pub struct DiskClosedSet<S> {
+    dir: PathBuf,
+    table: Table,
+    len: usize,
+    // Empty slots hold zero, so a key of zero is tracked on its own
+    has_zero: bool,
+    unpacked: HashClosedSet<S>,
+}
+
+impl<S> DiskClosedSet<S> {
+    /// Creates an empty set backed by a new file in `dir`.
+    pub fn new(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        let table = Table::create(&dir, INITIAL_SLOTS)?;
+        Ok(Self {
+            dir,
+            table,
+            len: 0,
+            has_zero: false,
+            unpacked: HashClosedSet::default(),
+        })
+    }
+
+    /// The bytes taken on disk by the table of packed keys.
+    pub fn disk_usage(&self) -> usize {
+        self.table.slots * SLOT_SIZE
+    }
+
+    /// Inserts a packed key, returning whether it was new.
+    fn insert(&mut self, key: u128) -> io::Result<bool> {
+        if key == 0 {
+            return Ok(!mem::replace(&mut self.has_zero, true));
+        }
+
+        // Doubling at half full keeps probe sequences short
+        if 2 * (self.len + 1) > self.table.slots {
+            let mut table = Table::create(&self.dir, 2 * self.table.slots)?;
+            for slot in 0..self.table.slots {
+                let key = self.table.key(slot);
+                if key != 0 {
+                    table.insert(key);
+                }
+            }
+            self.table = table;
+        }
+
+        let inserted = self.table.insert(key);
+        if inserted {
+            self.len += 1;
+        }
+        Ok(inserted)
+    }
+}
+
+impl<S: State> ClosedSet<S> for DiskClosedSet<S> {
+    fn visit<R>(&mut self, state: S, expand: impl FnOnce(&S) -> R) -> Option<R> {
+        match state.pack() {
+            Some(key) => {
+                let inserted = self
+                    .insert(key)
+                    .unwrap_or_else(|e| panic!("failed to grow the closed set on disk: {}", e));
+                inserted.then(|| expand(&state))
+            }
+            None => self.unpacked.visit(state, expand),
+        }
+    }
+
+    /// Only counts the states kept in memory, since the table on disk is paged
+    /// in and out as needed. See `disk_usage` for the size of the table.
+    fn memory(&self) -> usize {
+        ClosedSet::<S>::memory(&self.unpacked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{solve_with_closed, solve_with_report, Transition};
+    use std::env;
+
+    #[derive(Eq, Hash, PartialEq)]
+    struct Key(u128);
+
+    impl State for Key {
+        type Data = ();
+        type Action = ();
+        type Outcome = ();
+        type Transitions = Vec<((), Transition<Self>)>;
+        type Heuristic = usize;
+
+        fn transitions(&self, _: &()) -> Self::Transitions {
+            Vec::new()
+        }
+
+        fn pack(&self) -> Option<u128> {
+            (self.0 != u128::MAX).then_some(self.0)
+        }
+    }
+
+    #[test]
+    fn visit_on_disk() {
+        let mut closed = DiskClosedSet::new(env::temp_dir()).unwrap();
+        let path = closed.table.path.clone();
+        assert!(path.exists());
+
+        // Enough keys to grow the table a few times, each visited twice
+        let keys = (0..200_000u128).map(|i| (i * i) << 64 | i);
+        for key in keys.clone() {
+            assert_eq!(closed.visit(Key(key), |_| ()), Some(()), "{}", key);
+        }
+        for key in keys {
+            assert_eq!(closed.visit(Key(key), |_| ()), None, "{}", key);
+        }
+        assert_eq!(closed.len, 199_999);
+        assert!(closed.disk_usage() >= 2 * 200_000 * SLOT_SIZE);
+        assert!(!path.exists());
+
+        // States which don't pack are kept in memory
+        assert_eq!(closed.visit(Key(u128::MAX), |_| ()), Some(()));
+        assert_eq!(closed.visit(Key(u128::MAX), |_| ()), None);
+
+        let path = closed.table.path.clone();
+        drop(closed);
+        assert!(!path.exists());
+    }
+
+    #[derive(Eq, Hash, PartialEq)]
+    struct Line(i32);
+
+    impl State for Line {
+        type Data = i32;
+        type Action = i32;
+        type Outcome = ();
+        type Transitions = Vec<(i32, Transition<Self>)>;
+        type Heuristic = usize;
+
+        fn transitions(&self, target: &i32) -> Self::Transitions {
+            [1, -1, 3]
+                .iter()
+                .map(|&step| match self.0 + step {
+                    next if next == *target => (step, Transition::Success(())),
+                    next => (step, Transition::Indeterminate(Line(next))),
+                })
+                .collect()
+        }
+
+        fn heuristic(&self, target: &i32) -> usize {
+            ((target - self.0).unsigned_abs() as usize).div_ceil(3)
+        }
+
+        fn pack(&self) -> Option<u128> {
+            Some(self.0 as u32 as u128)
+        }
+    }
+
+    #[test]
+    fn solve_on_disk() {
+        let closed = DiskClosedSet::new(env::temp_dir()).unwrap();
+        let on_disk = solve_with_closed(Line(0), &100, closed);
+        let in_memory = solve_with_report(Line(0), &100);
+        assert_eq!(on_disk.solution, in_memory.solution);
+        assert_eq!(on_disk.expanded, in_memory.expanded);
+        assert_eq!(on_disk.memory.closed, 0);
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{State, Transition};
+use alloc::{collections::BTreeMap, vec, vec::Vec};
+use hashbrown::HashSet;
+
+/// The state farthest from some starting state.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Eccentricity<S> {
+    pub state: S,
+    /// The cost of the cheapest path to `state`.
+    pub distance: usize,
+    /// How many states can be reached at all, including the starting state.
+    pub reachable: usize,
+}
+
+/// Finds every state reachable from `initial_state` in order of distance,
+/// or `None` if there are more than `max_states` of them. Successful
+/// transitions end a path like they end a search, so only unsolved states
+/// are reached.
+fn reachable<S: State + Clone>(
+    initial_state: S,
+    data: &S::Data,
+    max_states: usize,
+) -> Option<Vec<(S, usize)>> {
+    let mut seen = HashSet::new();
+    let mut result = Vec::new();
+    let mut layers = BTreeMap::new();
+    layers.insert(0, vec![initial_state]);
+    while let Some((distance, layer)) = layers.pop_first() {
+        for state in layer {
+            if seen.contains(&state) {
+                continue;
+            }
+            if seen.len() == max_states {
+                return None;
+            }
+
+            for (action, transition) in state.transitions(data) {
+                if let Transition::Indeterminate(next) = transition {
+                    if !seen.contains(&next) {
+                        layers
+                            .entry(distance + S::action_cost(&action))
+                            .or_insert_with(Vec::new)
+                            .push(next);
+                    }
+                }
+            }
+            seen.insert(state.clone());
+            result.push((state, distance));
+        }
+    }
+    Some(result)
+}
+
+/// Finds the state farthest from `initial_state` by cheapest path, which
+/// makes a good starting point when looking for the hardest configuration of
+/// a puzzle. Returns `None` if more than `max_states` states are reachable.
+pub fn eccentricity<S: State + Clone>(
+    initial_state: S,
+    data: &S::Data,
+    max_states: usize,
+) -> Option<Eccentricity<S>> {
+    let states = reachable(initial_state, data, max_states)?;
+    let reachable = states.len();
+    // States come out in order of distance, so the last is the farthest
+    let (state, distance) = states.into_iter().last()?;
+    Some(Eccentricity {
+        state,
+        distance,
+        reachable,
+    })
+}
+
+/// Finds the greatest distance between any two states reachable from
+/// `initial_state`, where the distance from one state to another is the cost
+/// of the cheapest path between them. This searches from every reachable
+/// state, so it's only practical for small puzzles. Returns `None` if more
+/// than `max_states` states are reachable.
+pub fn diameter<S: State + Clone>(
+    initial_state: S,
+    data: &S::Data,
+    max_states: usize,
+) -> Option<usize> {
+    let states = reachable(initial_state, data, max_states)?;
+    let mut result = 0;
+    for (state, _) in states {
+        // Everything reachable from a reachable state was already counted
+        let farthest = eccentricity(state, data, max_states)?;
+        result = usize::max(result, farthest.distance);
+    }
+    Some(result)
+}
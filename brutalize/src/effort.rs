@@ -0,0 +1,165 @@
+#[cfg(feature = "std")]
+use crate::{limits::EXPANSIONS_PER_CHECK, search::Search};
+use crate::{Rng, State, Transition};
+use alloc::{vec, vec::Vec};
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+/// A rough idea of how much work solving a puzzle will take, sampled from
+/// random walks through its states.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Effort {
+    /// The average number of new states reachable from each state visited.
+    pub branching_factor: f64,
+    /// The average number of moves made before a walk hit a dead end, found a
+    /// goal or reached the depth limit.
+    pub depth: f64,
+    /// The fewest moves after which any walk found a goal.
+    pub goal_depth: Option<usize>,
+    /// The projected number of states in the search tree, down to
+    /// `goal_depth` if a goal was found or the depth limit otherwise. States
+    /// reached along several paths are counted once per path, so this tends
+    /// to overestimate what a search would expand.
+    pub projected: f64,
+}
+
+/// Estimates the effort of solving a puzzle without solving it, by taking
+/// `walks` random walks of at most `max_depth` moves from `initial_state`.
+/// Walks never step back onto a state they already visited.
+///
+/// The projection uses Knuth's estimator: along each walk, the product of the
+/// branching factors seen so far estimates the width of the tree at that
+/// depth, and summing those widths estimates its size.
+pub fn estimate_effort<S: State + Clone>(
+    initial_state: S,
+    data: &S::Data,
+    walks: usize,
+    max_depth: usize,
+    seed: u64,
+) -> Effort {
+    let mut rng = Rng::new(seed);
+    let mut goal_depth = None;
+    let mut expanded = 0;
+    let mut moves = 0;
+    let mut branchings = Vec::with_capacity(walks);
+    for _ in 0..walks {
+        let mut path = vec![initial_state.clone()];
+        let mut branching = Vec::new();
+        while branching.len() < max_depth {
+            let mut children = Vec::new();
+            let mut solved = false;
+            for (_, transition) in path.last().unwrap().transitions(data) {
+                match transition {
+                    Transition::Indeterminate(next) => {
+                        if !path.contains(&next) {
+                            children.push(next);
+                        }
+                    }
+                    Transition::Success(_) => solved = true,
+                }
+            }
+            expanded += 1;
+            moves += children.len();
+            branching.push(children.len());
+            if solved {
+                let depth = branching.len();
+                goal_depth = Some(goal_depth.map_or(depth, |d: usize| d.min(depth)));
+                break;
+            }
+            if children.is_empty() {
+                break;
+            }
+            let index = rng.below(children.len());
+            path.push(children.swap_remove(index));
+        }
+        branchings.push(branching);
+    }
+
+    let limit = goal_depth.unwrap_or(max_depth);
+    let projected = branchings
+        .iter()
+        .map(|branching| {
+            let mut width = 1.0;
+            let mut total = 1.0;
+            for &b in branching.iter().take(limit) {
+                width *= b as f64;
+                total += width;
+            }
+            total
+        })
+        .sum::<f64>()
+        / walks.max(1) as f64;
+
+    Effort {
+        branching_factor: moves as f64 / expanded.max(1) as f64,
+        depth: branchings.iter().map(Vec::len).sum::<usize>() as f64 / walks.max(1) as f64,
+        goal_depth,
+        projected,
+    }
+}
+
+/// What a full search of a puzzle is projected to cost.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchPlan {
+    /// The number of states the search is projected to expand.
+    pub states: f64,
+    /// The bytes of memory used for each state expanded.
+    pub bytes_per_state: f64,
+    /// The time taken to expand each state.
+    pub time_per_state: Duration,
+    /// Whether the sample finished the search, in which case the plan is what
+    /// it actually took rather than a projection.
+    pub exact: bool,
+}
+
+impl SearchPlan {
+    /// The bytes of memory the search is projected to use.
+    pub fn memory(&self) -> f64 {
+        self.states * self.bytes_per_state
+    }
+
+    /// How long the search is projected to take, saturating at
+    /// `Duration::MAX`.
+    pub fn time(&self) -> Duration {
+        let seconds = self.states * self.time_per_state.as_secs_f64();
+        Duration::try_from_secs_f64(seconds).unwrap_or(Duration::MAX)
+    }
+}
+
+/// Projects what solving a puzzle will cost by running the search for up to
+/// `sample`. The bytes and time taken by each state while sampling are scaled
+/// up to the number of states projected by `effort`, so the plan inherits
+/// its tendency to overestimate.
+#[cfg(feature = "std")]
+pub fn plan_search<S: State>(
+    initial_state: S,
+    data: &S::Data,
+    effort: &Effort,
+    sample: Duration,
+) -> SearchPlan {
+    let start = Instant::now();
+    let mut search = Search::new(initial_state, data, |_| true, None);
+    let (expanded, memory, exact) = loop {
+        if let Some(report) = search.run(EXPANSIONS_PER_CHECK) {
+            break (report.expanded, report.memory, true);
+        }
+        if start.elapsed() >= sample {
+            break (search.expanded(), search.memory(), false);
+        }
+    };
+
+    let per_state = expanded.max(1) as f64;
+    SearchPlan {
+        // The search can get further than the walks projected, in which case
+        // it has at least that far to go
+        states: if exact {
+            expanded as f64
+        } else {
+            effort.projected.max(expanded as f64)
+        },
+        bytes_per_state: memory.total() as f64 / per_state,
+        time_per_state: start.elapsed().div_f64(per_state),
+        exact,
+    }
+}
@@ -0,0 +1,128 @@
+use alloc::{collections::BinaryHeap, vec::Vec};
+use core::cmp::{Ord, Ordering, PartialOrd};
+
+/// The open list of a search, which pops items in order of increasing cost.
+pub trait Frontier<C, T>: Default {
+    fn push(&mut self, cost: C, item: T);
+    fn pop(&mut self) -> Option<T>;
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+struct HeapEntry<C, T> {
+    cost: C,
+    item: T,
+}
+
+impl<C: Ord, T> PartialEq for HeapEntry<C, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl<C: Ord, T> Eq for HeapEntry<C, T> {}
+
+impl<C: Ord, T> PartialOrd for HeapEntry<C, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<C: Ord, T> Ord for HeapEntry<C, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.cmp(&self.cost)
+    }
+}
+
+/// A frontier backed by a binary heap, which works for any ordered cost.
+pub struct HeapFrontier<C, T> {
+    heap: BinaryHeap<HeapEntry<C, T>>,
+}
+
+impl<C: Ord, T> Default for HeapFrontier<C, T> {
+    fn default() -> Self {
+        Self {
+            heap: BinaryHeap::new(),
+        }
+    }
+}
+
+impl<C: Ord, T> Frontier<C, T> for HeapFrontier<C, T> {
+    #[inline]
+    fn push(&mut self, cost: C, item: T) {
+        self.heap.push(HeapEntry { cost, item });
+    }
+
+    #[inline]
+    fn pop(&mut self) -> Option<T> {
+        self.heap.pop().map(|e| e.item)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.heap.len()
+    }
+}
+
+/// A frontier with one bucket per cost, which pushes and pops in constant time
+/// when costs are small integers. Costs too large to get their own bucket
+/// spill over into a heap.
+pub struct BucketFrontier<T> {
+    buckets: Vec<Vec<T>>,
+    min: usize,
+    len: usize,
+    overflow: HeapFrontier<usize, T>,
+}
+
+impl<T> BucketFrontier<T> {
+    const MAX_BUCKETS: usize = 1 << 16;
+}
+
+impl<T> Default for BucketFrontier<T> {
+    fn default() -> Self {
+        Self {
+            buckets: Vec::new(),
+            min: 0,
+            len: 0,
+            overflow: HeapFrontier::default(),
+        }
+    }
+}
+
+impl<T> Frontier<usize, T> for BucketFrontier<T> {
+    #[inline]
+    fn push(&mut self, cost: usize, item: T) {
+        if cost >= Self::MAX_BUCKETS {
+            self.overflow.push(cost, item);
+            return;
+        }
+
+        if cost >= self.buckets.len() {
+            self.buckets.resize_with(cost + 1, Vec::new);
+        }
+        self.buckets[cost].push(item);
+        self.min = usize::min(self.min, cost);
+        self.len += 1;
+    }
+
+    #[inline]
+    fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return self.overflow.pop();
+        }
+
+        while self.buckets[self.min].is_empty() {
+            self.min += 1;
+        }
+        self.len -= 1;
+        self.buckets[self.min].pop()
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.len + self.overflow.len()
+    }
+}
@@ -1,104 +1,1089 @@
-use std::{
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+mod admissibility;
+mod analysis;
+mod beam;
+mod buffers;
+#[cfg(feature = "async")]
+mod cancel;
+#[cfg(feature = "checkpoint")]
+mod checkpoint;
+mod closed;
+mod dijkstra;
+#[cfg(feature = "disk")]
+mod disk;
+mod eccentricity;
+mod effort;
+mod frontier;
+mod limits;
+mod macro_action;
+mod memory;
+mod observer;
+mod rng;
+mod search;
+mod searcher;
+#[cfg(feature = "std")]
+mod stream;
+mod waypoints;
+
+pub use crate::admissibility::*;
+pub use crate::analysis::*;
+pub use crate::beam::*;
+pub use crate::buffers::*;
+#[cfg(feature = "async")]
+pub use crate::cancel::*;
+#[cfg(feature = "checkpoint")]
+pub use crate::checkpoint::*;
+pub use crate::closed::*;
+pub use crate::dijkstra::*;
+#[cfg(feature = "disk")]
+pub use crate::disk::*;
+pub use crate::eccentricity::*;
+pub use crate::effort::*;
+pub use crate::frontier::*;
+pub use crate::limits::*;
+pub use crate::macro_action::*;
+pub use crate::memory::*;
+pub use crate::observer::*;
+pub use crate::rng::*;
+pub use crate::searcher::*;
+#[cfg(feature = "std")]
+pub use crate::stream::*;
+pub use crate::waypoints::*;
+
+use crate::search::Search;
+use alloc::{string::String, vec::Vec};
+use core::{
     cmp::{Ord, Ordering, PartialOrd},
-    collections::{hash_map, BinaryHeap, HashMap},
+    fmt,
     hash::Hash,
-    ops::Add,
 };
 
 pub enum Transition<S: State> {
     Indeterminate(S),
-    Success,
+    Success(S::Outcome),
 }
 
-pub trait State: Eq + Hash + PartialEq + Sized {
-    type Data;
-    type Action;
-    type Transitions: IntoIterator<Item = (Self::Action, Transition<Self>)>;
-    type Heuristic: Ord + Add<usize, Output = Self::Heuristic>;
+pub trait Cost: Default + Ord + fmt::Debug {
+    /// The frontier used when searching with this cost.
+    type Queue<T>: Frontier<Self, T>;
 
-    fn transitions(&self, data: &Self::Data) -> Self::Transitions;
-    fn heuristic(&self, data: &Self::Data) -> Self::Heuristic;
+    /// Adds the distance travelled so far to a heuristic estimate, saturating
+    /// instead of overflowing.
+    fn combine(self, distance: usize) -> Self;
 }
 
-#[derive(Eq, PartialEq)]
-struct Node<S: State> {
-    state: S,
-    distance: usize,
-    estimate: S::Heuristic,
-    index: usize,
+impl Cost for usize {
+    type Queue<T> = BucketFrontier<T>;
+
+    #[inline]
+    fn combine(self, distance: usize) -> Self {
+        self.saturating_add(distance)
+    }
+}
+
+impl Cost for u64 {
+    type Queue<T> = HeapFrontier<u64, T>;
+
+    #[inline]
+    fn combine(self, distance: usize) -> Self {
+        self.saturating_add(distance as u64)
+    }
 }
 
-impl<S: State> PartialOrd for Node<S> {
+/// An `f64` ordered by `total_cmp`, for games with fractional heuristics.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OrderedFloat(pub f64);
+
+impl PartialEq for OrderedFloat {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OrderedFloat {}
+
+impl PartialOrd for OrderedFloat {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl<S: State> Ord for Node<S> {
+impl Ord for OrderedFloat {
     fn cmp(&self, other: &Self) -> Ordering {
-        other.estimate.cmp(&self.estimate)
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl Cost for OrderedFloat {
+    type Queue<T> = HeapFrontier<OrderedFloat, T>;
+
+    #[inline]
+    fn combine(self, distance: usize) -> Self {
+        OrderedFloat(self.0 + distance as f64)
+    }
+}
+
+/// How the search reached a state, for heuristics that depend on more than
+/// the state itself.
+#[derive(Clone, Copy, Debug)]
+pub struct HeuristicContext<'a, A> {
+    /// The cost of the path the search took to the state.
+    pub depth: usize,
+    /// The action that led to the state, or `None` for the initial state.
+    pub parent_action: Option<&'a A>,
+}
+
+pub trait State: Eq + Hash + PartialEq + Sized {
+    type Data;
+    type Action: Clone;
+    /// Distinguishes between different ways of solving a puzzle. Games with
+    /// only one way to succeed use `()`.
+    type Outcome;
+    type Transitions: IntoIterator<Item = (Self::Action, Transition<Self>)>;
+    type Heuristic: Cost;
+
+    /// Whether `heuristic` gives a meaningful estimate. Games without one can
+    /// set this to `false` and leave `heuristic` unimplemented, in which case
+    /// the search never evaluates it and degrades to Dijkstra's algorithm.
+    const HAS_HEURISTIC: bool = true;
+
+    fn transitions(&self, data: &Self::Data) -> Self::Transitions;
+
+    fn heuristic(&self, _data: &Self::Data) -> Self::Heuristic {
+        Self::Heuristic::default()
+    }
+
+    /// The heuristic as searches ask for it, with the path taken so far.
+    /// Games can override this instead of `heuristic` to weight estimates by
+    /// depth or by the last move.
+    fn heuristic_ctx(
+        &self,
+        data: &Self::Data,
+        _context: &HeuristicContext<'_, Self::Action>,
+    ) -> Self::Heuristic {
+        self.heuristic(data)
+    }
+
+    /// How much taking `action` costs. Games with macro moves, which stand
+    /// for several steps at once, return the number of steps so that
+    /// solutions are compared by their full length. The heuristic has to
+    /// estimate costs in the same units.
+    fn action_cost(_action: &Self::Action) -> usize {
+        1
+    }
+
+    /// Whether taking `second` straight after `first` is redundant, because
+    /// taking them the other way round reaches the same state at the same
+    /// cost. The search skips `second` from states it reached by `first`,
+    /// which cuts out the interleavings of moves on independent objects.
+    /// Return `true` for at most one order of each pair so the other is still
+    /// tried, and only for pairs which can be taken in either order wherever
+    /// one order can. Since only the last move is looked at, declaring every
+    /// commuting pair in one consistent order keeps the search optimal.
+    fn commutes(_first: &Self::Action, _second: &Self::Action) -> bool {
+        false
+    }
+
+    /// The outcome this state has already achieved, if it's a goal. Success
+    /// is normally reported by `transitions`, but the search also needs to
+    /// recognize goals it didn't reach by a move, such as the initial state.
+    fn is_goal(&self, _data: &Self::Data) -> Option<Self::Outcome> {
+        None
+    }
+
+    /// Explains why the puzzle can't be solved from this state, if that can
+    /// be shown cheaply. The search checks the initial state once before
+    /// starting, so counting or matching arguments can rule out impossible
+    /// puzzles without exploring every reachable state.
+    fn infeasible(&self, _data: &Self::Data) -> Option<String> {
+        None
+    }
+
+    /// Packs the state into a compact key to store in the closed set instead
+    /// of the full state. Packing must be lossless: two states must pack to
+    /// the same key exactly when they are equal.
+    fn pack(&self) -> Option<u128> {
+        None
+    }
+}
+
+fn estimate<S: State>(
+    state: &S,
+    data: &S::Data,
+    distance: usize,
+    parent_action: Option<&S::Action>,
+) -> S::Heuristic {
+    if S::HAS_HEURISTIC {
+        let context = HeuristicContext {
+            depth: distance,
+            parent_action,
+        };
+        state.heuristic_ctx(data, &context).combine(distance)
+    } else {
+        S::Heuristic::default().combine(distance)
     }
 }
 
+pub struct SolveReport<A, O> {
+    pub solution: Option<Vec<A>>,
+    pub outcome: Option<O>,
+    pub expanded: usize,
+    pub memory: MemoryUsage,
+    /// Why the puzzle was ruled out without searching, if it was.
+    pub infeasible: Option<String>,
+}
+
 pub fn solve<S: State>(initial_state: S, data: &S::Data) -> Option<Vec<S::Action>> {
-    let mut states = HashMap::new();
-    let mut parents = Vec::new();
-    let mut queue = BinaryHeap::<Node<S>>::new();
-
-    // Insert initial state
-    let initial_transitions = initial_state.transitions(data);
-    states.insert(initial_state, ());
-
-    // Add transitions from initial state
-    for (action, transition) in initial_transitions {
-        match transition {
-            Transition::Indeterminate(state) => {
-                parents.push((0, action));
-
-                let estimate = state.heuristic(data) + 1;
-                queue.push(Node {
-                    state,
-                    distance: 1,
-                    estimate,
-                    index: parents.len(),
-                });
+    solve_with_report(initial_state, data).solution
+}
+
+pub fn solve_with_report<S: State>(
+    initial_state: S,
+    data: &S::Data,
+) -> SolveReport<S::Action, S::Outcome> {
+    solve_for(initial_state, data, |_| true)
+}
+
+/// Finds the shortest solution ending in an outcome accepted by `accept`.
+/// Successful transitions with any other outcome are treated as dead ends.
+pub fn solve_for<S: State>(
+    initial_state: S,
+    data: &S::Data,
+    accept: impl FnMut(&S::Outcome) -> bool,
+) -> SolveReport<S::Action, S::Outcome> {
+    Search::new(initial_state, data, accept, None).finish()
+}
+
+/// Finds the shortest solution like `solve_with_report`, remembering the
+/// states it has expanded in `closed` instead of in memory.
+pub fn solve_with_closed<S: State, C: ClosedSet<S>>(
+    initial_state: S,
+    data: &S::Data,
+    closed: C,
+) -> SolveReport<S::Action, S::Outcome> {
+    Search::with_closed(initial_state, data, |_| true, None, closed).finish()
+}
+
+/// Finds the shortest solution like `solve_with_report`, telling `observer`
+/// about every state as it's expanded.
+pub fn solve_observed<S: State>(
+    initial_state: S,
+    data: &S::Data,
+    observer: &mut dyn Observer<S>,
+) -> SolveReport<S::Action, S::Outcome> {
+    Search::new(initial_state, data, |_| true, Some(observer)).finish()
+}
+
+/// Finds the shortest solution, pairing each action with the transition it
+/// leads to. Every step but the last leads to the next state along the way.
+pub fn solve_traced<S: State + Clone>(
+    initial_state: S,
+    data: &S::Data,
+) -> Option<Vec<(S::Action, Transition<S>)>>
+where
+    S::Action: PartialEq,
+{
+    let solution = solve(initial_state.clone(), data)?;
+    replay(initial_state, data, solution)
+}
+
+/// Replays a solution from the initial state, pairing each action with the
+/// transition it leads to. Returns `None` if an action isn't available from
+/// the state it's taken in, which can only happen if transitions aren't
+/// deterministic.
+pub fn replay<S: State + Clone>(
+    initial_state: S,
+    data: &S::Data,
+    solution: Vec<S::Action>,
+) -> Option<Vec<(S::Action, Transition<S>)>>
+where
+    S::Action: PartialEq,
+{
+    let mut state = initial_state;
+    let mut steps = Vec::with_capacity(solution.len());
+    for action in solution {
+        let transition = state
+            .transitions(data)
+            .into_iter()
+            .find(|(a, _)| *a == action)?
+            .1;
+        if let Transition::Indeterminate(next) = &transition {
+            state = next.clone();
+        }
+        steps.push((action, transition));
+    }
+    Some(steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "std")]
+    use std::time::Duration;
+
+    #[derive(Clone, Eq, Hash, PartialEq)]
+    struct Counter(i32);
+
+    impl State for Counter {
+        type Data = i32;
+        type Action = i32;
+        type Outcome = ();
+        type Transitions = Vec<(i32, Transition<Self>)>;
+        type Heuristic = usize;
+
+        const HAS_HEURISTIC: bool = false;
+
+        fn is_goal(&self, target: &i32) -> Option<()> {
+            if self.0 == *target {
+                Some(())
+            } else {
+                None
+            }
+        }
+
+        fn transitions(&self, target: &i32) -> Self::Transitions {
+            [1, -1, 3]
+                .iter()
+                .map(|&step| {
+                    let next = self.0 + step;
+                    if next == *target {
+                        (step, Transition::Success(()))
+                    } else {
+                        (step, Transition::Indeterminate(Counter(next)))
+                    }
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn costs_saturate() {
+        assert_eq!(usize::MAX.combine(1), usize::MAX);
+        assert_eq!(u64::MAX.combine(1), u64::MAX);
+        assert_eq!(OrderedFloat(0.5).combine(2), OrderedFloat(2.5));
+        assert!(OrderedFloat(f64::INFINITY) > OrderedFloat(1.0).combine(usize::MAX));
+    }
+
+    #[test]
+    fn bucket_frontier_pops_in_cost_order() {
+        let mut frontier = BucketFrontier::default();
+        for &cost in [5, 2, usize::MAX, 7, 2, 0].iter() {
+            frontier.push(cost, cost);
+        }
+        frontier.push(1, 1);
+
+        let mut popped = Vec::new();
+        while let Some(cost) = frontier.pop() {
+            popped.push(cost);
+        }
+        assert_eq!(popped, vec![0, 1, 2, 2, 5, 7, usize::MAX]);
+        assert!(frontier.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn stream_events() {
+        let mut output = Vec::new();
+        let report = solve_to_writer(Counter(0), &7, 4, &mut output).unwrap();
+        assert_eq!(report.solution, Some(vec![1, 3, 3]));
+
+        let output = String::from_utf8(output).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert!(lines[..lines.len() - 2]
+            .iter()
+            .all(|l| l.starts_with("progress ")));
+        assert_eq!(lines[lines.len() - 2], "solution 3 1 3 3");
+        assert_eq!(
+            lines[lines.len() - 1],
+            format!("finished {} solved", report.expanded)
+        );
+
+        let mut output = Vec::new();
+        solve_to_writer(Counter(0), &0, 4, &mut output).unwrap();
+        assert_eq!(output, b"solution 0\nfinished 0 solved\n");
+    }
+
+    #[test]
+    fn reuse_search_buffers() {
+        let mut buffers = SearchBuffers::new();
+        for &target in [7, -4, 0, 12, 7].iter() {
+            let reused = solve_with_buffers(Counter(0), &target, &mut buffers);
+            let fresh = solve_with_report(Counter(0), &target);
+            assert_eq!(reused.solution, fresh.solution);
+            assert_eq!(reused.expanded, fresh.expanded);
+        }
+
+        // Searches that give up leave the buffers ready for the next one
+        let limits = Limits {
+            expansions: Some(3),
+            #[cfg(feature = "std")]
+            time: None,
+        };
+        assert!(matches!(
+            solve_limited_with_buffers(Counter(0), &5, &limits, &mut buffers),
+            Err(LimitExceeded::Expansions(3))
+        ));
+        let report = solve_with_buffers(Counter(0), &5, &mut buffers);
+        assert_eq!(report.solution, solve(Counter(0), &5));
+        assert_eq!(report.expanded, 5);
+    }
+
+    #[derive(Clone, Eq, Hash, PartialEq)]
+    struct Walker(i32);
+
+    #[derive(Debug, PartialEq)]
+    enum Exit {
+        Low,
+        High,
+    }
+
+    impl State for Walker {
+        type Data = ();
+        type Action = i32;
+        type Outcome = Exit;
+        type Transitions = Vec<(i32, Transition<Self>)>;
+        type Heuristic = usize;
+
+        fn transitions(&self, _: &()) -> Self::Transitions {
+            [1, -1]
+                .iter()
+                .map(|&step| match self.0 + step {
+                    -2 => (step, Transition::Success(Exit::Low)),
+                    4 => (step, Transition::Success(Exit::High)),
+                    next => (step, Transition::Indeterminate(Walker(next))),
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn farthest_states() {
+        // Walkers can reach -1 through 3 before stepping off either end
+        let farthest = eccentricity(Walker(0), &(), 100).unwrap();
+        assert_eq!(farthest.state.0, 3);
+        assert_eq!(farthest.distance, 3);
+        assert_eq!(farthest.reachable, 5);
+
+        assert_eq!(diameter(Walker(0), &(), 100), Some(4));
+        assert!(eccentricity(Walker(0), &(), 4).is_none());
+    }
+
+    #[test]
+    fn solve_for_outcome() {
+        let report = solve_with_report(Walker(0), &());
+        assert_eq!(report.outcome, Some(Exit::Low));
+        assert_eq!(report.solution, Some(vec![-1, -1]));
+
+        let report = solve_for(Walker(0), &(), |o| *o == Exit::High);
+        assert_eq!(report.outcome, Some(Exit::High));
+        assert_eq!(report.solution, Some(vec![1, 1, 1, 1]));
+    }
+
+    /// Steps two at a time, so it can only reach goals of the same parity.
+    #[derive(Clone, Eq, Hash, PartialEq)]
+    struct Stride(i32);
+
+    impl State for Stride {
+        type Data = i32;
+        type Action = i32;
+        type Outcome = ();
+        type Transitions = Vec<(i32, Transition<Self>)>;
+        type Heuristic = usize;
+
+        const HAS_HEURISTIC: bool = false;
+
+        fn transitions(&self, target: &i32) -> Self::Transitions {
+            [2, -2]
+                .iter()
+                .map(|&step| match self.0 + step {
+                    next if next == *target => (step, Transition::Success(())),
+                    next => (step, Transition::Indeterminate(Stride(next))),
+                })
+                .collect()
+        }
+
+        fn infeasible(&self, target: &i32) -> Option<String> {
+            if (target - self.0) % 2 != 0 {
+                Some("the target has the wrong parity".to_string())
+            } else {
+                None
+            }
+        }
+    }
+
+    impl Waypoints for Stride {
+        fn waypoint_count(_: &i32) -> usize {
+            1
+        }
+
+        fn reaches_waypoint(&self, _: &i32, _: usize) -> bool {
+            self.0 == 2
+        }
+    }
+
+    #[test]
+    fn infeasible_puzzles_with_waypoints() {
+        // Both would search forever if the parity check were lost
+        assert_eq!(solve_waypoints(Stride(0), &3, true), None);
+        assert_eq!(solve_waypoints(Stride(0), &3, false), None);
+    }
+
+    #[test]
+    fn infeasible_puzzles_skip_search() {
+        // Without the parity check this search would never end
+        let report = solve_with_report(Stride(0), &3);
+        assert_eq!(report.solution, None);
+        assert_eq!(report.expanded, 0);
+        assert_eq!(
+            report.infeasible.as_deref(),
+            Some("the target has the wrong parity")
+        );
+
+        let report = solve_with_report(Stride(0), &4);
+        assert_eq!(report.solution, Some(vec![2, 2]));
+        assert_eq!(report.infeasible, None);
+    }
+
+    /// Walks between -1 and 1, where 1 looks closest to the goal at -2 but is
+    /// a dead end.
+    #[derive(Clone, Eq, Hash, PartialEq)]
+    struct Lure(i32);
+
+    impl State for Lure {
+        type Data = ();
+        type Action = i32;
+        type Outcome = ();
+        type Transitions = Vec<(i32, Transition<Self>)>;
+        type Heuristic = usize;
+
+        fn transitions(&self, _: &()) -> Self::Transitions {
+            [1, -1]
+                .iter()
+                .filter_map(|&step| match self.0 + step {
+                    -2 => Some((step, Transition::Success(()))),
+                    next if (-1..=1).contains(&next) => {
+                        Some((step, Transition::Indeterminate(Lure(next))))
+                    }
+                    _ => None,
+                })
+                .collect()
+        }
+
+        fn heuristic(&self, _: &()) -> usize {
+            if self.0 == 1 {
+                0
+            } else {
+                1
+            }
+        }
+    }
+
+    /// Moves independently along two axes to reach (3, 3), optionally
+    /// declaring that the moves commute.
+    #[derive(Clone, Eq, Hash, PartialEq)]
+    struct Grid<const COMMUTES: bool>(i32, i32);
+
+    impl<const COMMUTES: bool> State for Grid<COMMUTES> {
+        type Data = ();
+        type Action = char;
+        type Outcome = ();
+        type Transitions = Vec<(char, Transition<Self>)>;
+        type Heuristic = usize;
+
+        const HAS_HEURISTIC: bool = false;
+
+        fn transitions(&self, _: &()) -> Self::Transitions {
+            [
+                ('x', Grid(self.0 + 1, self.1)),
+                ('y', Grid(self.0, self.1 + 1)),
+            ]
+            .iter()
+            .filter(|(_, next)| next.0 <= 3 && next.1 <= 3)
+            .map(|(action, next)| match next {
+                Grid(3, 3) => (*action, Transition::Success(())),
+                next => (*action, Transition::Indeterminate(next.clone())),
+            })
+            .collect()
+        }
+
+        fn commutes(first: &char, second: &char) -> bool {
+            COMMUTES && *first == 'y' && *second == 'x'
+        }
+    }
+
+    #[test]
+    fn commuting_moves_are_pruned() {
+        let pruned = solve_with_report(Grid::<true>(0, 0), &());
+        let full = solve_with_report(Grid::<false>(0, 0), &());
+        assert_eq!(full.solution.map(|s| s.len()), Some(6));
+
+        // Only the order with every x before every y is searched
+        assert_eq!(pruned.solution, Some("xxxyyy".chars().collect()));
+        assert!(pruned.memory.parents < full.memory.parents);
+    }
+
+    impl<const COMMUTES: bool> Waypoints for Grid<COMMUTES> {
+        fn waypoint_count(_: &()) -> usize {
+            1
+        }
+
+        fn reaches_waypoint(&self, _: &(), _: usize) -> bool {
+            *self == Grid(0, 1)
+        }
+    }
+
+    #[test]
+    fn commuting_moves_through_waypoints() {
+        // The waypoint is only reached by a y first, which x isn't taken
+        // after unless the y is what reached the waypoint
+        for &global in [false, true].iter() {
+            let solution = solve_waypoints(Grid::<true>(0, 0), &(), global).unwrap();
+            assert_eq!(solution, "yxxxyy".chars().collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn beam_search() {
+        assert_eq!(solve_beam(Lure(0), &(), 1), Beam::Unknown);
+        assert_eq!(solve_beam(Lure(0), &(), 2), Beam::Solved(vec![-1, -1], ()));
+        assert_eq!(solve_beam(Lure(-2), &(), 1), Beam::Solved(vec![1, -1], ()));
+    }
+
+    #[test]
+    fn guarded_beam_search() {
+        let guard = CycleGuard {
+            recent: 2,
+            max_depth: 10,
+        };
+        assert_eq!(solve_beam_guarded(Lure(0), &(), 1, guard), Beam::Unknown);
+        assert_eq!(
+            solve_beam_guarded(Lure(0), &(), 2, guard),
+            Beam::Solved(vec![-1, -1], ())
+        );
+        assert_eq!(
+            solve_beam_guarded(Lure(-2), &(), 1, guard),
+            Beam::Solved(vec![1, -1], ())
+        );
+
+        // Only ruling out the state being expanded lets the beam bounce
+        // between 0 and 1 until it runs out of depth
+        let guard = CycleGuard {
+            recent: 1,
+            max_depth: 10,
+        };
+        assert_eq!(solve_beam_guarded(Lure(0), &(), 1, guard), Beam::Unknown);
+    }
+
+    #[test]
+    fn estimate_effort_by_sampling() {
+        // Every walk takes two steps, either into the dead end or next to the
+        // goal, so the estimate doesn't depend on the seed
+        let effort = estimate_effort(Lure(0), &(), 20, 10, 7);
+        assert_eq!(effort.goal_depth, Some(2));
+        assert_eq!(effort.depth, 2.0);
+        assert_eq!(effort.branching_factor, 1.0);
+        assert_eq!(effort.projected, 3.0);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn plan_search_by_sampling() {
+        let effort = estimate_effort(Counter(0), &5, 20, 10, 7);
+        let plan = plan_search(Counter(0), &5, &effort, Duration::from_secs(10));
+        assert!(plan.exact);
+        assert_eq!(
+            plan.states,
+            solve_with_report(Counter(0), &5).expanded as f64
+        );
+
+        // Cutting the sample short scales it up to the projection
+        let effort = Effort {
+            projected: 1e9,
+            ..effort
+        };
+        let plan = plan_search(Counter(0), &100_000, &effort, Duration::ZERO);
+        assert!(!plan.exact);
+        assert_eq!(plan.states, 1e9);
+        assert!(plan.memory() > 1e9);
+        assert!(plan.time() > Duration::ZERO);
+    }
+
+    #[test]
+    fn step_through_search() {
+        let mut searcher = Searcher::new(Counter(0), &5);
+        let mut steps = 0;
+        let report = loop {
+            steps += 1;
+            if let StepResult::Done(report) = searcher.step() {
+                break report;
+            }
+            assert_eq!(searcher.expanded(), steps);
+        };
+        assert_eq!(report.solution, solve(Counter(0), &5));
+        assert_eq!(steps, report.expanded);
+
+        // Carrying on finds other solutions of the same length
+        let mut solutions = vec![report.solution.unwrap()];
+        for _ in 0..2 {
+            match searcher.run(usize::MAX) {
+                StepResult::Done(report) => solutions.push(report.solution.unwrap()),
+                StepResult::Running => unreachable!(),
             }
-            Transition::Success => return Some(vec![action]),
         }
+        solutions.sort();
+        solutions.dedup();
+        assert_eq!(solutions.len(), 3);
+        assert!(solutions.iter().all(|s| s.len() == 3));
+
+        let mut searcher = Searcher::new(Counter(0), &5);
+        assert!(matches!(searcher.run(1), StepResult::Running));
     }
 
-    // Pop states in priority order until empty
-    while let Some(parent_node) = queue.pop() {
-        if let hash_map::Entry::Vacant(vacant) = states.entry(parent_node.state) {
-            for (action, transition) in vacant.key().transitions(data) {
-                match transition {
-                    Transition::Indeterminate(state) => {
-                        parents.push((parent_node.index, action));
+    #[test]
+    fn report_memory() {
+        let report = solve_with_report(Walker(0), &());
+        assert!(report.memory.closed >= report.expanded * std::mem::size_of::<Walker>());
+        assert!(report.memory.open > 0);
+        assert!(report.memory.parents > 0);
+        assert_eq!(
+            report.memory.total(),
+            report.memory.closed + report.memory.open + report.memory.parents
+        );
+    }
+
+    #[derive(Clone, Eq, Hash, PartialEq)]
+    struct Patrol(i32);
+
+    struct Route {
+        waypoints: Vec<i32>,
+        goal: i32,
+    }
 
-                        let estimate = state.heuristic(data) + (parent_node.distance + 1);
-                        queue.push(Node {
-                            state,
-                            distance: parent_node.distance + 1,
-                            estimate,
-                            index: parents.len(),
-                        });
+    impl State for Patrol {
+        type Data = Route;
+        type Action = i32;
+        type Outcome = ();
+        type Transitions = Vec<(i32, Transition<Self>)>;
+        type Heuristic = usize;
+
+        fn transitions(&self, route: &Route) -> Self::Transitions {
+            [1, -1]
+                .iter()
+                .map(|&step| {
+                    let next = self.0 + step;
+                    if next == route.goal {
+                        (step, Transition::Success(()))
+                    } else {
+                        (step, Transition::Indeterminate(Patrol(next)))
                     }
-                    Transition::Success => {
-                        let mut result_actions = vec![action];
-                        let mut current_index = parent_node.index;
-                        while current_index != 0 {
-                            let (next_index, action) = parents.swap_remove(current_index - 1);
-                            result_actions.push(action);
-                            current_index = next_index;
-                        }
-                        result_actions.reverse();
-                        return Some(result_actions);
+                })
+                .collect()
+        }
+
+        fn heuristic(&self, route: &Route) -> usize {
+            (route.goal - self.0).unsigned_abs() as usize
+        }
+    }
+
+    #[test]
+    fn admissible_along_solution() {
+        let route = Route {
+            waypoints: Vec::new(),
+            goal: 3,
+        };
+        assert_eq!(
+            check_admissible(Patrol(0), &route, &[1, 1, 1]),
+            Some(Vec::new())
+        );
+        assert_eq!(check_admissible(Patrol(0), &route, &[1, -1]), None);
+    }
+
+    /// Walks like `Patrol`, but guesses twice the real distance.
+    #[derive(Clone, Eq, Hash, PartialEq)]
+    struct Hasty(Patrol);
+
+    impl State for Hasty {
+        type Data = Route;
+        type Action = i32;
+        type Outcome = ();
+        type Transitions = Vec<(i32, Transition<Self>)>;
+        type Heuristic = usize;
+
+        fn transitions(&self, route: &Route) -> Self::Transitions {
+            self.0
+                .transitions(route)
+                .into_iter()
+                .map(|(step, transition)| match transition {
+                    Transition::Indeterminate(next) => {
+                        (step, Transition::Indeterminate(Hasty(next)))
                     }
+                    Transition::Success(()) => (step, Transition::Success(())),
+                })
+                .collect()
+        }
+
+        fn heuristic(&self, route: &Route) -> usize {
+            self.0.heuristic(route) * 2
+        }
+    }
+
+    #[test]
+    fn overestimates_along_solution() {
+        let route = Route {
+            waypoints: Vec::new(),
+            goal: 2,
+        };
+        assert_eq!(
+            check_admissible(Hasty(Patrol(0)), &route, &[1, 1]),
+            Some(vec![
+                Overestimate {
+                    step: 0,
+                    heuristic: 4,
+                    remaining: 2,
+                },
+                Overestimate {
+                    step: 1,
+                    heuristic: 2,
+                    remaining: 1,
+                },
+            ])
+        );
+    }
+
+    impl Waypoints for Patrol {
+        fn waypoint_count(route: &Route) -> usize {
+            route.waypoints.len()
+        }
+
+        fn reaches_waypoint(&self, route: &Route, index: usize) -> bool {
+            self.0 == route.waypoints[index]
+        }
+    }
+
+    #[test]
+    fn solve_through_waypoints() {
+        let route = Route {
+            waypoints: vec![3, -1],
+            goal: 5,
+        };
+
+        for &global in [false, true].iter() {
+            let solution = solve_waypoints(Patrol(0), &route, global).unwrap();
+            assert_eq!(solution.len(), 13);
+
+            let mut position = 0;
+            let mut next = 0;
+            for step in solution {
+                position += step;
+                if next < route.waypoints.len() && position == route.waypoints[next] {
+                    next += 1;
                 }
             }
-            vacant.insert(());
+            assert_eq!(next, route.waypoints.len());
+            assert_eq!(position, route.goal);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn solve_within_limits() {
+        let limits = Limits {
+            expansions: Some(3),
+            time: None,
+        };
+        assert!(matches!(
+            solve_limited(Counter(0), &5, &limits, None),
+            Err(LimitExceeded::Expansions(3))
+        ));
+
+        let limits = Limits {
+            expansions: Some(5),
+            time: Some(Duration::from_secs(60)),
+        };
+        let report = solve_limited(Counter(0), &5, &limits, None).unwrap();
+        assert_eq!(report.solution, solve(Counter(0), &5));
+        assert_eq!(report.expanded, 5);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn combined_observers() {
+        let mut observers = (
+            TraceWriter::new(Vec::new()),
+            (None::<TraceWriter<Vec<u8>>>, TraceWriter::new(Vec::new())),
+        );
+        solve_observed(Counter(0), &5, &mut observers);
+        let (first, (_, second)) = observers;
+        assert_eq!(first.finish().unwrap(), b"0 1 0\n1 3 1\n2 1 2\n");
+        assert_eq!(second.finish().unwrap(), b"0 1 0\n1 3 1\n2 1 2\n");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn trace_expansions() {
+        let mut trace = TraceWriter::new(Vec::new());
+        let report = solve_observed(Counter(0), &5, &mut trace);
+        let trace = String::from_utf8(trace.finish().unwrap()).unwrap();
+        assert_eq!(trace, "0 1 0\n1 3 1\n2 1 2\n");
+
+        let mut csv = Vec::new();
+        trace_to_csv(trace.as_bytes(), &mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "depth,expanded\n0,1\n1,3\n2,1\n"
+        );
+        assert_eq!(report.expanded, 5);
+    }
+
+    #[test]
+    fn solve_without_heuristic() {
+        let solution = solve(Counter(0), &5).unwrap();
+        assert_eq!(solution.len(), 3);
+        assert_eq!(solution.iter().sum::<i32>(), 5);
+    }
+
+    #[test]
+    fn solve_with_states() {
+        let steps = solve_traced(Walker(0), &()).unwrap();
+        assert_eq!(steps.len(), 2);
+        assert!(matches!(
+            steps[0],
+            (-1, Transition::Indeterminate(Walker(-1)))
+        ));
+        assert!(matches!(steps[1], (-1, Transition::Success(Exit::Low))));
+
+        assert!(replay(Walker(0), &(), vec![-1, 0]).is_none());
+    }
+
+    /// A corridor with a macro move that walks three steps at once, but only
+    /// from the start.
+    #[derive(Eq, Hash, PartialEq)]
+    struct Corridor(i32);
+
+    impl State for Corridor {
+        type Data = i32;
+        type Action = MacroAction<i32>;
+        type Outcome = ();
+        type Transitions = Vec<(MacroAction<i32>, Transition<Self>)>;
+        type Heuristic = usize;
+
+        fn transitions(&self, goal: &i32) -> Self::Transitions {
+            let mut actions = Vec::new();
+            if self.0 == 0 {
+                actions.push(MacroAction::Macro(vec![1, 1, 1]));
+            }
+            actions.push(MacroAction::Step(1));
+            actions
+                .into_iter()
+                .map(|action| {
+                    let next = self.0
+                        + MacroAction::expand(vec![action.clone()])
+                            .iter()
+                            .sum::<i32>();
+                    if next >= *goal {
+                        (action, Transition::Success(()))
+                    } else {
+                        (action, Transition::Indeterminate(Corridor(next)))
+                    }
+                })
+                .collect()
+        }
+
+        fn heuristic(&self, goal: &i32) -> usize {
+            (goal - self.0).max(0) as usize
+        }
+
+        fn action_cost(action: &MacroAction<i32>) -> usize {
+            action.cost()
+        }
+    }
+
+    #[test]
+    fn solve_with_macro_moves() {
+        let solution = solve(Corridor(0), &6).unwrap();
+        assert_eq!(MacroAction::expand(solution).len(), 6);
+
+        // The macro move reaches a goal two steps away first, but overshooting
+        // it costs more than walking
+        let solution = solve(Corridor(0), &2).unwrap();
+        assert_eq!(solution, vec![MacroAction::Step(1), MacroAction::Step(1)]);
+        assert_eq!(MacroAction::Macro(vec![1, 2]).to_string(), "1, 2");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn analyze_counter() {
+        let analysis = analyze(Counter(0), &5).unwrap();
+        assert_eq!(analysis.length, 3);
+        // Two 1s and a 3, or two 3s and a -1, in any order
+        assert_eq!(analysis.optimal_solutions, 6);
+        assert_eq!(analysis.dead_ends, 0);
+        assert_eq!(analysis.branching_factor, 3.0);
+        assert!(analysis.difficulty() > 0.0);
+    }
+
+    #[test]
+    fn dijkstra_ignores_heuristic() {
+        let route = Route {
+            waypoints: Vec::new(),
+            goal: 5,
+        };
+        let guided = solve_with_report(Patrol(0), &route);
+        let blind = solve_dijkstra(Patrol(0), &route);
+        assert_eq!(blind.solution, guided.solution);
+        assert!(blind.expanded > guided.expanded);
+    }
+
+    #[derive(Eq, Hash, PartialEq)]
+    struct Leap(i32);
+
+    impl State for Leap {
+        type Data = i32;
+        type Action = i32;
+        type Outcome = ();
+        type Transitions = Vec<(i32, Transition<Self>)>;
+        type Heuristic = usize;
+
+        fn transitions(&self, target: &i32) -> Self::Transitions {
+            Counter(self.0)
+                .transitions(target)
+                .into_iter()
+                .map(|(step, transition)| match transition {
+                    Transition::Indeterminate(Counter(next)) => {
+                        (step, Transition::Indeterminate(Leap(next)))
+                    }
+                    Transition::Success(()) => (step, Transition::Success(())),
+                })
+                .collect()
+        }
+
+        // Favors states reached by the long step, which is still admissible
+        // since every state but the target is at least a step away
+        fn heuristic_ctx(&self, _: &i32, context: &HeuristicContext<'_, i32>) -> usize {
+            assert_eq!(context.depth == 0, context.parent_action.is_none());
+            if context.parent_action == Some(&3) {
+                0
+            } else {
+                1
+            }
         }
     }
 
-    None
+    #[test]
+    fn heuristic_sees_context() {
+        let solution = solve(Leap(0), &5).unwrap();
+        assert_eq!(solution, vec![3, 3, -1]);
+        assert_eq!(check_admissible(Leap(0), &5, &solution), Some(Vec::new()));
+    }
+
+    #[test]
+    fn solve_already_solved() {
+        let report = solve_with_report(Counter(5), &5);
+        assert_eq!(report.solution, Some(Vec::new()));
+        assert_eq!(report.outcome, Some(()));
+        assert_eq!(report.expanded, 0);
+    }
 }
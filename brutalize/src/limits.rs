@@ -0,0 +1,95 @@
+use crate::{search::Search, ClosedSet, Observer, SolveReport, State};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+/// How many states are expanded between checks of the clock.
+pub(crate) const EXPANSIONS_PER_CHECK: usize = 1024;
+
+/// Bounds on how much work a search may do before giving up.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Limits {
+    /// The most states to expand.
+    pub expansions: Option<usize>,
+    /// The longest to search for. The clock is only checked every so often,
+    /// so searches may run slightly over.
+    #[cfg(feature = "std")]
+    pub time: Option<Duration>,
+}
+
+impl Limits {
+    pub fn is_unlimited(&self) -> bool {
+        #[cfg(feature = "std")]
+        if self.time.is_some() {
+            return false;
+        }
+        self.expansions.is_none()
+    }
+}
+
+/// Why a limited search gave up.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LimitExceeded {
+    Expansions(usize),
+    #[cfg(feature = "std")]
+    Time(Duration),
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            LimitExceeded::Expansions(count) => {
+                write!(f, "gave up after expanding {} states", count)
+            }
+            #[cfg(feature = "std")]
+            LimitExceeded::Time(time) => write!(
+                f,
+                "gave up after {}.{:03}s",
+                time.as_secs(),
+                time.subsec_millis()
+            ),
+        }
+    }
+}
+
+/// Finds the shortest solution like `solve_with_report`, giving up once the
+/// search goes over `limits`. Like `solve_observed`, `observer` is told about
+/// every state as it's expanded.
+pub fn solve_limited<'a, S: State>(
+    initial_state: S,
+    data: &'a S::Data,
+    limits: &Limits,
+    observer: Option<&'a mut dyn Observer<S>>,
+) -> Result<SolveReport<S::Action, S::Outcome>, LimitExceeded> {
+    let mut search = Search::new(initial_state, data, |_| true, observer);
+    run_limited(&mut search, limits)
+}
+
+/// Runs `search` to the end, giving up once it goes over `limits`.
+pub(crate) fn run_limited<S: State, F: FnMut(&S::Outcome) -> bool, C: ClosedSet<S>>(
+    search: &mut Search<'_, S, F, C>,
+    limits: &Limits,
+) -> Result<SolveReport<S::Action, S::Outcome>, LimitExceeded> {
+    #[cfg(feature = "std")]
+    let start = Instant::now();
+    loop {
+        // Each run pops at least as many states as it expands, so this never
+        // expands more than the limit
+        let budget = match limits.expansions {
+            Some(max) if search.expanded() >= max => {
+                return Err(LimitExceeded::Expansions(max));
+            }
+            Some(max) => usize::min(max - search.expanded(), EXPANSIONS_PER_CHECK),
+            None => EXPANSIONS_PER_CHECK,
+        };
+        if let Some(report) = search.run(budget) {
+            return Ok(report);
+        }
+        #[cfg(feature = "std")]
+        if let Some(time) = limits.time {
+            if start.elapsed() >= time {
+                return Err(LimitExceeded::Time(time));
+            }
+        }
+    }
+}
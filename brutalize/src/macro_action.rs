@@ -0,0 +1,52 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+/// An action which is either a single step or a macro move standing for
+/// several steps in a row, such as walking down a corridor. Games using macro
+/// moves return `cost` from `State::action_cost`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum MacroAction<A> {
+    Step(A),
+    Macro(Vec<A>),
+}
+
+impl<A> MacroAction<A> {
+    /// The number of steps the action stands for.
+    #[inline]
+    pub fn cost(&self) -> usize {
+        match self {
+            MacroAction::Step(_) => 1,
+            MacroAction::Macro(steps) => steps.len(),
+        }
+    }
+
+    /// Expands a solution made of macro moves into the steps it stands for.
+    pub fn expand(actions: Vec<MacroAction<A>>) -> Vec<A> {
+        let mut result = Vec::new();
+        for action in actions {
+            match action {
+                MacroAction::Step(step) => result.push(step),
+                MacroAction::Macro(steps) => result.extend(steps),
+            }
+        }
+        result
+    }
+}
+
+impl<A: fmt::Display> fmt::Display for MacroAction<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MacroAction::Step(step) => write!(f, "{}", step),
+            MacroAction::Macro(steps) => {
+                let mut steps = steps.iter();
+                if let Some(step) = steps.next() {
+                    write!(f, "{}", step)?;
+                }
+                for step in steps {
+                    write!(f, ", {}", step)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
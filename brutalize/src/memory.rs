@@ -0,0 +1,71 @@
+use core::fmt;
+
+/// An estimate of the memory a search used, in bytes. It's worked out from the
+/// size and number of entries in each structure, so allocator overhead and
+/// anything the states own on the heap aren't counted.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct MemoryUsage {
+    /// The set of states which have already been expanded.
+    pub closed: usize,
+    /// The open list at its largest.
+    pub open: usize,
+    /// The table of parent links used to rebuild the solution.
+    pub parents: usize,
+}
+
+impl MemoryUsage {
+    pub fn total(&self) -> usize {
+        self.closed + self.open + self.parents
+    }
+}
+
+/// Writes a byte count with a binary unit, like `1.5 MiB`.
+pub struct Bytes(pub usize);
+
+impl fmt::Display for Bytes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+        if self.0 < 1024 {
+            return write!(f, "{} B", self.0);
+        }
+        let mut size = self.0 as f64 / 1024.0;
+        let mut unit = 0;
+        while size >= 1024.0 && unit < UNITS.len() - 1 {
+            size /= 1024.0;
+            unit += 1;
+        }
+        write!(f, "{:.1} {}", size, UNITS[unit])
+    }
+}
+
+impl fmt::Display for MemoryUsage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} (closed {}, open {}, parents {})",
+            Bytes(self.total()),
+            Bytes(self.closed),
+            Bytes(self.open),
+            Bytes(self.parents)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let usage = MemoryUsage {
+            closed: 3 * 1024 * 1024 / 2,
+            open: 2048,
+            parents: 100,
+        };
+        assert_eq!(
+            usage.to_string(),
+            "1.5 MiB (closed 1.5 MiB, open 2.0 KiB, parents 100 B)"
+        );
+    }
+}
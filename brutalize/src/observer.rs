@@ -0,0 +1,131 @@
+use crate::State;
+#[cfg(feature = "std")]
+use std::{
+    collections::BTreeMap,
+    io::{self, BufRead, Write},
+};
+
+/// Watches a search as it runs, for example to record how it explores.
+pub trait Observer<S: State> {
+    /// Called once for each state the search expands, with its distance from
+    /// the initial state and its estimated total cost.
+    fn expand(&mut self, depth: usize, estimate: &S::Heuristic, state: &S);
+}
+
+impl<S: State, O: Observer<S> + ?Sized> Observer<S> for &mut O {
+    fn expand(&mut self, depth: usize, estimate: &S::Heuristic, state: &S) {
+        (**self).expand(depth, estimate, state);
+    }
+}
+
+/// A missing observer ignores the search, which lets optional observers be
+/// passed around without special cases.
+impl<S: State, O: Observer<S>> Observer<S> for Option<O> {
+    fn expand(&mut self, depth: usize, estimate: &S::Heuristic, state: &S) {
+        if let Some(observer) = self {
+            observer.expand(depth, estimate, state);
+        }
+    }
+}
+
+/// Pairs of observers both watch the same search.
+impl<S: State, A: Observer<S>, B: Observer<S>> Observer<S> for (A, B) {
+    fn expand(&mut self, depth: usize, estimate: &S::Heuristic, state: &S) {
+        self.0.expand(depth, estimate, state);
+        self.1.expand(depth, estimate, state);
+    }
+}
+
+/// An observer which writes a trace of every expansion. Consecutive
+/// expansions with the same depth and estimate are written as a single
+/// `depth count estimate` line, which keeps traces small since searches mostly
+/// expand states in order.
+#[cfg(feature = "std")]
+pub struct TraceWriter<W: Write> {
+    writer: W,
+    run: Option<(usize, String, usize)>,
+    error: Option<io::Error>,
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> TraceWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            run: None,
+            error: None,
+        }
+    }
+
+    fn write_run(&mut self) {
+        if let Some((depth, estimate, count)) = self.run.take() {
+            if self.error.is_none() {
+                if let Err(e) = writeln!(self.writer, "{} {} {}", depth, count, estimate) {
+                    self.error = Some(e);
+                }
+            }
+        }
+    }
+
+    /// Writes out the last run and returns the writer, or the first error hit
+    /// while writing the trace.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_run();
+        match self.error {
+            Some(e) => Err(e),
+            None => {
+                self.writer.flush()?;
+                Ok(self.writer)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S: State, W: Write> Observer<S> for TraceWriter<W> {
+    fn expand(&mut self, depth: usize, estimate: &S::Heuristic, _: &S) {
+        let estimate = format!("{:?}", estimate);
+        if let Some((run_depth, run_estimate, count)) = &mut self.run {
+            if *run_depth == depth && *run_estimate == estimate {
+                *count += 1;
+                return;
+            }
+        }
+
+        self.write_run();
+        self.run = Some((depth, estimate, 1));
+    }
+}
+
+/// Converts a trace written by `TraceWriter` into CSV with the number of
+/// states expanded at each depth.
+#[cfg(feature = "std")]
+pub fn trace_to_csv(reader: impl BufRead, mut writer: impl Write) -> io::Result<()> {
+    fn invalid(line: &str) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid trace line '{}'", line),
+        )
+    }
+
+    let mut expanded = BTreeMap::new();
+    for line in reader.lines() {
+        let line = line?;
+        let mut pieces = line.splitn(3, ' ');
+        let depth = pieces
+            .next()
+            .and_then(|p| p.parse::<usize>().ok())
+            .ok_or_else(|| invalid(&line))?;
+        let count = pieces
+            .next()
+            .and_then(|p| p.parse::<usize>().ok())
+            .ok_or_else(|| invalid(&line))?;
+        *expanded.entry(depth).or_insert(0) += count;
+    }
+
+    writeln!(writer, "depth,expanded")?;
+    for (depth, count) in expanded {
+        writeln!(writer, "{},{}", depth, count)?;
+    }
+    Ok(())
+}
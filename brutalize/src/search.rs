@@ -0,0 +1,388 @@
+use crate::{
+    estimate, ClosedSet, Cost, Frontier, HashClosedSet, MemoryUsage, Observer, SolveReport, State,
+    Transition,
+};
+use alloc::{string::String, vec, vec::Vec};
+use core::mem;
+#[cfg(feature = "checkpoint")]
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+#[cfg(feature = "checkpoint")]
+use std::io::{Read, Write};
+
+/// The acceptance test of searches which take any outcome.
+pub(crate) type AcceptAll<S> = fn(&<S as State>::Outcome) -> bool;
+
+#[cfg_attr(feature = "checkpoint", derive(Deserialize, Serialize))]
+pub(crate) struct Node<S: State> {
+    state: S,
+    distance: usize,
+    index: usize,
+}
+
+/// The storage a search grows as it goes: its closed set, the parents of
+/// every queued state, and its frontier.
+pub(crate) type Parts<S, C> = (
+    C,
+    Vec<(usize, <S as State>::Action)>,
+    <<S as State>::Heuristic as Cost>::Queue<Node<S>>,
+);
+
+/// A search which can be run a few expansions at a time, keeping its place
+/// between runs.
+pub(crate) struct Search<'a, S: State, F, C = HashClosedSet<S>> {
+    data: &'a S::Data,
+    accept: F,
+    observer: Option<&'a mut dyn Observer<S>>,
+    expanded: usize,
+    closed: C,
+    parents: Vec<(usize, S::Action)>,
+    queue: <S::Heuristic as Cost>::Queue<Node<S>>,
+    peak_open: usize,
+    // Successes reached by costly moves aren't necessarily the cheapest, so
+    // the best one is held back until nothing left could beat it
+    deferred: Option<(usize, usize, S::Action, S::Outcome)>,
+    solved: Option<S::Outcome>,
+    infeasible: Option<String>,
+}
+
+impl<'a, S: State, F: FnMut(&S::Outcome) -> bool> Search<'a, S, F> {
+    pub(crate) fn new(
+        initial_state: S,
+        data: &'a S::Data,
+        accept: F,
+        observer: Option<&'a mut dyn Observer<S>>,
+    ) -> Self {
+        Self::with_closed(
+            initial_state,
+            data,
+            accept,
+            observer,
+            HashClosedSet::default(),
+        )
+    }
+}
+
+impl<'a, S: State, F: FnMut(&S::Outcome) -> bool, C: ClosedSet<S>> Search<'a, S, F, C> {
+    /// Starts a search which remembers the states it expands in `closed`.
+    pub(crate) fn with_closed(
+        initial_state: S,
+        data: &'a S::Data,
+        accept: F,
+        observer: Option<&'a mut dyn Observer<S>>,
+        closed: C,
+    ) -> Self {
+        Self::with_parts(
+            initial_state,
+            data,
+            accept,
+            observer,
+            (closed, Vec::new(), Default::default()),
+        )
+    }
+
+    /// Starts a search on storage left over from an earlier one, which must
+    /// already be empty.
+    pub(crate) fn with_parts(
+        initial_state: S,
+        data: &'a S::Data,
+        mut accept: F,
+        observer: Option<&'a mut dyn Observer<S>>,
+        (closed, parents, mut queue): Parts<S, C>,
+    ) -> Self {
+        // Puzzles can start out solved, which no transition would report
+        let solved = initial_state.is_goal(data).filter(|o| accept(o));
+        let infeasible = if solved.is_none() {
+            initial_state.infeasible(data)
+        } else {
+            None
+        };
+
+        if solved.is_none() && infeasible.is_none() {
+            queue.push(
+                estimate(&initial_state, data, 0, None),
+                Node {
+                    state: initial_state,
+                    distance: 0,
+                    index: 0,
+                },
+            );
+        }
+
+        Self {
+            data,
+            accept,
+            observer,
+            expanded: 0,
+            closed,
+            parents,
+            queue,
+            peak_open: 0,
+            deferred: None,
+            solved,
+            infeasible,
+        }
+    }
+
+    /// Runs the search to completion.
+    pub(crate) fn finish(mut self) -> SolveReport<S::Action, S::Outcome> {
+        loop {
+            if let Some(report) = self.run(usize::MAX) {
+                return report;
+            }
+        }
+    }
+
+    /// Gives back the storage of a finished search, so another can reuse it.
+    pub(crate) fn into_parts(self) -> Parts<S, C> {
+        (self.closed, self.parents, self.queue)
+    }
+
+    pub(crate) fn expanded(&self) -> usize {
+        self.expanded
+    }
+
+    /// Expands up to `budget` states, returning a report if the search
+    /// finished along the way.
+    pub(crate) fn run(&mut self, budget: usize) -> Option<SolveReport<S::Action, S::Outcome>> {
+        if let Some(outcome) = self.solved.take() {
+            return Some(SolveReport {
+                solution: Some(Vec::new()),
+                outcome: Some(outcome),
+                expanded: 0,
+                memory: MemoryUsage::default(),
+                infeasible: None,
+            });
+        }
+
+        let Self {
+            data,
+            accept,
+            observer,
+            expanded,
+            closed,
+            parents,
+            queue,
+            peak_open,
+            deferred,
+            ..
+        } = self;
+        let data = *data;
+
+        // Pop states in priority order until empty
+        for _ in 0..budget {
+            let parent_node = match queue.pop() {
+                Some(node) => node,
+                None => return Some(self.report(None)),
+            };
+            let parent_index = parent_node.index;
+            let depth = parent_node.distance;
+            let parent_action = parent_index.checked_sub(1).map(|i| &parents[i].1);
+            if let Some((cost, ..)) = deferred {
+                let bound = S::Heuristic::default().combine(*cost);
+                let estimate = estimate(&parent_node.state, data, depth, parent_action);
+                if estimate >= bound {
+                    // The state goes back for whoever carries on searching
+                    queue.push(estimate, parent_node);
+                    return Some(self.report(None));
+                }
+            }
+
+            let solution = closed.visit(parent_node.state, |parent| {
+                *expanded += 1;
+                // The frontier doesn't hand back costs, so only observed
+                // searches pay for estimating expanded states again
+                if let Some(observer) = observer.as_mut() {
+                    let parent_action = parent_index.checked_sub(1).map(|i| &parents[i].1);
+                    let estimate = estimate(parent, data, depth, parent_action);
+                    observer.expand(depth, &estimate, parent);
+                }
+                // Every move is still queued after finding a solution, so the
+                // search can carry on looking for others
+                let mut found = None;
+                for (action, transition) in parent.transitions(data) {
+                    // The other order is searched from the state the parent
+                    // was reached from
+                    if parent_index > 0 && S::commutes(&parents[parent_index - 1].1, &action) {
+                        continue;
+                    }
+                    let step = S::action_cost(&action);
+                    let distance = depth.saturating_add(step);
+                    match transition {
+                        Transition::Indeterminate(state) => {
+                            let estimate = estimate(&state, data, distance, Some(&action));
+                            parents.push((parent_index, action));
+
+                            queue.push(
+                                estimate,
+                                Node {
+                                    state,
+                                    distance,
+                                    index: parents.len(),
+                                },
+                            );
+                        }
+                        Transition::Success(outcome) => {
+                            if !accept(&outcome) {
+                                continue;
+                            }
+
+                            // Nothing deferred can be cheaper than a single
+                            // step from the cheapest state left
+                            if step == 1 && found.is_none() {
+                                found = Some((parent_index, action, outcome));
+                                continue;
+                            }
+                            if deferred.as_ref().is_none_or(|&(cost, ..)| distance < cost) {
+                                *deferred = Some((distance, parent_index, action, outcome));
+                            }
+                        }
+                    }
+                }
+                found
+            });
+            *peak_open = (*peak_open).max(queue.len());
+
+            if let Some(Some(success)) = solution {
+                return Some(self.report(Some(success)));
+            }
+        }
+        None
+    }
+
+    /// Estimates the memory the search has used so far.
+    pub(crate) fn memory(&self) -> MemoryUsage {
+        MemoryUsage {
+            closed: self.closed.memory(),
+            open: self.peak_open * mem::size_of::<(S::Heuristic, Node<S>)>(),
+            parents: self.parents.capacity() * mem::size_of::<(usize, S::Action)>(),
+        }
+    }
+
+    /// Reports a finished search, falling back to the best deferred success.
+    fn report(
+        &mut self,
+        success: Option<(usize, S::Action, S::Outcome)>,
+    ) -> SolveReport<S::Action, S::Outcome> {
+        let memory = self.memory();
+        let success = success.or_else(|| {
+            self.deferred
+                .take()
+                .map(|(_, parent_index, action, outcome)| (parent_index, action, outcome))
+        });
+        match success {
+            Some((parent_index, action, outcome)) => SolveReport {
+                solution: Some(path_to(&self.parents, parent_index, action)),
+                outcome: Some(outcome),
+                expanded: self.expanded,
+                memory,
+                infeasible: None,
+            },
+            None => SolveReport {
+                solution: None,
+                outcome: None,
+                expanded: self.expanded,
+                memory,
+                infeasible: self.infeasible.take(),
+            },
+        }
+    }
+}
+
+/// Everything a saved search picks back up with.
+#[cfg(feature = "checkpoint")]
+type Saved<S, A, O> = (
+    usize,
+    HashClosedSet<S>,
+    Vec<(usize, A)>,
+    usize,
+    Option<(usize, usize, A, O)>,
+    Option<O>,
+    Option<String>,
+    Vec<Node<S>>,
+);
+
+#[cfg(feature = "checkpoint")]
+impl<'a, S, F> Search<'a, S, F>
+where
+    S: State + Serialize + DeserializeOwned,
+    S::Action: Serialize + DeserializeOwned,
+    S::Outcome: Serialize + DeserializeOwned,
+    F: FnMut(&S::Outcome) -> bool,
+{
+    /// Writes the whole search to `writer`, so `load` can carry on with it
+    /// later.
+    pub(crate) fn save(&mut self, writer: impl Write) -> bincode::Result<()> {
+        // The frontier can only be read by emptying it, so its states are
+        // taken out in order and put back once they're written
+        let mut open = Vec::with_capacity(self.queue.len());
+        while let Some(node) = self.queue.pop() {
+            open.push(node);
+        }
+        let saved = (
+            self.expanded,
+            &self.closed,
+            &self.parents,
+            self.peak_open,
+            &self.deferred,
+            &self.solved,
+            &self.infeasible,
+            &open,
+        );
+        let result = bincode::serialize_into(writer, &saved);
+        self.requeue(open);
+        result
+    }
+
+    /// Reads a search written by `save`. It has to be given the same data it
+    /// was started with.
+    pub(crate) fn load(
+        reader: impl Read,
+        data: &'a S::Data,
+        accept: F,
+        observer: Option<&'a mut dyn Observer<S>>,
+    ) -> bincode::Result<Self> {
+        let saved: Saved<S, S::Action, S::Outcome> = bincode::deserialize_from(reader)?;
+        let (expanded, closed, parents, peak_open, deferred, solved, infeasible, open) = saved;
+        let mut search = Self {
+            data,
+            accept,
+            observer,
+            expanded,
+            closed,
+            parents,
+            queue: Default::default(),
+            peak_open,
+            deferred,
+            solved,
+            infeasible,
+        };
+        search.requeue(open);
+        Ok(search)
+    }
+
+    /// Puts back states taken out of the frontier, given in the order they
+    /// came out. Pushing them in reverse keeps ties popping in the same order
+    /// as before.
+    fn requeue(&mut self, open: Vec<Node<S>>) {
+        for node in open.into_iter().rev() {
+            let parent_action = node.index.checked_sub(1).map(|i| &self.parents[i].1);
+            let estimate = estimate(&node.state, self.data, node.distance, parent_action);
+            self.queue.push(estimate, node);
+        }
+    }
+}
+
+/// Follows the parents of a node back to the initial state, collecting the
+/// actions along the way and finishing with `action`. The parents are left
+/// as they were, so more paths can be followed later.
+pub(crate) fn path_to<A: Clone>(parents: &[(usize, A)], parent_index: usize, action: A) -> Vec<A> {
+    let mut result_actions = vec![action];
+    let mut current_index = parent_index;
+    while current_index != 0 {
+        let (next_index, action) = &parents[current_index - 1];
+        result_actions.push(action.clone());
+        current_index = *next_index;
+    }
+    result_actions.reverse();
+    result_actions
+}
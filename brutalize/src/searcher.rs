@@ -0,0 +1,60 @@
+use crate::{
+    search::{AcceptAll, Search},
+    Observer, SolveReport, State,
+};
+
+/// What happened when a `Searcher` was stepped.
+pub enum StepResult<A, O> {
+    /// The search went on and hasn't finished yet.
+    Running,
+    /// The search finished, with or without a solution.
+    Done(SolveReport<A, O>),
+}
+
+/// A search driven by its caller one step at a time, for interleaving solving
+/// with other work like drawing frames or answering other requests. Each step
+/// takes the next state off the frontier and expands it if it hasn't been
+/// already.
+///
+/// Stepping on after a solution is reported keeps looking for others, each
+/// no shorter than the last. Once nothing is left to search every step
+/// reports `Done` without a solution.
+pub struct Searcher<'a, S: State> {
+    search: Search<'a, S, AcceptAll<S>>,
+}
+
+impl<'a, S: State> Searcher<'a, S> {
+    pub fn new(initial_state: S, data: &'a S::Data) -> Self {
+        Self {
+            search: Search::new(initial_state, data, |_| true, None),
+        }
+    }
+
+    /// Like `new`, but `observer` is told about every state as it's expanded.
+    pub fn observed(
+        initial_state: S,
+        data: &'a S::Data,
+        observer: &'a mut dyn Observer<S>,
+    ) -> Self {
+        Self {
+            search: Search::new(initial_state, data, |_| true, Some(observer)),
+        }
+    }
+
+    /// How many states have been expanded so far.
+    pub fn expanded(&self) -> usize {
+        self.search.expanded()
+    }
+
+    pub fn step(&mut self) -> StepResult<S::Action, S::Outcome> {
+        self.run(1)
+    }
+
+    /// Takes up to `steps` steps, stopping early if the search finishes.
+    pub fn run(&mut self, steps: usize) -> StepResult<S::Action, S::Outcome> {
+        match self.search.run(steps) {
+            Some(report) => StepResult::Done(report),
+            None => StepResult::Running,
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use crate::{Searcher, SolveReport, State, StepResult};
+use std::{
+    fmt,
+    io::{self, Write},
+};
+
+/// Something that happened during a search, as streamed by
+/// `solve_to_writer`. Each event is displayed as a single line of
+/// space-separated words starting with its kind:
+///
+/// - `solution LENGTH ACTION...` when a better solution is found
+/// - `progress EXPANDED` every so often while searching
+/// - `finished EXPANDED solved` or `finished EXPANDED unsolved` at the end
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SolveEvent<'a, A> {
+    /// A solution shorter than any found before.
+    SolutionImproved(&'a [A]),
+    /// How many states have been expanded so far.
+    Progress(usize),
+    /// The search is over, after expanding this many states.
+    Finished { expanded: usize, solved: bool },
+}
+
+impl<A: fmt::Display> fmt::Display for SolveEvent<'_, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveEvent::SolutionImproved(solution) => {
+                write!(f, "solution {}", solution.len())?;
+                for action in solution.iter() {
+                    write!(f, " {}", action)?;
+                }
+                Ok(())
+            }
+            SolveEvent::Progress(expanded) => write!(f, "progress {}", expanded),
+            SolveEvent::Finished { expanded, solved } => write!(
+                f,
+                "finished {} {}",
+                expanded,
+                if *solved { "solved" } else { "unsolved" }
+            ),
+        }
+    }
+}
+
+/// Finds the shortest solution like `solve_with_report`, writing each
+/// `SolveEvent` to `writer` as a line as it happens. Progress is written
+/// after every `progress_every` expansions. Since the first solution found is
+/// already the shortest, at most one `solution` line comes before `finished`.
+pub fn solve_to_writer<S: State>(
+    initial_state: S,
+    data: &S::Data,
+    progress_every: usize,
+    mut writer: impl Write,
+) -> io::Result<SolveReport<S::Action, S::Outcome>>
+where
+    S::Action: fmt::Display,
+{
+    let mut searcher = Searcher::new(initial_state, data);
+    let report = loop {
+        match searcher.run(progress_every.max(1)) {
+            StepResult::Running => {
+                let progress: SolveEvent<S::Action> = SolveEvent::Progress(searcher.expanded());
+                writeln!(writer, "{}", progress)?;
+                writer.flush()?;
+            }
+            StepResult::Done(report) => break report,
+        }
+    };
+
+    if let Some(solution) = &report.solution {
+        writeln!(writer, "{}", SolveEvent::SolutionImproved(solution))?;
+    }
+    let finished: SolveEvent<S::Action> = SolveEvent::Finished {
+        expanded: report.expanded,
+        solved: report.solution.is_some(),
+    };
+    writeln!(writer, "{}", finished)?;
+    writer.flush()?;
+    Ok(report)
+}
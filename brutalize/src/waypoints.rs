@@ -0,0 +1,252 @@
+use crate::{solve_for, HeuristicContext, State, Transition};
+use alloc::{string::String, vec::Vec};
+use core::{
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
+
+/// A game whose puzzles can require visiting ordered sub-goals before being
+/// solved, such as stepping on an altar before returning to the start.
+pub trait Waypoints: State + Clone {
+    /// The number of waypoints declared by the puzzle.
+    fn waypoint_count(data: &Self::Data) -> usize;
+    /// Whether this state satisfies the waypoint with the given index.
+    fn reaches_waypoint(&self, data: &Self::Data, index: usize) -> bool;
+}
+
+fn advance<S: Waypoints>(state: &S, data: &S::Data, mut next: usize) -> usize {
+    while next < S::waypoint_count(data) && state.reaches_waypoint(data, next) {
+        next += 1;
+    }
+    next
+}
+
+/// A move in a search tracking waypoint progress, noting whether it reached
+/// the next waypoint.
+#[derive(Clone)]
+struct Step<A> {
+    action: A,
+    advanced: bool,
+}
+
+/// A state paired with the index of the next waypoint it has to reach.
+struct Progress<'a, S: Waypoints> {
+    state: S,
+    next: usize,
+    _data: PhantomData<&'a S::Data>,
+}
+
+impl<'a, S: Waypoints> PartialEq for Progress<'a, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.next == other.next && self.state == other.state
+    }
+}
+
+impl<'a, S: Waypoints> Eq for Progress<'a, S> {}
+
+impl<'a, S: Waypoints> Hash for Progress<'a, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.next.hash(state);
+        self.state.hash(state);
+    }
+}
+
+impl<'a, S: Waypoints> State for Progress<'a, S> {
+    type Data = &'a S::Data;
+    type Action = Step<S::Action>;
+    type Outcome = S::Outcome;
+    type Transitions = Vec<(Self::Action, Transition<Self>)>;
+    type Heuristic = S::Heuristic;
+
+    const HAS_HEURISTIC: bool = S::HAS_HEURISTIC;
+
+    fn transitions(&self, data: &Self::Data) -> Self::Transitions {
+        let count = S::waypoint_count(data);
+        let mut result = Vec::new();
+        for (action, transition) in self.state.transitions(data) {
+            match transition {
+                Transition::Indeterminate(state) => {
+                    let next = advance(&state, data, self.next);
+                    let step = Step {
+                        action,
+                        advanced: next > self.next,
+                    };
+                    result.push((
+                        step,
+                        Transition::Indeterminate(Progress {
+                            state,
+                            next,
+                            _data: PhantomData,
+                        }),
+                    ));
+                }
+                // Solving the puzzle before visiting every waypoint is a dead end
+                Transition::Success(outcome) if self.next == count => {
+                    let step = Step {
+                        action,
+                        advanced: false,
+                    };
+                    result.push((step, Transition::Success(outcome)));
+                }
+                Transition::Success(_) => (),
+            }
+        }
+        result
+    }
+
+    fn action_cost(step: &Self::Action) -> usize {
+        S::action_cost(&step.action)
+    }
+
+    fn commutes(first: &Self::Action, second: &Self::Action) -> bool {
+        // Taking the moves the other way round reaches the same state with at
+        // least as much progress, unless the first move is what reached a
+        // waypoint
+        !first.advanced && S::commutes(&first.action, &second.action)
+    }
+
+    fn is_goal(&self, data: &Self::Data) -> Option<Self::Outcome> {
+        if self.next == S::waypoint_count(data) {
+            self.state.is_goal(data)
+        } else {
+            None
+        }
+    }
+
+    fn heuristic(&self, data: &Self::Data) -> Self::Heuristic {
+        // Detours through waypoints only lengthen the path to the goal, so the
+        // game's heuristic remains admissible.
+        self.state.heuristic(data)
+    }
+
+    fn heuristic_ctx(
+        &self,
+        data: &Self::Data,
+        context: &HeuristicContext<'_, Self::Action>,
+    ) -> Self::Heuristic {
+        let context = HeuristicContext {
+            depth: context.depth,
+            parent_action: context.parent_action.map(|step| &step.action),
+        };
+        self.state.heuristic_ctx(data, &context)
+    }
+
+    fn infeasible(&self, data: &Self::Data) -> Option<String> {
+        self.state.infeasible(data)
+    }
+}
+
+/// A state which succeeds as soon as it reaches one particular waypoint.
+struct Leg<'a, S: Waypoints> {
+    state: S,
+    _data: PhantomData<&'a S::Data>,
+}
+
+impl<'a, S: Waypoints> PartialEq for Leg<'a, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.state == other.state
+    }
+}
+
+impl<'a, S: Waypoints> Eq for Leg<'a, S> {}
+
+impl<'a, S: Waypoints> Hash for Leg<'a, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.state.hash(state);
+    }
+}
+
+impl<'a, S: Waypoints> State for Leg<'a, S> {
+    type Data = (&'a S::Data, usize);
+    type Action = S::Action;
+    type Outcome = (S, usize);
+    type Transitions = Vec<(S::Action, Transition<Self>)>;
+    // The game's heuristic estimates the distance to the goal, not to a
+    // waypoint, so legs are searched without one.
+    type Heuristic = usize;
+
+    const HAS_HEURISTIC: bool = false;
+
+    fn transitions(&self, &(data, index): &Self::Data) -> Self::Transitions {
+        let mut result = Vec::new();
+        for (action, transition) in self.state.transitions(data) {
+            if let Transition::Indeterminate(state) = transition {
+                if state.reaches_waypoint(data, index) {
+                    let next = advance(&state, data, index + 1);
+                    result.push((action, Transition::Success((state, next))));
+                } else {
+                    result.push((
+                        action,
+                        Transition::Indeterminate(Leg {
+                            state,
+                            _data: PhantomData,
+                        }),
+                    ));
+                }
+            }
+        }
+        result
+    }
+
+    fn action_cost(action: &Self::Action) -> usize {
+        S::action_cost(action)
+    }
+
+    fn commutes(first: &Self::Action, second: &Self::Action) -> bool {
+        // Legs end at the first state on the waypoint, so the other order
+        // either gets there sooner or reaches the same state
+        S::commutes(first, second)
+    }
+
+    fn infeasible(&self, &(data, _): &Self::Data) -> Option<String> {
+        // Nothing reached from here can solve the puzzle, waypoint or not
+        self.state.infeasible(data)
+    }
+
+    fn pack(&self) -> Option<u128> {
+        self.state.pack()
+    }
+}
+
+/// Solves a puzzle that requires visiting its waypoints in order before
+/// reaching the goal.
+///
+/// With `global` set, the search tracks waypoint progress as part of the
+/// state and finds the shortest solution overall. Otherwise each waypoint is
+/// solved for in turn starting from where the previous one was reached, which
+/// is much cheaper but only optimal for each leg individually.
+pub fn solve_waypoints<S: Waypoints>(
+    initial_state: S,
+    data: &S::Data,
+    global: bool,
+) -> Option<Vec<S::Action>> {
+    let next = advance(&initial_state, data, 0);
+
+    if global {
+        let initial = Progress {
+            state: initial_state,
+            next,
+            _data: PhantomData,
+        };
+        let solution = crate::solve(initial, &data)?;
+        return Some(solution.into_iter().map(|step| step.action).collect());
+    }
+
+    let mut actions = Vec::new();
+    let mut state = initial_state;
+    let mut next = next;
+    while next < S::waypoint_count(data) {
+        let leg = Leg {
+            state,
+            _data: PhantomData,
+        };
+        let report = solve_for(leg, &(data, next), |_| true);
+        actions.extend(report.solution?);
+        let (reached, after) = report.outcome?;
+        state = reached;
+        next = after;
+    }
+
+    actions.extend(crate::solve(state, data)?);
+    Some(actions)
+}
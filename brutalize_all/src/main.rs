@@ -0,0 +1,8 @@
+use brutalize_cli::Game;
+
+fn main() {
+    brutalize_cli::execute_registry(&[
+        Game::new::<anima::State>("anima"),
+        Game::new::<sausage::State>("sausage"),
+    ]);
+}
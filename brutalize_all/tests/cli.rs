@@ -0,0 +1,384 @@
+use std::{
+    env, fs,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+#[test]
+fn solve_already_solved() {
+    let path = env::temp_dir().join(format!("brutalize_solved_{}.txt", std::process::id()));
+    fs::write(&path, "game anima\nr.\n\nR 0 0\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_brutalize_all"))
+        .arg(&path)
+        .output()
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(output.status.success());
+    assert!(
+        stdout.contains("Found solution of length 0:\n"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn report_failed_initial_state() {
+    let path = env::temp_dir().join(format!("brutalize_failed_{}.txt", std::process::id()));
+    fs::write(
+        &path,
+        "game sausage\npuzzle 3 2\n...\n   \nstart 0 1 right\nsausages 1\n1 0 horizontal\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_brutalize_all"))
+        .arg(&path)
+        .output()
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("initial state failed: sausage 0 off the island"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn report_exceeded_budget() {
+    let path = env::temp_dir().join(format!("brutalize_budget_{}.txt", std::process::id()));
+    fs::write(&path, "game anima\nr...\n\nR 3 0\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_brutalize_all"))
+        .args(["--budget", "1"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("gave up after expanding 1 states"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn reject_malformed_budget() {
+    let output = Command::new(env!("CARGO_BIN_EXE_brutalize_all"))
+        .args(["--budget", "10k", "puzzle.txt"])
+        .output()
+        .unwrap();
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr.contains("invalid value '10k' for --budget"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn report_partial_result_on_timeout() {
+    let path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../puzzles/anima/3.14-antiparticle.txt"
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_brutalize_all"))
+        .args(["--game", "anima", "--per-puzzle-timeout", "0"])
+        .arg(path)
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(!output.status.success());
+    assert!(stderr.contains("gave up after 0.000s"), "{}", stderr);
+    assert!(stdout.contains("Explored to depth "), "{}", stdout);
+    assert!(stdout.contains("Closest state: heuristic "), "{}", stdout);
+}
+
+#[test]
+fn solve_demos() {
+    let output = Command::new(env!("CARGO_BIN_EXE_brutalize_all"))
+        .arg("--demo")
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(output.status.success());
+    assert!(stdout.contains("anima:\n"), "{}", stdout);
+    assert!(stdout.contains("sausage:\n"), "{}", stdout);
+    assert_eq!(stdout.matches("Found solution").count(), 2, "{}", stdout);
+}
+
+#[test]
+fn write_solution_files() {
+    let dir = env::temp_dir().join(format!("brutalize_sol_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("line.txt");
+    fs::write(&path, "game anima\nr...\n\nR 3 0\n").unwrap();
+
+    let run = |extra: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_brutalize_all"))
+            .arg("--solution-out")
+            .args(extra)
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let stdout = run(&[]);
+    assert!(stdout.contains("Found solution"), "{}", stdout);
+    assert_eq!(fs::read_to_string(dir.join("line.sol")).unwrap(), "LLL\n");
+
+    let stdout = run(&[]);
+    assert!(stdout.contains("skipped"), "{}", stdout);
+
+    let stdout = run(&["--force"]);
+    assert!(stdout.contains("Found solution"), "{}", stdout);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn beam_search() {
+    let path = env::temp_dir().join(format!("brutalize_beam_{}.txt", std::process::id()));
+    fs::write(&path, "game anima\nr...\n\nR 3 0\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_brutalize_all"))
+        .args(["--beam", "1"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(output.status.success());
+    assert!(
+        stdout.contains("Found solution of length 3 (may not be the shortest):\n"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn solve_through_waypoints() {
+    let path = env::temp_dir().join(format!("brutalize_waypoints_{}.txt", std::process::id()));
+    fs::write(&path, "game anima\n..r\n...\n\nwaypoint 0 1\nR 1 0\n").unwrap();
+
+    for (extra, found) in [
+        (
+            &[][..],
+            "Found solution of length 4 through the waypoints:\n",
+        ),
+        (
+            &["--waypoint-legs"][..],
+            "Found solution of length 4 through the waypoints (may not be the shortest):\n",
+        ),
+    ] {
+        let output = Command::new(env!("CARGO_BIN_EXE_brutalize_all"))
+            .args(extra)
+            .arg(&path)
+            .output()
+            .unwrap();
+
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(output.status.success());
+        assert!(stdout.contains(found), "{}", stdout);
+    }
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn compare_algorithms() {
+    let path = env::temp_dir().join(format!("brutalize_compare_{}.txt", std::process::id()));
+    fs::write(&path, "game anima\nr...\n\nR 3 0\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_brutalize_all"))
+        .arg("--compare")
+        .arg(&path)
+        .output()
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(output.status.success());
+    assert!(
+        stdout.contains("Algorithm            Time   Expanded     Memory   Length\n"),
+        "{}",
+        stdout
+    );
+    let dijkstra = stdout.lines().find(|l| l.starts_with("Dijkstra")).unwrap();
+    assert!(dijkstra.ends_with(" KiB        3"), "{}", stdout);
+}
+
+#[test]
+fn check_unreadable_solution() {
+    let path = env::temp_dir().join(format!("brutalize_check_{}.txt", std::process::id()));
+    fs::write(&path, "game anima\nr...\n\nR 3 0\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_brutalize_all"))
+        .args(["--check", "LLQ"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains("Can't read solution 'LLQ': unknown action 'Q'\n"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn html_walkthrough() {
+    let path = env::temp_dir().join(format!("brutalize_html_{}.txt", std::process::id()));
+    let html_path = path.with_extension("html");
+    fs::write(&path, "game anima\nr..\n\nR 2 0\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_brutalize_all"))
+        .arg("--html-out")
+        .arg(&html_path)
+        .arg(&path)
+        .output()
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(output.status.success());
+
+    let html = fs::read_to_string(&html_path).unwrap();
+    fs::remove_file(&html_path).unwrap();
+    assert_eq!(html.matches("<div class=\"step\">").count(), 3);
+    assert!(html.contains("<h2>1. Left</h2>"), "{}", html);
+    assert!(html.contains("<td style=\"background: #d22\">R</td>"));
+    assert!(html.contains("<p>Solved!</p>"));
+}
+
+#[test]
+fn plan_before_solving() {
+    let path = env::temp_dir().join(format!("brutalize_plan_{}.txt", std::process::id()));
+    fs::write(&path, "game anima\nr...\n\nR 3 0\n").unwrap();
+
+    let run = |extra: &[&str], answer: &str| {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_brutalize_all"))
+            .arg("--plan")
+            .args(extra)
+            .arg(&path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(answer.as_bytes())
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let stdout = run(&[], "n\n");
+    assert!(stdout.contains("proceed? [y/N]"), "{}", stdout);
+    assert!(stdout.contains("skipped"), "{}", stdout);
+    assert!(!stdout.contains("Found solution"), "{}", stdout);
+
+    let stdout = run(&[], "y\n");
+    assert!(stdout.contains("Found solution of length 3"), "{}", stdout);
+
+    let stdout = run(&["--yes"], "");
+    assert!(stdout.contains("with current settings\n"), "{}", stdout);
+    assert!(stdout.contains("Found solution of length 3"), "{}", stdout);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn summarize_mixed_games() {
+    let dir = env::temp_dir().join(format!("brutalize_summary_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("a.txt"), "game anima\nr...\n\nR 3 0\n").unwrap();
+    fs::write(dir.join("b.txt"), "game anima\nr.\n\nR 1 0\n").unwrap();
+    fs::write(dir.join("c.txt"), "r.\n\nR 1 0\n").unwrap();
+    fs::write(
+        dir.join("d.txt"),
+        "game sausage\npuzzle 3 2\n...\n   \nstart 0 1 right\nsausages 1\n1 0 horizontal\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_brutalize_all"))
+        .arg("--summary")
+        .arg(&dir)
+        .output()
+        .unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!output.status.success());
+    assert!(
+        stdout.contains(&format!(
+            "anima: 2 puzzle(s), 2 solved, average length 2.0, longest 3 ({})\n",
+            dir.join("a.txt").display()
+        )),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("sausage: 1 puzzle(s), 0 solved\n"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("?: 1 puzzle(s), 0 solved\n"), "{}", stdout);
+    assert!(stdout.contains("c.txt: game\n"), "{}", stdout);
+    assert!(stdout.contains("d.txt: invalid\n"), "{}", stdout);
+}
+
+#[test]
+fn json_with_states() {
+    let path = env::temp_dir().join(format!("brutalize_json_{}.txt", std::process::id()));
+    fs::write(&path, "game anima\nr..\n\nR 2 0\n").unwrap();
+
+    let run = |extra: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_brutalize_all"))
+            .arg("--json")
+            .args(extra)
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let stdout = run(&[]);
+    assert_eq!(
+        stdout,
+        "{\"solution\":[\"Left\",\"Left\"],\"notation\":\"LL\",\"expanded\":2}\n"
+    );
+
+    // Whether states are filled in depends on the game's json feature, but
+    // every step is listed either way
+    let stdout = run(&["--include-states"]);
+    fs::remove_file(&path).unwrap();
+    assert!(
+        stdout.contains("\"steps\":[{\"action\":null,\"state\":"),
+        "{}",
+        stdout
+    );
+    assert_eq!(stdout.matches("\"action\":").count(), 3, "{}", stdout);
+    assert!(
+        stdout.contains("{\"action\":\"Left\",\"state\":null}]"),
+        "{}",
+        stdout
+    );
+}
@@ -0,0 +1,86 @@
+use brutalize::{HashClosedSet, ShardedClosedSet};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+/// Levels to bench, by their name and the bundled puzzle they load.
+const SAUSAGE_LEVELS: &[(&str, &str)] = &[
+    ("seafinger", "sausage/seafinger"),
+    ("fiery_jut", "sausage/fiery_jut"),
+    ("eastreach", "sausage/eastreach"),
+    ("southjaunt", "sausage/southjaunt"),
+    ("comely_hearth", "sausage/comely_hearth"),
+];
+
+const ANIMA_LEVELS: &[(&str, &str)] = &[
+    ("spiral", "anima/1.3-spiral"),
+    ("square_dance", "anima/2.5-square_dance"),
+    ("deadlock", "anima/3.4-deadlock"),
+    ("fractal", "anima/3.9-fractal"),
+    ("free_radical", "anima/3.12-free_radical"),
+];
+
+fn bench_levels<S: brutalize_cli::State>(c: &mut Criterion, game: &str, levels: &[(&str, &str)]) {
+    let mut group = c.benchmark_group(game);
+
+    for (name, puzzle) in levels {
+        let puzzle = brutalize_puzzles::load(puzzle).unwrap();
+        let (initial_state, data) = S::parse(&puzzle).unwrap();
+
+        // Node counts are deterministic, so they only need to be reported once
+        // per level rather than measured.
+        let report = brutalize::solve_with_report(initial_state.clone(), &data);
+        println!(
+            "{}/{}: expanded {} nodes, solution length {:?}",
+            game,
+            name,
+            report.expanded,
+            report.solution.map(|s| s.len()),
+        );
+
+        group.bench_function(*name, |b| {
+            b.iter(|| brutalize::solve(black_box(&initial_state).clone(), &data))
+        });
+    }
+
+    group.finish();
+}
+
+/// Compares the monolithic closed set against the sharded one on the same
+/// levels.
+fn bench_closed_sets<S: brutalize_cli::State>(c: &mut Criterion, levels: &[(&str, &str)]) {
+    let mut group = c.benchmark_group("closed");
+
+    for (name, puzzle) in levels {
+        let puzzle = brutalize_puzzles::load(puzzle).unwrap();
+        let (initial_state, data) = S::parse(&puzzle).unwrap();
+
+        group.bench_function(format!("{}/hash", name), |b| {
+            b.iter(|| {
+                brutalize::solve_with_closed(
+                    black_box(&initial_state).clone(),
+                    &data,
+                    HashClosedSet::default(),
+                )
+            })
+        });
+        group.bench_function(format!("{}/sharded", name), |b| {
+            b.iter(|| {
+                brutalize::solve_with_closed(
+                    black_box(&initial_state).clone(),
+                    &data,
+                    ShardedClosedSet::default(),
+                )
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    bench_levels::<sausage::State>(c, "sausage", SAUSAGE_LEVELS);
+    bench_levels::<anima::State>(c, "anima", ANIMA_LEVELS);
+    bench_closed_sets::<sausage::State>(c, SAUSAGE_LEVELS);
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
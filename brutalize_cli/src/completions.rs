@@ -0,0 +1,452 @@
+use std::{env, fmt::Write, path::Path};
+
+/// A command line option, used to generate shell completions and man pages.
+pub(crate) struct Opt {
+    pub flag: &'static str,
+    pub value: Option<&'static str>,
+    pub help: &'static str,
+}
+
+pub(crate) const SOLVE_OPTIONS: &[Opt] = &[
+    Opt {
+        flag: "-v",
+        value: None,
+        help: "Print states along with solutions",
+    },
+    Opt {
+        flag: "-q",
+        value: None,
+        help: "Do not print solutions",
+    },
+    Opt {
+        flag: "--stats",
+        value: None,
+        help: "Print branching and per-action pruning statistics",
+    },
+    Opt {
+        flag: "--check-heuristic",
+        value: None,
+        help: "Report states on the solution where the heuristic overestimates",
+    },
+    Opt {
+        flag: "--demo",
+        value: None,
+        help: "Solve a bundled example puzzle",
+    },
+    Opt {
+        flag: "--solution-out",
+        value: None,
+        help: "Write each solution to a .sol file beside its puzzle",
+    },
+    Opt {
+        flag: "--solution-dir",
+        value: Some("DIR"),
+        help: "Write each solution to a .sol file in DIR",
+    },
+    Opt {
+        flag: "--force",
+        value: None,
+        help: "Solve puzzles again even if their .sol file exists",
+    },
+    Opt {
+        flag: "--index",
+        value: Some("FILE"),
+        help: "Solve every puzzle under the given paths and write their lengths to FILE",
+    },
+    Opt {
+        flag: "--diff-index",
+        value: Some("FILE"),
+        help: "With --index, print how the results changed since FILE",
+    },
+    Opt {
+        flag: "--watch",
+        value: None,
+        help: "Re-solve puzzles whenever their files change",
+    },
+    Opt {
+        flag: "--bound",
+        value: None,
+        help: "Only print a lower bound on the solution length",
+    },
+    Opt {
+        flag: "--difficulty",
+        value: None,
+        help: "Score how hard the puzzle is from its optimal solutions",
+    },
+    Opt {
+        flag: "--compare",
+        value: None,
+        help: "Run every search algorithm and compare their results",
+    },
+    Opt {
+        flag: "--beam",
+        value: Some("WIDTH"),
+        help: "Keep only the WIDTH best states per depth, finding a solution that may not be the shortest",
+    },
+    Opt {
+        flag: "--beam-depth",
+        value: Some("DEPTH"),
+        help: "With --beam, only avoid stepping straight back instead of remembering every state, stopping at DEPTH",
+    },
+    Opt {
+        flag: "--waypoint-legs",
+        value: None,
+        help: "Solve puzzles with waypoints one leg at a time, which is faster but may not find the shortest solution",
+    },
+    Opt {
+        flag: "--json",
+        value: None,
+        help: "Print each result as a line of JSON",
+    },
+    Opt {
+        flag: "--include-states",
+        value: None,
+        help: "With --json, list every step of the solution along with the state it leads to",
+    },
+    Opt {
+        flag: "--estimate",
+        value: None,
+        help: "Project how many states solving would take from random walks",
+    },
+    Opt {
+        flag: "--plan",
+        value: None,
+        help: "Project the states, memory and time solving will take and ask before starting",
+    },
+    Opt {
+        flag: "--yes",
+        value: None,
+        help: "Start solving after --plan without asking",
+    },
+    Opt {
+        flag: "--check",
+        value: Some("SOLUTION"),
+        help: "Check whether a solution in the game's notation solves the puzzle",
+    },
+    Opt {
+        flag: "--parse-only",
+        value: None,
+        help: "Check the puzzle and print its initial state",
+    },
+    Opt {
+        flag: "--render",
+        value: None,
+        help: "Print the initial state with a legend",
+    },
+    Opt {
+        flag: "--fancy",
+        value: None,
+        help: "Draw boards with emoji when the terminal supports UTF-8",
+    },
+    Opt {
+        flag: "--html-out",
+        value: Some("PATH"),
+        help: "Write an HTML page stepping through the solution",
+    },
+    Opt {
+        flag: "--render-out",
+        value: Some("DIR"),
+        help: "Write SVG and PNG images of the initial state to a directory",
+    },
+    Opt {
+        flag: "--gif-out",
+        value: Some("PATH"),
+        help: "Write a GIF animating the solution",
+    },
+    Opt {
+        flag: "--frame-delay",
+        value: Some("MS"),
+        help: "How long each frame of --gif-out is shown, 500 by default",
+    },
+    Opt {
+        flag: "--serve",
+        value: Some("ADDRESS"),
+        help: "Solve puzzles sent over TCP within --budget and --timeout, replying with JSON",
+    },
+    Opt {
+        flag: "--metrics",
+        value: Some("ADDRESS"),
+        help: "Serve Prometheus metrics about --serve requests over HTTP",
+    },
+    Opt {
+        flag: "--budget",
+        value: Some("STATES"),
+        help: "Give up after expanding this many states",
+    },
+    Opt {
+        flag: "--timeout",
+        value: Some("SECONDS"),
+        help: "Give up after searching for this long",
+    },
+    Opt {
+        flag: "--per-puzzle-timeout",
+        value: Some("SECONDS"),
+        help: "Give up on each puzzle after this long and show the closest state reached",
+    },
+    Opt {
+        flag: "--trace",
+        value: Some("PATH"),
+        help: "Write a trace of every expanded state to a file",
+    },
+    Opt {
+        flag: "--trace-csv",
+        value: Some("PATH"),
+        help: "Print the states expanded per depth in a trace as CSV",
+    },
+];
+
+pub(crate) const GAME_OPTION: Opt = Opt {
+    flag: "--game",
+    value: Some("GAME"),
+    help: "The game for puzzles without a header line",
+};
+
+pub(crate) const SUMMARY_OPTION: Opt = Opt {
+    flag: "--summary",
+    value: None,
+    help: "Solve every puzzle and print stats for each game instead of each solution",
+};
+
+const GENERATE_OPTIONS: &[Opt] = &[
+    Opt {
+        flag: "--generate-completions",
+        value: Some("SHELL"),
+        help: "Print a shell completion script",
+    },
+    Opt {
+        flag: "--generate-man",
+        value: None,
+        help: "Print a man page",
+    },
+];
+
+const SHELLS: &[&str] = &["bash", "zsh", "fish"];
+
+/// The values an option accepts, if they can be listed.
+fn choices<'a>(opt: &Opt, games: &'a [&'a str]) -> Option<&'a [&'a str]> {
+    match opt.flag {
+        "--game" => Some(games),
+        "--generate-completions" => Some(SHELLS),
+        _ => None,
+    }
+}
+
+fn bash(program: &str, options: &[&Opt], games: &[&str]) -> String {
+    let function = format!("_{}", program.replace('-', "_"));
+    let flags = options.iter().map(|o| o.flag).collect::<Vec<_>>();
+
+    let mut result = String::new();
+    writeln!(result, "{}() {{", function).unwrap();
+    writeln!(result, "    local cur=\"${{COMP_WORDS[COMP_CWORD]}}\"").unwrap();
+    writeln!(result, "    local prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"").unwrap();
+    writeln!(result, "    case \"$prev\" in").unwrap();
+    for opt in options.iter() {
+        if let Some(choices) = choices(opt, games) {
+            writeln!(
+                result,
+                "        {}) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return ;;",
+                opt.flag,
+                choices.join(" ")
+            )
+            .unwrap();
+        }
+    }
+    writeln!(result, "    esac").unwrap();
+    writeln!(result, "    if [[ \"$cur\" == -* ]]; then").unwrap();
+    writeln!(
+        result,
+        "        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))",
+        flags.join(" ")
+    )
+    .unwrap();
+    writeln!(result, "    else").unwrap();
+    writeln!(result, "        COMPREPLY=($(compgen -f -- \"$cur\"))").unwrap();
+    writeln!(result, "    fi").unwrap();
+    writeln!(result, "}}").unwrap();
+    writeln!(result, "complete -o filenames -F {} {}", function, program).unwrap();
+    result
+}
+
+fn zsh(program: &str, options: &[&Opt], games: &[&str]) -> String {
+    let mut result = String::new();
+    writeln!(result, "#compdef {}", program).unwrap();
+    writeln!(result, "_arguments \\").unwrap();
+    for opt in options.iter() {
+        let help = opt.help.replace('\'', "'\\''");
+        match (opt.value, choices(opt, games)) {
+            (Some(value), Some(choices)) => writeln!(
+                result,
+                "    '{}[{}]:{}:({})' \\",
+                opt.flag,
+                help,
+                value.to_lowercase(),
+                choices.join(" ")
+            ),
+            (Some(value), None) => writeln!(
+                result,
+                "    '{}[{}]:{}:' \\",
+                opt.flag,
+                help,
+                value.to_lowercase()
+            ),
+            (None, _) => writeln!(result, "    '{}[{}]' \\", opt.flag, help),
+        }
+        .unwrap();
+    }
+    writeln!(result, "    '*:puzzle:_files'").unwrap();
+    result
+}
+
+fn fish(program: &str, options: &[&Opt], games: &[&str]) -> String {
+    let mut result = String::new();
+    for opt in options.iter() {
+        let name = match opt.flag.strip_prefix("--") {
+            Some(long) => format!("-l {}", long),
+            None => format!("-s {}", &opt.flag[1..]),
+        };
+        write!(result, "complete -c {} {}", program, name).unwrap();
+        if let Some(choices) = choices(opt, games) {
+            write!(result, " -x -a '{}'", choices.join(" ")).unwrap();
+        } else if opt.value.is_some() {
+            write!(result, " -r").unwrap();
+        }
+        writeln!(result, " -d '{}'", opt.help.replace('\'', "\\'")).unwrap();
+    }
+    result
+}
+
+fn man(program: &str, options: &[&Opt], games: &[&str]) -> String {
+    fn escape(s: &str) -> String {
+        s.replace('\\', "\\\\").replace('-', "\\-")
+    }
+
+    let mut result = String::new();
+    writeln!(result, ".TH {} 1", escape(&program.to_uppercase())).unwrap();
+    writeln!(result, ".SH NAME").unwrap();
+    writeln!(result, "{} \\- brute-force puzzle solver", escape(program)).unwrap();
+    writeln!(result, ".SH SYNOPSIS").unwrap();
+    writeln!(result, ".B {}", escape(program)).unwrap();
+    writeln!(result, "[\\fIOPTIONS\\fR] \\fIPATHS\\fR...").unwrap();
+    writeln!(result, ".SH DESCRIPTION").unwrap();
+    writeln!(
+        result,
+        "Solves each puzzle file in \\fIPATHS\\fR and prints the shortest solution."
+    )
+    .unwrap();
+    writeln!(result, ".SH OPTIONS").unwrap();
+    for opt in options.iter() {
+        writeln!(result, ".TP").unwrap();
+        match opt.value {
+            Some(value) => writeln!(result, ".B {} \\fI{}\\fR", escape(opt.flag), value),
+            None => writeln!(result, ".B {}", escape(opt.flag)),
+        }
+        .unwrap();
+        write!(result, "{}", escape(opt.help)).unwrap();
+        match choices(opt, games) {
+            Some(choices) => writeln!(result, " ({}).", escape(&choices.join(", "))),
+            None => writeln!(result, "."),
+        }
+        .unwrap();
+    }
+    result
+}
+
+/// Prints the usage message listing every option.
+pub(crate) fn print_usage(options: &[&Opt], games: &[&str]) {
+    let options = options
+        .iter()
+        .cloned()
+        .chain(GENERATE_OPTIONS.iter())
+        .collect::<Vec<_>>();
+    let names = options
+        .iter()
+        .map(|o| match o.value {
+            Some(value) => format!("{} {}", o.flag, value),
+            None => o.flag.to_string(),
+        })
+        .collect::<Vec<_>>();
+    let width = names.iter().map(String::len).max().unwrap_or(0);
+
+    println!("Usage: {} [OPTIONS] PATHS", env::args().next().unwrap());
+    for (name, opt) in names.iter().zip(options.iter()) {
+        match choices(opt, games) {
+            Some(choices) => println!(
+                "  {:width$}  {} ({})",
+                name,
+                opt.help,
+                choices.join(", "),
+                width = width
+            ),
+            None => println!("  {:width$}  {}", name, opt.help, width = width),
+        }
+    }
+    println!(
+        "  {:width$}  A list of paths to problem files",
+        "PATHS",
+        width = width
+    );
+}
+
+/// Handles `--generate-completions SHELL` and `--generate-man`, printing the
+/// requested script. Returns whether the arguments asked for one.
+pub(crate) fn generate(args: &[String], options: &[&Opt], games: &[&str]) -> bool {
+    let program = env::args()
+        .next()
+        .as_deref()
+        .and_then(|p| Path::new(p).file_name())
+        .and_then(|p| p.to_str())
+        .map(str::to_string)
+        .unwrap_or_default();
+    let options = options
+        .iter()
+        .cloned()
+        .chain(GENERATE_OPTIONS.iter())
+        .collect::<Vec<_>>();
+
+    match args.first().map(String::as_str) {
+        Some("--generate-completions") => {
+            match args.get(1).map(String::as_str) {
+                Some("bash") => print!("{}", bash(&program, &options, games)),
+                Some("zsh") => print!("{}", zsh(&program, &options, games)),
+                Some("fish") => print!("{}", fish(&program, &options, games)),
+                _ => eprintln!("Expected one of: {}", SHELLS.join(", ")),
+            }
+            true
+        }
+        Some("--generate-man") => {
+            print!("{}", man(&program, &options, games));
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completions_cover_options() {
+        let options = SOLVE_OPTIONS
+            .iter()
+            .chain(Some(&GAME_OPTION))
+            .collect::<Vec<_>>();
+        let games = ["anima", "sausage"];
+
+        let bash = bash("brutalize_all", &options, &games);
+        assert!(bash.contains("--game) COMPREPLY=($(compgen -W \"anima sausage\""));
+        assert!(bash.contains("complete -o filenames -F _brutalize_all brutalize_all"));
+
+        let zsh = zsh("brutalize_all", &options, &games);
+        assert!(zsh
+            .contains("'--game[The game for puzzles without a header line]:game:(anima sausage)'"));
+
+        let fish = fish("brutalize_all", &options, &games);
+        assert!(
+            fish.contains("complete -c brutalize_all -s v -d 'Print states along with solutions'")
+        );
+
+        let man = man("brutalize_all", &options, &games);
+        assert!(man.contains(".B \\-\\-game \\fIGAME\\fR\n"));
+    }
+}
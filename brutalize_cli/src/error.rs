@@ -0,0 +1,74 @@
+use std::{error, fmt, io, time::Duration};
+
+/// Everything that can go wrong while solving a puzzle from the command line
+/// or over `--serve`, shared by every game so they all report errors alike.
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// The puzzle couldn't be parsed, with the game's description of why.
+    Parse(String),
+    /// The puzzle parsed, but its initial state can't be searched.
+    InvalidPuzzle(String),
+    /// A multi-game binary was given a puzzle without saying which game.
+    MissingGame,
+    UnknownGame(String),
+    Timeout(Duration),
+    Budget(usize),
+}
+
+impl Error {
+    /// A short name for the kind of error, for tools reading JSON responses.
+    pub fn category(&self) -> &'static str {
+        match self {
+            Error::Io(_) => "io",
+            Error::Parse(_) => "parse",
+            Error::InvalidPuzzle(_) => "invalid",
+            Error::MissingGame | Error::UnknownGame(_) => "game",
+            Error::Timeout(_) => "timeout",
+            Error::Budget(_) => "budget",
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Parse(e) => write!(f, "failed to parse puzzle: {}", e),
+            Error::InvalidPuzzle(e) => write!(f, "initial state failed: {}", e),
+            Error::MissingGame => write!(f, "missing game"),
+            Error::UnknownGame(name) => write!(f, "unknown game '{}'", name),
+            Error::Timeout(time) => write!(
+                f,
+                "gave up after {}.{:03}s",
+                time.as_secs(),
+                time.subsec_millis()
+            ),
+            Error::Budget(count) => write!(f, "gave up after expanding {} states", count),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<brutalize::LimitExceeded> for Error {
+    fn from(e: brutalize::LimitExceeded) -> Self {
+        match e {
+            brutalize::LimitExceeded::Expansions(count) => Self::Budget(count),
+            brutalize::LimitExceeded::Time(time) => Self::Timeout(time),
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use crate::State;
+use std::{env, fmt::Write};
+
+/// Whether the terminal's locale can show UTF-8, judging by the first of the
+/// usual locale variables that's set, like the C library does.
+pub(crate) fn supports_unicode() -> bool {
+    locale_supports_unicode(|name| env::var(name).ok())
+}
+
+fn locale_supports_unicode(var: impl Fn(&str) -> Option<String>) -> bool {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|name| var(name).filter(|value| !value.is_empty()))
+        .is_some_and(|locale| {
+            let locale = locale.to_ascii_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        })
+}
+
+/// Draws the board from `State::cells` in a box-drawing frame, putting each
+/// game's glyphs in place of its characters. Glyphs are two columns wide, so
+/// other characters are padded to match. Whatever `display` prints after the
+/// board follows it unchanged. Returns `None` for games without cells.
+pub fn fancy_state<S: State>(state: &S, data: &S::Data) -> Option<String> {
+    let cells = state.cells(data)?;
+    let width = cells.iter().map(Vec::len).max().unwrap_or(0);
+    let border = "─".repeat(2 * width);
+
+    let mut result = String::new();
+    writeln!(result, "┌{}┐", border).unwrap();
+    for row in cells.iter() {
+        result.push('│');
+        for &c in row.iter() {
+            match S::GLYPHS.iter().find(|&&(from, _)| from == c) {
+                Some((_, glyph)) => result.push_str(glyph),
+                None => {
+                    result.push(c);
+                    result.push(' ');
+                }
+            }
+        }
+        result.push_str(&"  ".repeat(width - row.len()));
+        result.push_str("│\n");
+    }
+    writeln!(result, "└{}┘", border).unwrap();
+
+    let text = crate::display_state(state, data);
+    for line in text.lines().skip(cells.len()) {
+        writeln!(result, "{}", line).unwrap();
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locale(vars: &[(&str, &str)]) -> bool {
+        locale_supports_unicode(|name| {
+            vars.iter()
+                .find(|&&(n, _)| n == name)
+                .map(|&(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn detect_unicode_locale() {
+        assert!(locale(&[("LANG", "en_US.UTF-8")]));
+        assert!(locale(&[("LC_ALL", ""), ("LANG", "de_DE.utf8")]));
+        assert!(!locale(&[("LC_ALL", "C"), ("LANG", "en_US.UTF-8")]));
+        assert!(!locale(&[]));
+    }
+}
@@ -0,0 +1,171 @@
+use crate::State;
+use brutalize::Transition;
+use std::fmt::{self, Write};
+
+/// The background of cells whose character has no entry in `State::COLORS`.
+const DEFAULT_COLOR: &str = "#ddd";
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; }
+.step { display: none; }
+.step.shown { display: block; }
+.board { border-collapse: collapse; margin: 1em 0; }
+.board td { width: 2em; height: 2em; text-align: center; font-family: monospace; }
+nav button { font-size: 1em; margin-right: 0.5em; }
+";
+
+const SCRIPT: &str = "\
+const steps = document.querySelectorAll('.step');
+const counter = document.getElementById('counter');
+let current = 0;
+function show(index) {
+    current = Math.max(0, Math.min(steps.length - 1, index));
+    steps.forEach((step, i) => step.classList.toggle('shown', i === current));
+    counter.textContent = `Step ${current} of ${steps.length - 1}`;
+}
+document.getElementById('first').onclick = () => show(0);
+document.getElementById('previous').onclick = () => show(current - 1);
+document.getElementById('next').onclick = () => show(current + 1);
+document.getElementById('last').onclick = () => show(steps.length - 1);
+document.addEventListener('keydown', event => {
+    if (event.key === 'ArrowLeft') show(current - 1);
+    if (event.key === 'ArrowRight') show(current + 1);
+});
+show(0);
+";
+
+fn escape(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+/// Writes one state as a table of colored cells, followed by whatever
+/// `display` prints after the board. Games without cells are shown as their
+/// plain text display.
+fn write_state<S: State>(html: &mut String, state: &S, data: &S::Data) -> fmt::Result {
+    let text = crate::display_state(state, data);
+    let rows = match state.cells(data) {
+        Some(cells) => {
+            writeln!(html, "<table class=\"board\">")?;
+            for row in cells.iter() {
+                write!(html, "<tr>")?;
+                for &c in row.iter() {
+                    let color = S::COLORS
+                        .iter()
+                        .find(|&&(from, _)| from == c)
+                        .map_or(DEFAULT_COLOR, |&(_, color)| color);
+                    write!(
+                        html,
+                        "<td style=\"background: {}\">{}</td>",
+                        color,
+                        escape(c.encode_utf8(&mut [0; 4]))
+                    )?;
+                }
+                writeln!(html, "</tr>")?;
+            }
+            writeln!(html, "</table>")?;
+            cells.len()
+        }
+        None => 0,
+    };
+
+    let rest = text.lines().skip(rows).collect::<Vec<_>>();
+    if !rest.is_empty() {
+        writeln!(html, "<pre>{}</pre>", escape(&rest.join("\n")))?;
+    }
+    Ok(())
+}
+
+/// Builds a standalone HTML page stepping through `solution` from the initial
+/// state, one board per action with buttons to move between them. Returns
+/// `None` if the solution doesn't replay.
+pub fn html_walkthrough<S: State>(
+    name: &str,
+    initial_state: &S,
+    data: &S::Data,
+    solution: Vec<S::Action>,
+) -> Option<String>
+where
+    S::Action: fmt::Display + PartialEq,
+{
+    let moves = solution.len();
+    let steps = brutalize::replay(initial_state.clone(), data, solution)?;
+    let mut html = String::new();
+    write_page(&mut html, name, initial_state, data, moves, steps).unwrap();
+    Some(html)
+}
+
+fn write_page<S: State>(
+    html: &mut String,
+    name: &str,
+    initial_state: &S,
+    data: &S::Data,
+    moves: usize,
+    steps: Vec<(S::Action, Transition<S>)>,
+) -> fmt::Result
+where
+    S::Action: fmt::Display,
+{
+    writeln!(html, "<!DOCTYPE html>")?;
+    writeln!(html, "<html>")?;
+    writeln!(html, "<head>")?;
+    writeln!(html, "<meta charset=\"utf-8\">")?;
+    writeln!(html, "<title>{}</title>", escape(name))?;
+    writeln!(html, "<style>\n{}</style>", STYLE)?;
+    writeln!(html, "</head>")?;
+    writeln!(html, "<body>")?;
+    writeln!(html, "<h1>{}</h1>", escape(name))?;
+    writeln!(
+        html,
+        "<p>Solved in {} move(s). Use the buttons or arrow keys to step through.</p>",
+        moves
+    )?;
+    writeln!(html, "<nav>")?;
+    for (id, label) in [
+        ("first", "First"),
+        ("previous", "Previous"),
+        ("next", "Next"),
+        ("last", "Last"),
+    ] {
+        writeln!(html, "<button id=\"{}\">{}</button>", id, label)?;
+    }
+    writeln!(html, "<span id=\"counter\"></span>")?;
+    writeln!(html, "</nav>")?;
+
+    writeln!(html, "<div class=\"step\">")?;
+    writeln!(html, "<h2>Start</h2>")?;
+    write_state(html, initial_state, data)?;
+    writeln!(html, "</div>")?;
+    for (i, (action, transition)) in steps.into_iter().enumerate() {
+        writeln!(html, "<div class=\"step\">")?;
+        writeln!(html, "<h2>{}. {}</h2>", i + 1, escape(&action.to_string()))?;
+        match transition {
+            Transition::Indeterminate(state) => write_state(html, &state, data)?,
+            Transition::Success(_) => writeln!(html, "<p>Solved!</p>")?,
+        }
+        writeln!(html, "</div>")?;
+    }
+
+    writeln!(html, "<script>\n{}</script>", SCRIPT)?;
+    writeln!(html, "</body>")?;
+    writeln!(html, "</html>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::escape;
+
+    #[test]
+    fn escapes_markup() {
+        assert_eq!(escape("<a & \"b\">"), "&lt;a &amp; &quot;b&quot;&gt;");
+    }
+}
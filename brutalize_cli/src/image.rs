@@ -0,0 +1,319 @@
+use crate::State;
+use brutalize::Transition;
+use std::{collections::HashMap, fmt::Write, iter, time::Duration};
+
+/// The width and height of each cell in pixels.
+const CELL_SIZE: usize = 32;
+/// The color of cells whose character has no entry in `State::COLORS`.
+const DEFAULT_COLOR: &str = "#ddd";
+
+fn color_of<S: State>(c: char) -> &'static str {
+    S::COLORS
+        .iter()
+        .find(|&&(from, _)| from == c)
+        .map_or(DEFAULT_COLOR, |&(_, color)| color)
+}
+
+/// Reads a CSS color written as `#rgb` or `#rrggbb`.
+fn parse_color(color: &str) -> Option<[u8; 3]> {
+    let hex = color.strip_prefix('#')?;
+    let digit = |i: usize| u8::from_str_radix(hex.get(i..i + 1)?, 16).ok();
+    match hex.len() {
+        3 => Some([digit(0)? * 17, digit(1)? * 17, digit(2)? * 17]),
+        6 => Some([
+            digit(0)? * 16 + digit(1)?,
+            digit(2)? * 16 + digit(3)?,
+            digit(4)? * 16 + digit(5)?,
+        ]),
+        _ => None,
+    }
+}
+
+/// Draws the board from `State::cells` as an SVG image, one square per cell
+/// colored with the game's `COLORS`. Returns `None` for games without cells.
+pub fn svg_state<S: State>(state: &S, data: &S::Data) -> Option<String> {
+    let cells = state.cells(data)?;
+    let width = cells.iter().map(Vec::len).max().unwrap_or(0) * CELL_SIZE;
+    let height = cells.len() * CELL_SIZE;
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">",
+        width, height
+    )
+    .unwrap();
+    for (y, row) in cells.iter().enumerate() {
+        for (x, &c) in row.iter().enumerate() {
+            writeln!(
+                svg,
+                "<rect x=\"{}\" y=\"{}\" width=\"{3}\" height=\"{3}\" fill=\"{}\"/>",
+                x * CELL_SIZE,
+                y * CELL_SIZE,
+                color_of::<S>(c),
+                CELL_SIZE
+            )
+            .unwrap();
+        }
+    }
+    svg.push_str("</svg>\n");
+    Some(svg)
+}
+
+/// Fills in every pixel of the image of `cells`, row by row from the top.
+/// Returns the width and height along with the pixels.
+fn rasterize<S: State>(cells: &[Vec<char>]) -> (usize, usize, Vec<[u8; 3]>) {
+    let columns = cells.iter().map(Vec::len).max().unwrap_or(0);
+    let (width, height) = (columns * CELL_SIZE, cells.len() * CELL_SIZE);
+    let background = parse_color(DEFAULT_COLOR).unwrap();
+
+    let mut pixels = Vec::with_capacity(width * height);
+    for row in cells.iter() {
+        let mut line = Vec::with_capacity(width);
+        for x in 0..columns {
+            let color = row
+                .get(x)
+                .and_then(|&c| parse_color(color_of::<S>(c)))
+                .unwrap_or(background);
+            line.extend(iter::repeat_n(color, CELL_SIZE));
+        }
+        for _ in 0..CELL_SIZE {
+            pixels.extend_from_slice(&line);
+        }
+    }
+    (width, height, pixels)
+}
+
+/// Draws the same image as `svg_state` as a PNG. Returns `None` for games
+/// without cells.
+pub fn png_state<S: State>(state: &S, data: &S::Data) -> Option<Vec<u8>> {
+    let (width, height, pixels) = rasterize::<S>(&state.cells(data)?);
+
+    // Each scanline starts with its filter type, which is always none
+    let mut scanlines = Vec::with_capacity(height * (1 + 3 * width));
+    for line in pixels.chunks(width.max(1)) {
+        scanlines.push(0);
+        scanlines.extend(line.iter().flatten());
+    }
+
+    Some(encode_png(width as u32, height as u32, &scanlines))
+}
+
+/// Animates a solution from the initial state as a looping GIF, showing each
+/// state for `delay`. Returns `None` for games without cells or if the
+/// solution doesn't replay.
+pub fn gif_solution<S: State>(
+    initial_state: &S,
+    data: &S::Data,
+    solution: Vec<S::Action>,
+    delay: Duration,
+) -> Option<Vec<u8>>
+where
+    S::Action: PartialEq,
+{
+    let mut frames = vec![rasterize::<S>(&initial_state.cells(data)?)];
+    for (_, transition) in brutalize::replay(initial_state.clone(), data, solution)? {
+        if let Transition::Indeterminate(state) = transition {
+            frames.push(rasterize::<S>(&state.cells(data)?));
+        }
+    }
+    let (width, height, _) = frames[0];
+
+    // Boards only use the handful of colors in `COLORS`, so one palette
+    // covers every frame
+    let mut palette = Vec::<[u8; 3]>::new();
+    for (_, _, pixels) in frames.iter() {
+        for pixel in pixels.iter() {
+            if palette.len() < 256 && !palette.contains(pixel) {
+                palette.push(*pixel);
+            }
+        }
+    }
+    palette.resize(256, [0; 3]);
+
+    let mut gif = b"GIF89a".to_vec();
+    gif.extend_from_slice(&(width as u16).to_le_bytes());
+    gif.extend_from_slice(&(height as u16).to_le_bytes());
+    // A global color table of 256 entries, with no background color
+    gif.extend_from_slice(&[0xf7, 0, 0]);
+    gif.extend(palette.iter().flatten());
+    // Loop forever
+    gif.extend_from_slice(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00");
+
+    let centiseconds = (delay.as_millis() / 10).min(u16::MAX as u128) as u16;
+    for (_, _, pixels) in frames.iter() {
+        gif.extend_from_slice(&[0x21, 0xf9, 0x04, 0x00]);
+        gif.extend_from_slice(&centiseconds.to_le_bytes());
+        gif.extend_from_slice(&[0, 0]);
+
+        gif.push(0x2c);
+        gif.extend_from_slice(&[0, 0, 0, 0]);
+        gif.extend_from_slice(&(width as u16).to_le_bytes());
+        gif.extend_from_slice(&(height as u16).to_le_bytes());
+        gif.push(0);
+
+        let indices = pixels
+            .iter()
+            .map(|pixel| palette.iter().position(|p| p == pixel).unwrap_or(0) as u8)
+            .collect::<Vec<_>>();
+        gif.push(8);
+        for block in lzw(&indices).chunks(255) {
+            gif.push(block.len() as u8);
+            gif.extend_from_slice(block);
+        }
+        gif.push(0);
+    }
+    gif.push(0x3b);
+
+    Some(gif)
+}
+
+/// Compresses 8-bit color indices with the variable width LZW used by GIF.
+fn lzw(indices: &[u8]) -> Vec<u8> {
+    const CLEAR: u16 = 256;
+    const END: u16 = 257;
+    const MAX_CODES: u16 = 4096;
+
+    let mut output = Vec::new();
+    let (mut buffer, mut bits) = (0u32, 0u32);
+    let mut emit = |code: u16, size: u32| {
+        buffer |= (code as u32) << bits;
+        bits += size;
+        while bits >= 8 {
+            output.push(buffer as u8);
+            buffer >>= 8;
+            bits -= 8;
+        }
+    };
+
+    let mut table = HashMap::<(u16, u8), u16>::new();
+    let (mut next, mut size) = (END + 1, 9);
+    emit(CLEAR, size);
+    let mut indices = indices.iter();
+    if let Some(&first) = indices.next() {
+        let mut prefix = first as u16;
+        for &index in indices {
+            if let Some(&code) = table.get(&(prefix, index)) {
+                prefix = code;
+                continue;
+            }
+            emit(prefix, size);
+            if next < MAX_CODES {
+                table.insert((prefix, index), next);
+                if next == 1 << size {
+                    size += 1;
+                }
+                next += 1;
+            } else {
+                emit(CLEAR, size);
+                table.clear();
+                next = END + 1;
+                size = 9;
+            }
+            prefix = index as u16;
+        }
+        emit(prefix, size);
+    }
+    emit(END, size);
+    if bits > 0 {
+        output.push(buffer as u8);
+    }
+    output
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in bytes {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Encodes 8-bit RGB scanlines as a PNG. Boards are small enough that the
+/// image data is stored without compression, which keeps this dependency free.
+fn encode_png(width: u32, height: u32, scanlines: &[u8]) -> Vec<u8> {
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // Bit depth 8, truecolor, default compression, filtering and no interlace
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    write_chunk(&mut png, b"IHDR", &header);
+
+    let mut zlib = vec![0x78, 0x01];
+    let mut blocks = scanlines.chunks(0xffff).peekable();
+    if blocks.peek().is_none() {
+        zlib.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        zlib.push(last as u8);
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(scanlines).to_be_bytes());
+    write_chunk(&mut png, b"IDAT", &zlib);
+
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors() {
+        assert_eq!(parse_color("#d22"), Some([0xdd, 0x22, 0x22]));
+        assert_eq!(parse_color("#12abef"), Some([0x12, 0xab, 0xef]));
+        assert_eq!(parse_color("red"), None);
+    }
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn lzw_codes() {
+        // Clear, 0, the new code for 0 0, 0 and end, nine bits each
+        assert_eq!(lzw(&[0, 0, 0, 0]), [0x00, 0x01, 0x08, 0x04, 0x10, 0x10]);
+    }
+
+    #[test]
+    fn png_layout() {
+        let png = encode_png(1, 1, &[0, 255, 0, 0]);
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        assert_eq!(&png[12..16], b"IHDR");
+        assert!(png.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]));
+    }
+}
@@ -0,0 +1,216 @@
+use crate::Error;
+use std::{collections::BTreeMap, fmt::Write, fs, io, path::Path, time::Instant};
+
+/// How solving one puzzle went, as recorded in an index written by `--index`.
+#[derive(Clone, Debug, PartialEq)]
+struct Entry {
+    length: Option<usize>,
+    seconds: f64,
+    /// `solved`, `unsolvable`, or the category of the error that stopped it.
+    status: String,
+}
+
+impl Entry {
+    fn describe(&self) -> String {
+        match self.length {
+            Some(length) => format!("{} moves", length),
+            None => self.status.clone(),
+        }
+    }
+}
+
+type Index = BTreeMap<String, Entry>;
+
+/// Finds every puzzle under `path`, descending into directories. Only `.txt`
+/// files are picked up from directories, but files given directly are always
+/// used.
+pub(crate) fn collect(path: &Path, puzzles: &mut Vec<String>) -> io::Result<()> {
+    if path.is_dir() {
+        for entry in fs::read_dir(path)? {
+            let path = entry?.path();
+            if path.is_dir() || path.extension().is_some_and(|e| e == "txt") {
+                collect(&path, puzzles)?;
+            }
+        }
+    } else {
+        puzzles.push(path.to_string_lossy().into_owned());
+    }
+    Ok(())
+}
+
+fn write(index: &Index) -> String {
+    let mut result = String::from("# puzzle\tlength\tseconds\tstatus\n");
+    for (name, entry) in index.iter() {
+        let length = entry
+            .length
+            .map_or_else(|| "-".to_string(), |l| l.to_string());
+        writeln!(
+            result,
+            "{}\t{}\t{:.3}\t{}",
+            name, length, entry.seconds, entry.status
+        )
+        .unwrap();
+    }
+    result
+}
+
+/// Reads an index written by `write`, skipping lines it can't make sense of.
+fn parse(source: &str) -> Index {
+    source
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut pieces = line.split('\t');
+            let name = pieces.next()?;
+            let length = pieces.next()?;
+            let seconds = pieces.next()?.parse().ok()?;
+            let status = pieces.next()?;
+            let entry = Entry {
+                length: length.parse().ok(),
+                seconds,
+                status: status.to_string(),
+            };
+            Some((name.to_string(), entry))
+        })
+        .collect()
+}
+
+/// Describes each puzzle whose length or status changed between two indexes,
+/// along with puzzles only one of them has. Times always vary a little, so
+/// they're left out.
+fn diff(old: &Index, new: &Index) -> Vec<String> {
+    let mut changes = Vec::new();
+    for (name, entry) in new.iter() {
+        match old.get(name) {
+            Some(previous)
+                if previous.length == entry.length && previous.status == entry.status => {}
+            Some(previous) => changes.push(format!(
+                "{}: {} -> {}",
+                name,
+                previous.describe(),
+                entry.describe()
+            )),
+            None => changes.push(format!("{}: new, {}", name, entry.describe())),
+        }
+    }
+    for name in old.keys().filter(|name| !new.contains_key(*name)) {
+        changes.push(format!("{}: removed", name));
+    }
+    changes
+}
+
+/// Solves every puzzle under `paths` and writes an index of their lengths,
+/// times and statuses to `index_path`. With `previous` given, also prints how
+/// the results changed since that index was written. Returns whether the
+/// index was written and nothing changed.
+pub(crate) fn write_index(
+    paths: &[String],
+    index_path: &str,
+    previous: Option<&str>,
+    mut solve: impl FnMut(&str) -> Result<Option<usize>, Error>,
+) -> bool {
+    let mut puzzles = Vec::new();
+    for path in paths {
+        if let Err(e) = collect(Path::new(path), &mut puzzles) {
+            eprintln!("Error while reading '{}': {}", path, e);
+            return false;
+        }
+    }
+    puzzles.sort();
+
+    let mut index = Index::new();
+    for puzzle in puzzles {
+        let now = Instant::now();
+        let result = solve(&puzzle);
+        let seconds = now.elapsed().as_secs_f64();
+        let entry = match result {
+            Ok(length) => {
+                let status = if length.is_some() {
+                    "solved"
+                } else {
+                    "unsolvable"
+                };
+                Entry {
+                    length,
+                    seconds,
+                    status: status.to_string(),
+                }
+            }
+            Err(e) => {
+                eprintln!("Error while solving '{}': {}", puzzle, e);
+                Entry {
+                    length: None,
+                    seconds,
+                    status: e.category().to_string(),
+                }
+            }
+        };
+        index.insert(puzzle, entry);
+    }
+
+    let mut unchanged = true;
+    if let Some(previous) = previous {
+        match fs::read_to_string(previous) {
+            Ok(source) => {
+                let changes = diff(&parse(&source), &index);
+                println!("{} change(s) since {}", changes.len(), previous);
+                for change in changes.iter() {
+                    println!("{}", change);
+                }
+                unchanged = changes.is_empty();
+            }
+            Err(e) => {
+                eprintln!("Error while reading index '{}': {}", previous, e);
+                unchanged = false;
+            }
+        }
+    }
+
+    if let Err(e) = fs::write(index_path, write(&index)) {
+        eprintln!("Error while writing index '{}': {}", index_path, e);
+        return false;
+    }
+    unchanged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(length: Option<usize>, status: &str) -> Entry {
+        Entry {
+            length,
+            seconds: 0.5,
+            status: status.to_string(),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut index = Index::new();
+        index.insert("a.txt".to_string(), entry(Some(12), "solved"));
+        index.insert("b.txt".to_string(), entry(None, "timeout"));
+        assert_eq!(parse(&write(&index)), index);
+    }
+
+    #[test]
+    fn changes() {
+        let mut old = Index::new();
+        old.insert("same.txt".to_string(), entry(Some(3), "solved"));
+        old.insert("longer.txt".to_string(), entry(Some(4), "solved"));
+        old.insert("gone.txt".to_string(), entry(Some(1), "solved"));
+        let mut new = Index::new();
+        new.insert("same.txt".to_string(), entry(Some(3), "solved"));
+        new.insert("longer.txt".to_string(), entry(Some(6), "solved"));
+        new.insert("added.txt".to_string(), entry(None, "budget"));
+
+        assert_eq!(
+            diff(&old, &new),
+            vec![
+                "added.txt: new, budget",
+                "longer.txt: 4 moves -> 6 moves",
+                "gone.txt: removed",
+            ]
+        );
+    }
+}
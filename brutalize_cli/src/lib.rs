@@ -1,10 +1,116 @@
-use std::{env, fmt, fs, io, path::Path, time::Instant};
+use crate::{partial::Closest, stats::Stats};
+use brutalize::Cost;
+use std::{
+    cmp::Ordering,
+    env, fmt,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    mem,
+    path::{Path, PathBuf},
+    process,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+mod completions;
+mod error;
+mod fancy;
+mod html;
+#[cfg(feature = "render")]
+mod image;
+mod index;
+#[cfg(feature = "metrics")]
+mod metrics;
+mod partial;
+mod registry;
+mod serve;
+mod stats;
+mod summary;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+mod watch;
+
+pub use crate::error::*;
+pub use crate::fancy::fancy_state;
+pub use crate::html::html_walkthrough;
+#[cfg(feature = "render")]
+pub use crate::image::{gif_solution, png_state, svg_state};
+pub use crate::registry::*;
 
 pub trait State: brutalize::State + Clone {
     type ParseError: fmt::Debug;
 
+    /// Explains the characters used by `display`, printed by `--render`.
+    const LEGEND: &'static str = "";
+
+    /// Two column wide glyphs drawn by `--fancy` in place of the characters
+    /// in `cells`.
+    const GLYPHS: &'static [(char, &'static str)] = &[];
+
+    /// CSS colors used by `--html-out` and `--render-out` for the characters
+    /// in `cells`, written as `#rgb` or `#rrggbb`.
+    const COLORS: &'static [(char, &'static str)] = &[];
+
     fn parse(s: &str) -> Result<(Self, Self::Data), Self::ParseError>;
+
+    /// Checks that a parsed initial state can be searched at all, describing
+    /// why not otherwise. Puzzles failing this would only report no solution.
+    fn validate(&self, _data: &Self::Data) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// The move count the game itself gives as par for this puzzle, if the
+    /// puzzle file records it.
+    fn par(_data: &Self::Data) -> Option<usize> {
+        None
+    }
+
+    /// A small bundled puzzle that `--demo` solves, so people can check a
+    /// solver works without writing a puzzle of their own.
+    fn demo() -> Option<&'static str> {
+        None
+    }
+
+    /// Solves a puzzle which has to visit waypoints in order, for games that
+    /// implement `brutalize::Waypoints` with `brutalize::solve_waypoints`.
+    /// Returns `None` for puzzles without waypoints, which are solved as
+    /// usual.
+    fn solve_waypoints(
+        &self,
+        _data: &Self::Data,
+        _global: bool,
+    ) -> Option<Option<Vec<Self::Action>>> {
+        None
+    }
+
+    /// Writes a solution in the game's compact notation, if it has one.
+    fn write_solution(_solution: &[Self::Action]) -> Option<String> {
+        None
+    }
+
+    /// Reads a solution written by `write_solution`, describing what couldn't
+    /// be read otherwise.
+    fn parse_solution(_s: &str) -> Result<Vec<Self::Action>, String> {
+        Err("this game has no solution notation".to_string())
+    }
+
+    fn write(&self, data: &Self::Data, f: &mut fmt::Formatter<'_>) -> fmt::Result;
     fn display(&self, data: &Self::Data, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+
+    /// The board as rows of cells from the top, each holding the character
+    /// `display` draws there. `display` has to start with these rows, which
+    /// `--fancy` redraws with `GLYPHS`.
+    fn cells(&self, _data: &Self::Data) -> Option<Vec<Vec<char>>> {
+        None
+    }
+
+    /// The state as a JSON value for `--include-states`, so tools can draw
+    /// solutions without knowing the game's rules. Games with serde support
+    /// serialize the whole state, and the rest fall back to their packed key
+    /// as a hex string.
+    fn to_json(&self) -> Option<String> {
+        self.pack().map(|key| format!("\"{:032x}\"", key))
+    }
 }
 
 struct DisplayState<'a, S: State>(&'a S, &'a S::Data);
@@ -15,9 +121,73 @@ impl<'a, S: State> fmt::Display for DisplayState<'a, S> {
     }
 }
 
+struct WriteState<'a, S: State>(&'a S, &'a S::Data);
+
+impl<'a, S: State> fmt::Display for WriteState<'a, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.write(self.1, f)
+    }
+}
+
+pub fn write_puzzle<S: State>(state: &S, data: &S::Data) -> String {
+    WriteState(state, data).to_string()
+}
+
+/// Renders a state the way the CLI prints it, for checking `display` output.
+pub fn display_state<S: State>(state: &S, data: &S::Data) -> String {
+    DisplayState(state, data).to_string()
+}
+
+/// Renders a state for the terminal, with glyphs if `--fancy` was given and
+/// the terminal can show them.
+fn show<S: State>(state: &S, data: &S::Data, settings: &Settings) -> String {
+    if settings.fancy && fancy::supports_unicode() {
+        if let Some(fancy) = fancy_state(state, data) {
+            return fancy;
+        }
+    }
+    display_state(state, data)
+}
+
 struct Settings {
     verbose: bool,
     quiet: bool,
+    stats: bool,
+    check_heuristic: bool,
+    demo: bool,
+    solution_out: bool,
+    solution_dir: Option<String>,
+    force: bool,
+    index: Option<String>,
+    diff_index: Option<String>,
+    watch: bool,
+    bound: bool,
+    difficulty: bool,
+    compare: bool,
+    beam: Option<usize>,
+    beam_depth: Option<usize>,
+    waypoint_legs: bool,
+    json: bool,
+    include_states: bool,
+    estimate: bool,
+    plan: bool,
+    yes: bool,
+    check: Option<String>,
+    parse_only: bool,
+    render: bool,
+    fancy: bool,
+    html_out: Option<String>,
+    render_out: Option<String>,
+    gif_out: Option<String>,
+    frame_delay: Duration,
+    serve: Option<String>,
+    metrics: Option<String>,
+    trace: Option<String>,
+    trace_csv: Option<String>,
+    limits: brutalize::Limits,
+    /// Whether the time limit came from `--per-puzzle-timeout`, which shows
+    /// how far searches that run out of time got.
+    report_partial: bool,
 }
 
 impl Settings {
@@ -25,6 +195,40 @@ impl Settings {
         Self {
             verbose: false,
             quiet: false,
+            stats: false,
+            check_heuristic: false,
+            demo: false,
+            solution_out: false,
+            solution_dir: None,
+            force: false,
+            index: None,
+            diff_index: None,
+            watch: false,
+            bound: false,
+            difficulty: false,
+            compare: false,
+            beam: None,
+            beam_depth: None,
+            waypoint_legs: false,
+            json: false,
+            include_states: false,
+            estimate: false,
+            plan: false,
+            yes: false,
+            check: None,
+            parse_only: false,
+            render: false,
+            fancy: false,
+            html_out: None,
+            render_out: None,
+            gif_out: None,
+            frame_delay: Duration::from_millis(500),
+            serve: None,
+            metrics: None,
+            trace: None,
+            trace_csv: None,
+            limits: brutalize::Limits::default(),
+            report_partial: false,
         }
     }
 }
@@ -32,58 +236,779 @@ impl Settings {
 pub fn execute<S: State>()
 where
     S::Action: fmt::Display + PartialEq,
+    S::Outcome: fmt::Debug,
 {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let options = completions::SOLVE_OPTIONS.iter().collect::<Vec<_>>();
+    if completions::generate(&args, &options, &[]) {
+        return;
+    }
+    let (settings, paths) = parse_args_or_exit(args.into_iter());
+    // Puzzles are solved one after another, so their searches can share
+    // storage
+    let mut buffers = brutalize::SearchBuffers::new();
+
+    if let Some(path) = &settings.trace_csv {
+        print_trace_csv(path);
+    } else if let Some(address) = &settings.serve {
+        serve::run(address, settings.metrics.as_deref(), |source| {
+            serve::solve_json::<S>(source, &settings.limits, &mut buffers)
+        });
+    } else if settings.demo {
+        let succeeded = match S::demo() {
+            Some(source) => watch::solve_paths(&["demo".to_string()], false, |name| {
+                solve_source::<S>(name, source, &settings, &mut buffers)
+            }),
+            None => {
+                eprintln!("This game has no demo puzzle");
+                false
+            }
+        };
+        if !succeeded {
+            process::exit(1);
+        }
+    } else if paths.is_empty() {
+        completions::print_usage(&options, &[]);
+    } else if let Some(index) = &settings.index {
+        let unchanged = index::write_index(&paths, index, settings.diff_index.as_deref(), |path| {
+            solve::<S>(path.as_ref(), &settings, &mut buffers)
+        });
+        if !unchanged {
+            process::exit(1);
+        }
+    } else {
+        let succeeded = watch::solve_paths(&paths, settings.watch, |path| {
+            solve::<S>(path.as_ref(), &settings, &mut buffers)
+        });
+        if !succeeded {
+            process::exit(1);
+        }
+    }
+}
+
+/// Parses the command line like `parse_args`, exiting with the problem if it
+/// can't be.
+fn parse_args_or_exit(args: impl Iterator<Item = String>) -> (Settings, Vec<String>) {
+    parse_args(args).unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        process::exit(2);
+    })
+}
+
+/// Parses the value following `flag`, which must be there and make sense.
+fn parse_value<T: FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid value '{}' for {}", value, flag))
+}
+
+/// Parses a number of seconds following `flag`.
+fn parse_seconds(flag: &str, value: Option<String>) -> Result<Duration, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+    // Negative and infinite durations parse as numbers but aren't durations
+    value
+        .parse()
+        .ok()
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .ok_or_else(|| format!("invalid value '{}' for {}", value, flag))
+}
+
+fn parse_args(args: impl Iterator<Item = String>) -> Result<(Settings, Vec<String>), String> {
     let mut settings = Settings::new();
     let mut paths = Vec::new();
 
-    for arg in env::args().skip(1) {
+    let mut args = args;
+    while let Some(arg) = args.next() {
         match arg.as_str() {
             "-v" => settings.verbose = true,
             "-q" => settings.quiet = true,
+            "--stats" => settings.stats = true,
+            "--check-heuristic" => settings.check_heuristic = true,
+            "--demo" => settings.demo = true,
+            "--solution-out" => settings.solution_out = true,
+            "--solution-dir" => {
+                settings.solution_out = true;
+                settings.solution_dir = args.next();
+            }
+            "--force" => settings.force = true,
+            "--index" => settings.index = args.next(),
+            "--diff-index" => settings.diff_index = args.next(),
+            "--watch" => settings.watch = true,
+            "--bound" => settings.bound = true,
+            "--difficulty" => settings.difficulty = true,
+            "--compare" => settings.compare = true,
+            "--beam" => settings.beam = Some(parse_value(&arg, args.next())?),
+            "--beam-depth" => settings.beam_depth = Some(parse_value(&arg, args.next())?),
+            "--waypoint-legs" => settings.waypoint_legs = true,
+            "--json" => settings.json = true,
+            "--include-states" => settings.include_states = true,
+            "--estimate" => settings.estimate = true,
+            "--plan" => settings.plan = true,
+            "--yes" => settings.yes = true,
+            "--check" => settings.check = args.next(),
+            "--parse-only" => settings.parse_only = true,
+            "--render" => settings.render = true,
+            "--fancy" => settings.fancy = true,
+            "--html-out" => settings.html_out = args.next(),
+            "--render-out" => settings.render_out = args.next(),
+            "--gif-out" => settings.gif_out = args.next(),
+            "--frame-delay" => {
+                settings.frame_delay = Duration::from_millis(parse_value(&arg, args.next())?)
+            }
+            "--serve" => settings.serve = args.next(),
+            "--metrics" => settings.metrics = args.next(),
+            "--trace" => settings.trace = args.next(),
+            "--trace-csv" => settings.trace_csv = args.next(),
+            "--budget" => settings.limits.expansions = Some(parse_value(&arg, args.next())?),
+            "--timeout" => settings.limits.time = Some(parse_seconds(&arg, args.next())?),
+            "--per-puzzle-timeout" => {
+                settings.limits.time = Some(parse_seconds(&arg, args.next())?);
+                settings.report_partial = true;
+            }
             _ => paths.push(arg),
         }
     }
 
-    if paths.is_empty() {
-        println!("Usage: {} [-v -q] PATHS", env::args().next().unwrap());
-        println!("  -v       Print states along with solutions");
-        println!("  -q       Do not print solutions");
-        println!("  PATHS    A list of paths to problem files");
+    Ok((settings, paths))
+}
+
+/// Prints the per-depth expansion counts of a trace written by `--trace`.
+fn print_trace_csv(path: &str) {
+    let result = File::open(path)
+        .and_then(|file| brutalize::trace_to_csv(BufReader::new(file), io::stdout().lock()));
+    if let Err(e) = result {
+        eprintln!("Error while reading trace '{}':\n{:?}", path, e);
+    }
+}
+
+type Report<S> =
+    brutalize::SolveReport<<S as brutalize::State>::Action, <S as brutalize::State>::Outcome>;
+
+/// Solves a puzzle within the `--budget` and `--timeout` limits, writing a
+/// trace of the search to the path given by `--trace` if there is one and
+/// collecting `stats` if given. With `--per-puzzle-timeout`, searches that
+/// run out of time print the closest state they reached. Searches with
+/// nothing observing them use `buffers` for their storage.
+fn solve_with_trace<S: State>(
+    name: &str,
+    initial_state: S,
+    data: &S::Data,
+    settings: &Settings,
+    stats: Option<&mut Stats<S>>,
+    buffers: &mut brutalize::SearchBuffers<S>,
+) -> Result<Report<S>, Error>
+where
+    S::Action: fmt::Display,
+{
+    let limits = &settings.limits;
+    let trace = settings
+        .trace
+        .as_ref()
+        .and_then(|path| match File::create(path) {
+            Ok(file) => Some((path, brutalize::TraceWriter::new(BufWriter::new(file)))),
+            Err(e) => {
+                eprintln!("Error while creating trace '{}':\n{:?}", path, e);
+                None
+            }
+        });
+    let closest = settings.report_partial.then(|| Closest::new(data));
+    // Observing costs a little even when nothing is watching
+    if trace.is_none() && stats.is_none() && closest.is_none() {
+        return Ok(brutalize::solve_limited_with_buffers(
+            initial_state,
+            data,
+            limits,
+            buffers,
+        )?);
+    }
+
+    let (path, trace) = trace.unzip();
+    let mut observers = ((trace, stats), closest);
+    let report = brutalize::solve_limited(initial_state, data, limits, Some(&mut observers));
+    let ((trace, _), closest) = observers;
+    if let (Some(path), Some(trace)) = (path, trace) {
+        if let Err(e) = trace.finish() {
+            eprintln!("Error while writing trace '{}':\n{:?}", path, e);
+        }
+    }
+    if let (Err(brutalize::LimitExceeded::Time(_)), Some(closest)) = (&report, &closest) {
+        print_partial(name, closest, data, settings);
+    }
+    Ok(report?)
+}
+
+/// Prints how deep a search that ran out of time got, and the state it
+/// reached that looked closest to a goal.
+fn print_partial<S: State>(name: &str, closest: &Closest<S>, data: &S::Data, settings: &Settings) {
+    println!("{}:", name);
+    println!("Explored to depth {}", closest.depth());
+    if !S::HAS_HEURISTIC {
+        println!("Closest state: unknown (no heuristic)");
+    } else if let Some((heuristic, depth, state)) = closest.best() {
+        println!(
+            "Closest state: heuristic {:?} at depth {}",
+            heuristic, depth
+        );
+        print!("{}", show(state, data, settings));
+    }
+}
+
+/// Runs every search algorithm on the same puzzle and prints how they did,
+/// returning the solution length if they all agree on it.
+fn compare<S: State>(initial_state: S, data: &S::Data) -> Option<usize> {
+    type Solver<S> = fn(S, &<S as brutalize::State>::Data) -> Report<S>;
+    let solvers: [(&str, Solver<S>); 2] = [
+        ("A*", brutalize::solve_with_report),
+        ("Dijkstra", brutalize::solve_dijkstra),
+    ];
+
+    println!(
+        "{:<10} {:>14} {:>10} {:>10} {:>8}",
+        "Algorithm", "Time", "Expanded", "Memory", "Length"
+    );
+    let mut lengths = Vec::new();
+    for (name, solver) in solvers.iter() {
+        let now = Instant::now();
+        let report = solver(initial_state.clone(), data);
+        let elapsed = now.elapsed();
+        let length = report.solution.as_ref().map(Vec::len);
+        println!(
+            "{:<10} {:>3}.{:09}s {:>10} {:>10} {:>8}",
+            name,
+            elapsed.as_secs(),
+            elapsed.subsec_nanos(),
+            report.expanded,
+            brutalize::Bytes(report.memory.total()).to_string(),
+            length.map_or("none".to_string(), |l| l.to_string())
+        );
+        lengths.push(length);
+    }
+
+    // Both searches are optimal, so a disagreement means the heuristic
+    // overestimates somewhere
+    if lengths.windows(2).all(|w| w[0] == w[1]) {
+        lengths[0]
     } else {
-        for path in paths {
-            if let Err(e) = solve::<S>(path.as_ref(), &settings) {
-                eprintln!("Error while solving '{}':\n{:?}", path, e);
+        println!("Solution lengths disagree, the heuristic is not admissible");
+        None
+    }
+}
+
+/// Searches with a beam of `width` states and prints the solution it found,
+/// returning its length. Finding nothing doesn't rule out a solution. With a
+/// `max_depth`, only moves straight back to the previous state are ruled out
+/// instead of every state seen.
+fn beam<S: State>(
+    initial_state: S,
+    data: &S::Data,
+    width: usize,
+    max_depth: Option<usize>,
+) -> Option<usize>
+where
+    S::Action: fmt::Display,
+{
+    let result = match max_depth {
+        Some(max_depth) => {
+            let guard = brutalize::CycleGuard {
+                recent: 2,
+                max_depth,
+            };
+            brutalize::solve_beam_guarded(initial_state, data, width, guard)
+        }
+        None => brutalize::solve_beam(initial_state, data, width),
+    };
+    match result {
+        brutalize::Beam::Solved(solution, _) => {
+            println!(
+                "Found solution of length {} (may not be the shortest):",
+                solution.len()
+            );
+            let actions = solution.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+            println!("{}", actions.join(", "));
+            Some(solution.len())
+        }
+        brutalize::Beam::Unknown => {
+            println!("Unknown: the beam ran out of states, try a wider one");
+            None
+        }
+    }
+}
+
+/// Prints a solution that visits the puzzle's waypoints, returning its
+/// length. Solving one leg at a time only finds the shortest way to each
+/// waypoint in turn.
+fn print_waypoint_solution<A: fmt::Display>(solution: Option<Vec<A>>, legs: bool) -> Option<usize> {
+    let solution = match solution {
+        Some(solution) => solution,
+        None => {
+            println!("No solution through the waypoints");
+            return None;
+        }
+    };
+    if legs {
+        println!(
+            "Found solution of length {} through the waypoints (may not be the shortest):",
+            solution.len()
+        );
+    } else {
+        println!(
+            "Found solution of length {} through the waypoints:",
+            solution.len()
+        );
+    }
+    let actions = solution.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+    println!("{}", actions.join(", "));
+    Some(solution.len())
+}
+
+/// How many random walks `--estimate` and `--plan` sample.
+const WALKS: usize = 1000;
+/// The most moves each random walk takes.
+const MAX_DEPTH: usize = 200;
+/// How long `--plan` runs the search for to see how fast it goes.
+const PLAN_SAMPLE: Duration = Duration::from_millis(500);
+
+/// Samples random walks through a puzzle and prints how big its search is
+/// likely to be.
+fn estimate<S: State>(initial_state: S, data: &S::Data) {
+    let effort = brutalize::estimate_effort(initial_state, data, WALKS, MAX_DEPTH, 0);
+    println!("Branching factor: {:.2}", effort.branching_factor);
+    println!("Average walk depth: {:.1}", effort.depth);
+    match effort.goal_depth {
+        Some(depth) => println!("Shallowest goal found: {} moves", depth),
+        None => println!("Shallowest goal found: none within {} moves", MAX_DEPTH),
+    }
+    println!("Projected states: {:.3e}", effort.projected);
+}
+
+/// Writes a number of states with a metric suffix, like `40M`.
+fn describe_count(count: f64) -> String {
+    match count {
+        c if c >= 1e12 => format!("{:.1e}", c),
+        c if c >= 1e9 => format!("{:.1}G", c / 1e9),
+        c if c >= 1e6 => format!("{:.0}M", c / 1e6),
+        c if c >= 1e3 => format!("{:.0}K", c / 1e3),
+        c => format!("{:.0}", c),
+    }
+}
+
+/// Writes a duration rounded to the most sensible unit, like `25 min`.
+fn describe_duration(duration: Duration) -> String {
+    match duration.as_secs_f64() {
+        s if s >= 365.0 * 24.0 * 3600.0 => format!("{:.1e} years", s / (365.0 * 24.0 * 3600.0)),
+        s if s >= 48.0 * 3600.0 => format!("{:.1} days", s / (24.0 * 3600.0)),
+        s if s >= 3600.0 => format!("{:.1} h", s / 3600.0),
+        s if s >= 60.0 => format!("{:.0} min", s / 60.0),
+        s => format!("{:.0} s", s),
+    }
+}
+
+/// Prints what solving a puzzle is projected to cost within the `--budget`
+/// and `--timeout` limits, then asks whether to go ahead unless `--yes` was
+/// given. Anything but a yes, including no answer at all, declines.
+fn confirm_plan<S: State>(
+    name: &str,
+    initial_state: S,
+    data: &S::Data,
+    settings: &Settings,
+) -> io::Result<bool> {
+    let effort = brutalize::estimate_effort(initial_state.clone(), data, WALKS, MAX_DEPTH, 0);
+    let plan = brutalize::plan_search(initial_state, data, &effort, PLAN_SAMPLE);
+
+    // Searches stop at whichever limit they hit first
+    let mut states = plan.states;
+    if let Some(max) = settings.limits.expansions {
+        states = states.min(max as f64);
+    }
+    if let Some(time) = settings.limits.time {
+        states = states.min(time.as_secs_f64() / plan.time_per_state.as_secs_f64());
+    }
+    let limited = brutalize::SearchPlan { states, ..plan };
+
+    print!(
+        "Plan for {}: {} {} states, ~{}, ~{} with current settings",
+        name,
+        if limited.exact {
+            "exactly"
+        } else {
+            "estimated"
+        },
+        describe_count(limited.states),
+        brutalize::Bytes(limited.memory() as usize),
+        describe_duration(limited.time())
+    );
+    if settings.yes {
+        println!();
+        return Ok(true);
+    }
+    print!("; proceed? [y/N] ");
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer)? == 0 {
+        // Nobody answered, so the prompt still needs ending
+        println!();
+    }
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// Replays a solution written in the game's notation and prints whether it
+/// solves the puzzle, returning its length if it does.
+fn check<S: State>(initial_state: S, data: &S::Data, solution: &str) -> Option<usize>
+where
+    S::Action: PartialEq,
+{
+    let solution = match S::parse_solution(solution) {
+        Ok(solution) => solution,
+        Err(e) => {
+            println!("Can't read solution '{}': {}", solution, e);
+            return None;
+        }
+    };
+
+    let length = solution.len();
+    let steps = match brutalize::replay(initial_state, data, solution) {
+        Some(steps) => steps,
+        None => {
+            println!("Solution makes an impossible move");
+            return None;
+        }
+    };
+    let solved_at = steps
+        .iter()
+        .position(|(_, t)| matches!(t, brutalize::Transition::Success(_)));
+
+    match solved_at {
+        Some(i) if i + 1 == length => {
+            println!("Solution of length {} solves the puzzle", length);
+            Some(length)
+        }
+        Some(i) => {
+            println!("Solution solves the puzzle early, after {} moves", i + 1);
+            None
+        }
+        None => {
+            println!("Solution does not solve the puzzle");
+            None
+        }
+    }
+}
+
+fn solve<S: State>(
+    path: &Path,
+    settings: &Settings,
+    buffers: &mut brutalize::SearchBuffers<S>,
+) -> Result<Option<usize>, Error>
+where
+    S::Action: fmt::Display + PartialEq,
+    S::Outcome: fmt::Debug,
+{
+    let source = fs::read_to_string(path)?;
+    solve_source::<S>(path.to_str().unwrap(), &source, settings, buffers)
+}
+
+/// Solves a puzzle of one game like `solve_source`, given its name and source.
+pub(crate) type Solver = Box<dyn FnMut(&str, &str, &Settings) -> Result<Option<usize>, Error>>;
+
+/// Makes a `Solver` for puzzles of `S`, which keeps its search storage from
+/// one puzzle to the next.
+pub(crate) fn solver<S: State + 'static>() -> Solver
+where
+    S::Action: fmt::Display + PartialEq,
+    S::Outcome: fmt::Debug,
+{
+    let mut buffers = brutalize::SearchBuffers::new();
+    Box::new(move |name, source, settings| solve_source::<S>(name, source, settings, &mut buffers))
+}
+
+/// Where `--solution-out` writes the solution to the puzzle at `path`: beside
+/// it, or in the directory given by `--solution-dir`.
+fn solution_path(path: &str, settings: &Settings) -> Option<PathBuf> {
+    if !settings.solution_out {
+        return None;
+    }
+    let path = Path::new(path).with_extension("sol");
+    match &settings.solution_dir {
+        Some(dir) => Some(Path::new(dir).join(path.file_name()?)),
+        None => Some(path),
+    }
+}
+
+/// Writes a solution in the game's notation, falling back to its actions
+/// separated by commas.
+fn write_solution<S: State>(solution: &[S::Action]) -> String
+where
+    S::Action: fmt::Display,
+{
+    S::write_solution(solution).unwrap_or_else(|| {
+        solution
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    })
+}
+
+/// Prints every state along an optimal solution where the heuristic guessed
+/// more than the cost actually left, for `--check-heuristic`.
+fn print_overestimates<S: State>(initial_state: S, data: &S::Data, solution: &[S::Action])
+where
+    S::Action: PartialEq,
+{
+    match brutalize::check_admissible(initial_state, data, solution) {
+        Some(overestimates) if overestimates.is_empty() => {
+            println!("Heuristic: admissible along the solution")
+        }
+        Some(overestimates) => {
+            println!("Heuristic: overestimated {} state(s)", overestimates.len());
+            for o in overestimates {
+                println!(
+                    "  step {}: estimated {:?} with {} left",
+                    o.step, o.heuristic, o.remaining
+                );
             }
         }
+        None => eprintln!("Solution does not replay, transitions are not deterministic"),
     }
 }
 
-#[derive(Debug)]
-enum SolveError<T> {
-    IoError(io::Error),
-    ParseError(T),
+/// Writes SVG and PNG images of a state into `dir`, named after the puzzle.
+#[cfg(feature = "render")]
+fn render_out<S: State>(name: &str, state: &S, data: &S::Data, dir: &str) -> Result<(), Error> {
+    let (svg, png) = match (svg_state(state, data), png_state(state, data)) {
+        (Some(svg), Some(png)) => (svg, png),
+        _ => {
+            println!("This game can't be drawn as an image");
+            return Ok(());
+        }
+    };
+    let stem = Path::new(name)
+        .file_stem()
+        .unwrap_or_else(|| "puzzle".as_ref());
+    fs::create_dir_all(dir)?;
+    for (extension, contents) in [("svg", svg.into_bytes()), ("png", png)] {
+        let path = Path::new(dir).join(format!("{}.{}", stem.to_string_lossy(), extension));
+        fs::write(&path, contents)?;
+        println!("Wrote {}", path.display());
+    }
+    Ok(())
 }
 
-impl<T> From<io::Error> for SolveError<T> {
-    fn from(e: io::Error) -> Self {
-        Self::IoError(e)
+#[cfg(not(feature = "render"))]
+fn render_out<S: State>(_: &str, _: &S, _: &S::Data, _: &str) -> Result<(), Error> {
+    Err(Error::Io(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "--render-out needs brutalize_cli's render feature",
+    )))
+}
+
+/// Writes a GIF animating the solution to `path`.
+#[cfg(feature = "render")]
+fn gif_out<S: State>(
+    path: &str,
+    initial_state: &S,
+    data: &S::Data,
+    solution: Vec<S::Action>,
+    delay: Duration,
+) -> Result<(), Error>
+where
+    S::Action: PartialEq,
+{
+    match gif_solution(initial_state, data, solution, delay) {
+        Some(gif) => fs::write(path, gif)?,
+        None => {
+            eprintln!("Couldn't animate the solution, the game has no cells or it doesn't replay")
+        }
     }
+    Ok(())
+}
+
+#[cfg(not(feature = "render"))]
+fn gif_out<S: State>(
+    _: &str,
+    _: &S,
+    _: &S::Data,
+    _: Vec<S::Action>,
+    _: Duration,
+) -> Result<(), Error> {
+    Err(Error::Io(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "--gif-out needs brutalize_cli's render feature",
+    )))
+}
+
+/// Compares an optimal solution length against the game's par. Beating par
+/// means the game's own solution wasn't optimal, which is worth a look.
+fn describe_par(length: usize, par: usize) -> String {
+    match length.cmp(&par) {
+        Ordering::Less => format!("Par: {} (beaten by {}!)", par, par - length),
+        Ordering::Equal => format!("Par: {} (matched)", par),
+        Ordering::Greater => format!(
+            "Par: {} (unreachable, optimal is {} over)",
+            par,
+            length - par
+        ),
+    }
+}
+
+/// Parses and solves a puzzle without printing anything, returning the length
+/// of its shortest solution.
+fn solve_length<S: State>(
+    source: &str,
+    limits: &brutalize::Limits,
+) -> Result<Option<usize>, Error> {
+    let (initial_state, data) = S::parse(source).map_err(|e| Error::Parse(format!("{:?}", e)))?;
+    initial_state
+        .validate(&data)
+        .map_err(Error::InvalidPuzzle)?;
+    let report = brutalize::solve_limited(initial_state, &data, limits, None)?;
+    Ok(report.solution.map(|s| s.len()))
 }
 
-fn solve<S: State>(path: &Path, settings: &Settings) -> Result<(), SolveError<S::ParseError>>
+/// Parses and solves a puzzle as the settings ask. Plain searches borrow
+/// `buffers` for their storage, so that re-solving puzzles with `--watch`
+/// skips most allocations.
+fn solve_source<S: State>(
+    name: &str,
+    source: &str,
+    settings: &Settings,
+    buffers: &mut brutalize::SearchBuffers<S>,
+) -> Result<Option<usize>, Error>
 where
     S::Action: fmt::Display + PartialEq,
+    S::Outcome: fmt::Debug,
 {
     let now = Instant::now();
-    let (initial_state, data) =
-        S::parse(&fs::read_to_string(path)?).map_err(SolveError::ParseError)?;
+    let (initial_state, data) = S::parse(source).map_err(|e| Error::Parse(format!("{:?}", e)))?;
     let parse_elapsed = now.elapsed();
+    initial_state
+        .validate(&data)
+        .map_err(Error::InvalidPuzzle)?;
+
+    if settings.parse_only || settings.render {
+        println!("{}:", name);
+        print!("{}", show(&initial_state, &data, settings));
+        if settings.render && !S::LEGEND.is_empty() {
+            println!();
+            print!("{}", S::LEGEND);
+        }
+        return Ok(None);
+    }
+
+    if let Some(dir) = &settings.render_out {
+        println!("{}:", name);
+        render_out(name, &initial_state, &data, dir)?;
+        return Ok(None);
+    }
+
+    if settings.bound {
+        println!("{}:", name);
+        // The heuristic is admissible, so it never overestimates the length
+        if S::HAS_HEURISTIC {
+            println!(
+                "Lower bound: {:?}",
+                initial_state.heuristic(&data).combine(0)
+            );
+        } else {
+            println!("Lower bound: none (no heuristic)");
+        }
+        return Ok(None);
+    }
+
+    if settings.difficulty {
+        println!("{}:", name);
+        return match brutalize::analyze(initial_state, &data) {
+            Some(analysis) => {
+                println!(
+                    "Difficulty: {:.2} (length {}, {} optimal solution(s), branching factor {:.2}, {} dead end(s))",
+                    analysis.difficulty(),
+                    analysis.length,
+                    analysis.optimal_solutions,
+                    analysis.branching_factor,
+                    analysis.dead_ends
+                );
+                Ok(Some(analysis.length))
+            }
+            None => {
+                println!("No solution");
+                Ok(None)
+            }
+        };
+    }
+
+    if settings.compare {
+        println!("{}:", name);
+        return Ok(compare(initial_state, &data));
+    }
+
+    if let Some(width) = settings.beam {
+        println!("{}:", name);
+        return Ok(beam(initial_state, &data, width, settings.beam_depth));
+    }
+
+    if let Some(solution) = initial_state.solve_waypoints(&data, !settings.waypoint_legs) {
+        println!("{}:", name);
+        return Ok(print_waypoint_solution(solution, settings.waypoint_legs));
+    }
+
+    if settings.json {
+        let report = brutalize::solve_limited_with_buffers(
+            initial_state.clone(),
+            &data,
+            &settings.limits,
+            buffers,
+        )?;
+        let length = report.solution.as_ref().map(Vec::len);
+        let reply = serve::json_reply(&initial_state, &data, report, settings.include_states);
+        println!("{}", reply.json);
+        return Ok(length);
+    }
+
+    if settings.estimate {
+        println!("{}:", name);
+        estimate(initial_state, &data);
+        return Ok(None);
+    }
+
+    if let Some(solution) = &settings.check {
+        println!("{}:", name);
+        return Ok(check(initial_state, &data, solution));
+    }
+
+    // Solutions already written are kept so packs can be solved a bit at a
+    // time
+    let solution_path = solution_path(name, settings);
+    if let Some(path) = &solution_path {
+        if path.exists() && !settings.force {
+            println!("{}: skipped, {} exists", name, path.display());
+            return Ok(None);
+        }
+    }
+
+    if settings.plan && !confirm_plan(name, initial_state.clone(), &data, settings)? {
+        println!("{}: skipped", name);
+        return Ok(None);
+    }
 
     let now = Instant::now();
-    let result = brutalize::solve(initial_state.clone(), &data);
+    let mut stats = if settings.stats {
+        Some(Stats::new(&data))
+    } else {
+        None
+    };
+    let report = solve_with_trace(
+        name,
+        initial_state.clone(),
+        &data,
+        settings,
+        stats.as_mut(),
+        buffers,
+    )?;
     let solve_elapsed = now.elapsed();
+    let length = report.solution.as_ref().map(Vec::len);
 
-    println!("{}:", path.to_str().unwrap());
+    println!("{}:", name);
     println!(
         "Parse: {}.{:09}s",
         parse_elapsed.as_secs(),
@@ -94,24 +1019,60 @@ where
         solve_elapsed.as_secs(),
         solve_elapsed.subsec_nanos()
     );
+    println!("Memory: ~{}", report.memory);
+    if let Some(stats) = &stats {
+        stats.print(report.solution.as_deref());
+    }
+    if settings.check_heuristic {
+        if let Some(solution) = &report.solution {
+            print_overestimates(initial_state.clone(), &data, solution);
+        }
+    }
+    if let (Some(path), Some(solution)) = (&solution_path, &report.solution) {
+        fs::write(path, write_solution::<S>(solution) + "\n")?;
+    }
+    if let (Some(path), Some(solution)) = (&settings.html_out, &report.solution) {
+        match html_walkthrough(name, &initial_state, &data, solution.clone()) {
+            Some(html) => fs::write(path, html)?,
+            None => eprintln!("Solution does not replay, transitions are not deterministic"),
+        }
+    }
+    if let (Some(path), Some(solution)) = (&settings.gif_out, &report.solution) {
+        gif_out(
+            path,
+            &initial_state,
+            &data,
+            solution.clone(),
+            settings.frame_delay,
+        )?;
+    }
 
     if !settings.quiet {
-        if let Some(solution) = result {
+        if let Some(solution) = report.solution {
             println!("Found solution of length {}:", solution.len());
+            if let Some(par) = S::par(&data) {
+                println!("{}", describe_par(solution.len(), par));
+            }
+            // Games with a single way to win use `()`, which isn't worth printing
+            if mem::size_of::<S::Outcome>() != 0 {
+                if let Some(outcome) = &report.outcome {
+                    println!("Outcome: {:?}", outcome);
+                }
+            }
 
             if settings.verbose {
-                let mut state = initial_state;
-                for action in solution {
-                    println!("{}", DisplayState(&state, &data));
-                    println!("{}", action);
-                    if let brutalize::Transition::Indeterminate(s) = state
-                        .transitions(&data)
-                        .into_iter()
-                        .find(|(a, _)| a == &action)
-                        .unwrap()
-                        .1
-                    {
-                        state = s;
+                println!("{}", show(&initial_state, &data, settings));
+                match brutalize::replay(initial_state, &data, solution) {
+                    Some(steps) => {
+                        for (action, transition) in steps {
+                            println!("{}", action);
+                            if let brutalize::Transition::Indeterminate(state) = transition {
+                                println!("{}", show(&state, &data, settings));
+                            }
+                        }
+                    }
+                    None => {
+                        eprintln!("Solution does not replay, transitions are not deterministic")
                     }
                 }
             } else {
@@ -123,19 +1084,80 @@ where
                     print!(", {}", action);
                 }
                 println!();
+                if let Some(notation) = S::write_solution(&solution) {
+                    println!("Notation: {}", notation);
+                }
             }
+        } else if let Some(reason) = &report.infeasible {
+            println!("No solution: {}", reason);
         } else {
             println!("No solution");
         }
     }
 
-    Ok(())
+    Ok(length)
 }
 
 #[cfg(test)]
 mod tests {
+    use super::{describe_par, parse_args};
+
     #[test]
     fn it_works() {
         assert_eq!(2 + 2, 4);
     }
+
+    #[test]
+    fn par_comparison() {
+        assert_eq!(describe_par(8, 10), "Par: 10 (beaten by 2!)");
+        assert_eq!(describe_par(10, 10), "Par: 10 (matched)");
+        assert_eq!(
+            describe_par(11, 10),
+            "Par: 10 (unreachable, optimal is 1 over)"
+        );
+    }
+
+    #[test]
+    fn reject_bad_limits() {
+        let parse = |args: &[&str]| parse_args(args.iter().map(|a| a.to_string())).err();
+        assert_eq!(
+            parse(&["--budget", "10k"]).as_deref(),
+            Some("invalid value '10k' for --budget")
+        );
+        assert_eq!(
+            parse(&["--timeout", "-1"]).as_deref(),
+            Some("invalid value '-1' for --timeout")
+        );
+        assert_eq!(
+            parse(&["p.txt", "--timeout"]).as_deref(),
+            Some("--timeout needs a value")
+        );
+        assert_eq!(
+            parse(&["--per-puzzle-timeout", "1m"]).as_deref(),
+            Some("invalid value '1m' for --per-puzzle-timeout")
+        );
+        assert_eq!(
+            parse(&["--frame-delay", "fast"]).as_deref(),
+            Some("invalid value 'fast' for --frame-delay")
+        );
+        assert_eq!(
+            parse(&["--frame-delay"]).as_deref(),
+            Some("--frame-delay needs a value")
+        );
+        assert_eq!(parse(&["--budget", "10", "--timeout", "0.5"]), None);
+    }
+
+    #[test]
+    fn reject_bad_beam_widths() {
+        let parse = |args: &[&str]| parse_args(args.iter().map(|a| a.to_string())).err();
+        assert_eq!(
+            parse(&["--beam", "wide"]).as_deref(),
+            Some("invalid value 'wide' for --beam")
+        );
+        assert_eq!(parse(&["--beam"]).as_deref(), Some("--beam needs a value"));
+        assert_eq!(
+            parse(&["--beam", "8", "--beam-depth", "-3"]).as_deref(),
+            Some("invalid value '-3' for --beam-depth")
+        );
+    }
 }
@@ -0,0 +1,201 @@
+use crate::serve::Reply;
+use std::{
+    fmt::Write as _,
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+/// Counts of the requests a server has handled, shared with the thread that
+/// reports them.
+#[derive(Default)]
+struct Metrics {
+    solves: AtomicUsize,
+    failures: AtomicUsize,
+    searches: AtomicUsize,
+    expanded: AtomicUsize,
+    in_flight: AtomicUsize,
+}
+
+impl Metrics {
+    fn record(&self, reply: &Reply) {
+        let counter = if reply.solved {
+            &self.solves
+        } else {
+            &self.failures
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        if let Some(expanded) = reply.expanded {
+            self.searches.fetch_add(1, Ordering::Relaxed);
+            self.expanded.fetch_add(expanded, Ordering::Relaxed);
+        }
+    }
+
+    /// Writes the metrics in Prometheus' text format. The average number of
+    /// expansions is left to the scraper, which divides the sum by the count.
+    fn render(&self) -> String {
+        let mut result = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, values: &[(&str, usize)]| {
+            writeln!(result, "# HELP {} {}", name, help).unwrap();
+            writeln!(result, "# TYPE {} {}", name, kind).unwrap();
+            for (suffix, value) in values {
+                writeln!(result, "{}{} {}", name, suffix, value).unwrap();
+            }
+        };
+        let load = |counter: &AtomicUsize| counter.load(Ordering::Relaxed);
+
+        metric(
+            "brutalize_solves_total",
+            "counter",
+            "Requests answered with a solution.",
+            &[("", load(&self.solves))],
+        );
+        metric(
+            "brutalize_failures_total",
+            "counter",
+            "Requests answered with an error or without a solution.",
+            &[("", load(&self.failures))],
+        );
+        metric(
+            "brutalize_expanded_states",
+            "summary",
+            "States expanded by each search.",
+            &[
+                ("_sum", load(&self.expanded)),
+                ("_count", load(&self.searches)),
+            ],
+        );
+        metric(
+            "brutalize_in_flight_requests",
+            "gauge",
+            "Requests being solved right now.",
+            &[("", load(&self.in_flight))],
+        );
+        result
+    }
+}
+
+/// Answers a single HTTP request, with the metrics for `GET /metrics` and a
+/// 404 for anything else.
+fn answer(stream: TcpStream, metrics: &Metrics) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // The headers don't matter, but they have to be read before replying
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut stream = reader.into_inner();
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", metrics.render()),
+        _ => ("404 Not Found", "Not found\n".to_string()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+/// Answers requests for metrics on a thread of their own.
+fn report(listener: TcpListener, metrics: Arc<Metrics>) {
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            if let Err(e) = stream.and_then(|stream| answer(stream, &metrics)) {
+                eprintln!("Error while serving metrics: {}", e);
+            }
+        }
+    });
+}
+
+/// Wraps `respond` so that it records every request it answers.
+fn measure(
+    metrics: Arc<Metrics>,
+    mut respond: impl FnMut(&str) -> Reply,
+) -> impl FnMut(&str) -> Reply {
+    move |source| {
+        metrics.in_flight.fetch_add(1, Ordering::Relaxed);
+        let reply = respond(source);
+        metrics.in_flight.fetch_sub(1, Ordering::Relaxed);
+        metrics.record(&reply);
+        reply
+    }
+}
+
+/// Starts serving metrics over HTTP on `address`, returning a version of
+/// `respond` which records every request it answers.
+pub(crate) fn instrument(
+    address: &str,
+    respond: impl FnMut(&str) -> Reply,
+) -> io::Result<impl FnMut(&str) -> Reply> {
+    let listener = TcpListener::bind(address)?;
+    println!(
+        "Serving metrics on http://{}/metrics",
+        listener.local_addr()?
+    );
+    let metrics = Arc::new(Metrics::default());
+    report(listener, metrics.clone());
+    Ok(measure(metrics, respond))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn reply(solved: bool, expanded: Option<usize>) -> Reply {
+        Reply {
+            json: String::new(),
+            solved,
+            expanded,
+        }
+    }
+
+    #[test]
+    fn render_counts() {
+        let metrics = Metrics::default();
+        metrics.record(&reply(true, Some(10)));
+        metrics.record(&reply(false, Some(4)));
+        metrics.record(&reply(false, None));
+
+        let rendered = metrics.render();
+        assert!(rendered.contains("# TYPE brutalize_solves_total counter\n"));
+        assert!(rendered.contains("\nbrutalize_solves_total 1\n"));
+        assert!(rendered.contains("\nbrutalize_failures_total 2\n"));
+        assert!(rendered.contains("\nbrutalize_expanded_states_sum 14\n"));
+        assert!(rendered.contains("\nbrutalize_expanded_states_count 2\n"));
+        assert!(rendered.contains("\nbrutalize_in_flight_requests 0\n"));
+    }
+
+    #[test]
+    fn scrape_over_http() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let metrics = Arc::new(Metrics::default());
+        report(listener, metrics.clone());
+        let mut respond = measure(metrics, |_| reply(true, Some(3)));
+        respond("puzzle");
+
+        let get = |path: &str| {
+            let mut stream = TcpStream::connect(address).unwrap();
+            write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+        let response = get("/metrics");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        assert!(response.contains("\nbrutalize_solves_total 1\n"));
+        assert!(response.contains("\nbrutalize_expanded_states_sum 3\n"));
+        assert!(get("/").starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+}
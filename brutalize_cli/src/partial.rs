@@ -0,0 +1,47 @@
+use crate::State;
+
+/// Remembers the expanded state the heuristic puts closest to a goal and the
+/// deepest any expanded state was, for `--per-puzzle-timeout` to show how far
+/// a search got before it ran out of time.
+pub(crate) struct Closest<'a, S: State> {
+    data: &'a S::Data,
+    best: Option<(S::Heuristic, usize, S)>,
+    depth: usize,
+}
+
+impl<'a, S: State> Closest<'a, S> {
+    pub fn new(data: &'a S::Data) -> Self {
+        Self {
+            data,
+            best: None,
+            depth: 0,
+        }
+    }
+
+    /// The deepest any expanded state was.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The closest state along with its heuristic and depth. Ties go to the
+    /// state expanded first, which is the shallowest of them.
+    pub fn best(&self) -> Option<(&S::Heuristic, usize, &S)> {
+        self.best
+            .as_ref()
+            .map(|(heuristic, depth, state)| (heuristic, *depth, state))
+    }
+}
+
+impl<'a, S: State> brutalize::Observer<S> for Closest<'a, S> {
+    fn expand(&mut self, depth: usize, _: &S::Heuristic, state: &S) {
+        self.depth = self.depth.max(depth);
+        let heuristic = state.heuristic(self.data);
+        if self
+            .best
+            .as_ref()
+            .is_none_or(|(best, ..)| heuristic < *best)
+        {
+            self.best = Some((heuristic, depth, state.clone()));
+        }
+    }
+}
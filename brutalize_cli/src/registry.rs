@@ -0,0 +1,236 @@
+use crate::{
+    completions::{self, GAME_OPTION, SOLVE_OPTIONS, SUMMARY_OPTION},
+    index, parse_args_or_exit, print_trace_csv,
+    serve::{self, Reply, Respond},
+    solve_length, solver, summary, watch, Error, Settings, Solver, State,
+};
+use std::{collections::HashMap, env, fmt, fs, process};
+
+/// A game that a multi-game binary can solve puzzles for, looked up by name.
+pub struct Game {
+    name: &'static str,
+    solver: fn() -> Solver,
+    responder: fn(&brutalize::Limits) -> Respond,
+    length: fn(&str, &brutalize::Limits) -> Result<Option<usize>, Error>,
+    demo: fn() -> Option<&'static str>,
+}
+
+impl Game {
+    pub fn new<S: State + 'static>(name: &'static str) -> Self
+    where
+        S::Action: fmt::Display + PartialEq,
+        S::Outcome: fmt::Debug,
+    {
+        Self {
+            name,
+            solver: solver::<S>,
+            responder: serve::responder::<S>,
+            length: solve_length::<S>,
+            demo: S::demo,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+/// Splits a leading `game NAME` line off of a puzzle, returning the name and
+/// the rest of the puzzle.
+fn split_header(source: &str) -> (Option<&str>, &str) {
+    match source.strip_prefix("game ") {
+        Some(rest) => {
+            let (name, rest) = rest.split_at(rest.find('\n').unwrap_or(rest.len()));
+            (
+                Some(name.trim_end()),
+                rest.strip_prefix('\n').unwrap_or(rest),
+            )
+        }
+        None => (None, source),
+    }
+}
+
+/// Runs a binary that can solve puzzles for any of the given games. The game
+/// is picked by a `game NAME` header line in the puzzle, falling back to the
+/// `--game NAME` argument for puzzles without one. `--summary` solves a whole
+/// collection of puzzles and reports stats for each game.
+pub fn execute_registry(games: &[Game]) {
+    let names = games.iter().map(|g| g.name).collect::<Vec<_>>();
+    let raw_args = env::args().skip(1).collect::<Vec<_>>();
+    let options = SOLVE_OPTIONS
+        .iter()
+        .chain([&GAME_OPTION, &SUMMARY_OPTION])
+        .collect::<Vec<_>>();
+    if completions::generate(&raw_args, &options, &names) {
+        return;
+    }
+
+    let mut default_game = None;
+    let mut summary = false;
+    let mut args = Vec::new();
+    let mut raw_args = raw_args.into_iter();
+    while let Some(arg) = raw_args.next() {
+        if arg == "--game" {
+            default_game = raw_args.next();
+        } else if arg == "--summary" {
+            summary = true;
+        } else {
+            args.push(arg);
+        }
+    }
+    let (settings, paths) = parse_args_or_exit(args.into_iter());
+    // Each game keeps its own search storage, made when it's first needed
+    let mut solvers = Solvers::new();
+
+    if let Some(path) = &settings.trace_csv {
+        print_trace_csv(path);
+    } else if let Some(address) = &settings.serve {
+        let mut responders = HashMap::new();
+        serve::run(address, settings.metrics.as_deref(), |source| {
+            respond(
+                games,
+                &mut responders,
+                default_game.as_deref(),
+                source,
+                &settings.limits,
+            )
+        });
+    } else if settings.demo {
+        // Solves every game's demo, named by the game
+        let names = games
+            .iter()
+            .filter(|g| (g.demo)().is_some())
+            .map(|g| g.name.to_string())
+            .collect::<Vec<_>>();
+        let succeeded = watch::solve_paths(&names, false, |name| {
+            let game = find_game(games, Some(name))?;
+            let source = (game.demo)().unwrap_or_default();
+            solve_with(&mut solvers, game, name, source, &settings)
+        });
+        if !succeeded {
+            process::exit(1);
+        }
+    } else if paths.is_empty() {
+        completions::print_usage(&options, &names);
+    } else if summary {
+        let solved = summary::print_summary(&paths, |path| {
+            summarize(games, default_game.as_deref(), path, &settings.limits)
+        });
+        if !solved {
+            process::exit(1);
+        }
+    } else if let Some(index) = &settings.index {
+        let unchanged = index::write_index(&paths, index, settings.diff_index.as_deref(), |path| {
+            solve(
+                games,
+                &mut solvers,
+                default_game.as_deref(),
+                path,
+                &settings,
+            )
+        });
+        if !unchanged {
+            process::exit(1);
+        }
+    } else {
+        let succeeded = watch::solve_paths(&paths, settings.watch, |path| {
+            solve(
+                games,
+                &mut solvers,
+                default_game.as_deref(),
+                path,
+                &settings,
+            )
+        });
+        if !succeeded {
+            process::exit(1);
+        }
+    }
+}
+
+type Solvers = HashMap<&'static str, Solver>;
+
+fn solve(
+    games: &[Game],
+    solvers: &mut Solvers,
+    default_game: Option<&str>,
+    path: &str,
+    settings: &Settings,
+) -> Result<Option<usize>, Error> {
+    let source = fs::read_to_string(path)?;
+    let (header, puzzle) = split_header(&source);
+    let game = find_game(games, header.or(default_game))?;
+    solve_with(solvers, game, path, puzzle, settings)
+}
+
+/// Solves a puzzle with the game's solver, making it on first use.
+fn solve_with(
+    solvers: &mut Solvers,
+    game: &Game,
+    name: &str,
+    source: &str,
+    settings: &Settings,
+) -> Result<Option<usize>, Error> {
+    let solve = solvers.entry(game.name).or_insert_with(game.solver);
+    solve(name, source, settings)
+}
+
+/// Solves a puzzle quietly for `--summary`, returning the name of the game it
+/// was picked for if any.
+fn summarize(
+    games: &[Game],
+    default_game: Option<&str>,
+    path: &str,
+    limits: &brutalize::Limits,
+) -> (Option<String>, Result<Option<usize>, Error>) {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(e) => return (None, Err(e.into())),
+    };
+    let (header, puzzle) = split_header(&source);
+    match find_game(games, header.or(default_game)) {
+        Ok(game) => (Some(game.name.to_string()), (game.length)(puzzle, limits)),
+        Err(e) => (None, Err(e)),
+    }
+}
+
+fn find_game<'a>(games: &'a [Game], name: Option<&str>) -> Result<&'a Game, Error> {
+    let name = name.ok_or(Error::MissingGame)?;
+    games
+        .iter()
+        .find(|g| g.name == name)
+        .ok_or_else(|| Error::UnknownGame(name.to_string()))
+}
+
+type Responders = HashMap<&'static str, Respond>;
+
+fn respond(
+    games: &[Game],
+    responders: &mut Responders,
+    default_game: Option<&str>,
+    source: &str,
+    limits: &brutalize::Limits,
+) -> Reply {
+    let (header, puzzle) = split_header(source);
+    match find_game(games, header.or(default_game)) {
+        Ok(game) => {
+            let respond = responders
+                .entry(game.name)
+                .or_insert_with(|| (game.responder)(limits));
+            respond(puzzle)
+        }
+        Err(e) => Reply::error(&e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::split_header;
+
+    #[test]
+    fn split_game_header() {
+        assert_eq!(split_header("game anima\n..r\n"), (Some("anima"), "..r\n"));
+        assert_eq!(split_header("game sausage"), (Some("sausage"), ""));
+        assert_eq!(split_header("puzzle 1 1\n.\n"), (None, "puzzle 1 1\n.\n"));
+    }
+}
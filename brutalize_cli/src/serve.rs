@@ -0,0 +1,421 @@
+use crate::{Error, State};
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    mem,
+    net::{TcpListener, TcpStream},
+    time::{Duration, Instant},
+};
+
+/// How long a client gets to send its puzzle, or to take the response,
+/// before it's given up on so that other clients aren't kept waiting.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The largest puzzle a client may send, so that one client can't make the
+/// server buffer without end.
+const MAX_REQUEST_BYTES: u64 = 1 << 20;
+
+/// Quotes a string as a JSON string literal.
+pub(crate) fn json_string(s: &str) -> String {
+    let mut result = String::with_capacity(s.len() + 2);
+    result.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// Describes an error as a JSON object, with its category so clients can
+/// tell bad puzzles apart from searches that gave up.
+pub(crate) fn json_error(error: &Error) -> String {
+    format!(
+        "{{\"error\":{},\"category\":{}}}",
+        json_string(&error.to_string()),
+        json_string(error.category())
+    )
+}
+
+/// The response sent back to a client, along with how the request went.
+// Only the metrics feature looks at how requests went
+#[cfg_attr(not(feature = "metrics"), allow(dead_code))]
+pub(crate) struct Reply {
+    pub(crate) json: String,
+    /// Whether a solution was found.
+    pub(crate) solved: bool,
+    /// How many states were expanded, if a search ran at all.
+    pub(crate) expanded: Option<usize>,
+}
+
+impl Reply {
+    pub(crate) fn error(error: &Error) -> Self {
+        Self {
+            json: json_error(error),
+            solved: false,
+            expanded: None,
+        }
+    }
+}
+
+/// Parses and solves a puzzle within `limits`, describing the result as a
+/// JSON object. The search borrows `buffers` so that a server answering many
+/// requests doesn't allocate its storage afresh for each one.
+pub(crate) fn solve_json<S: State>(
+    source: &str,
+    limits: &brutalize::Limits,
+    buffers: &mut brutalize::SearchBuffers<S>,
+) -> Reply
+where
+    S::Action: fmt::Display + PartialEq,
+    S::Outcome: fmt::Debug,
+{
+    let (initial_state, data) = match S::parse(source) {
+        Ok(parsed) => parsed,
+        Err(e) => return Reply::error(&Error::Parse(format!("{:?}", e))),
+    };
+    if let Err(e) = initial_state.validate(&data) {
+        return Reply::error(&Error::InvalidPuzzle(e));
+    }
+
+    let report = match brutalize::solve_limited_with_buffers(
+        initial_state.clone(),
+        &data,
+        limits,
+        buffers,
+    ) {
+        Ok(report) => report,
+        Err(e) => return Reply::error(&e.into()),
+    };
+    json_reply(&initial_state, &data, report, false)
+}
+
+/// Answers a request, like `solve_json` bound to a game.
+pub(crate) type Respond = Box<dyn FnMut(&str) -> Reply>;
+
+/// Makes a function that answers requests for puzzles of one game within
+/// `limits`, holding on to its search storage from one request to the next.
+pub(crate) fn responder<S: State + 'static>(limits: &brutalize::Limits) -> Respond
+where
+    S::Action: fmt::Display + PartialEq,
+    S::Outcome: fmt::Debug,
+{
+    let limits = *limits;
+    let mut buffers = brutalize::SearchBuffers::new();
+    Box::new(move |source| solve_json::<S>(source, &limits, &mut buffers))
+}
+
+/// Describes a finished search as a JSON object. With `include_states` set,
+/// also lists each step of the solution with the state it leads to, which is
+/// `null` for the final step and for games that can't serialize states.
+pub(crate) fn json_reply<S: State>(
+    initial_state: &S,
+    data: &S::Data,
+    report: brutalize::SolveReport<S::Action, S::Outcome>,
+    include_states: bool,
+) -> Reply
+where
+    S::Action: fmt::Display + PartialEq,
+    S::Outcome: fmt::Debug,
+{
+    let solved = report.solution.is_some();
+    let mut notation = String::new();
+    let mut steps = String::new();
+    let solution = match report.solution {
+        Some(solution) => {
+            if let Some(written) = S::write_solution(&solution) {
+                notation = format!(",\"notation\":{}", json_string(&written));
+            }
+            if include_states {
+                let json = json_steps(initial_state, data, solution.clone());
+                steps = format!(",\"steps\":{}", json);
+            }
+            let actions = solution
+                .iter()
+                .map(|a| json_string(&a.to_string()))
+                .collect::<Vec<_>>();
+            format!("[{}]", actions.join(","))
+        }
+        None => "null".to_string(),
+    };
+    let infeasible = match &report.infeasible {
+        Some(reason) => format!(",\"infeasible\":{}", json_string(reason)),
+        None => String::new(),
+    };
+    let outcome = match report.outcome {
+        Some(outcome) if mem::size_of::<S::Outcome>() != 0 => {
+            format!(",\"outcome\":{}", json_string(&format!("{:?}", outcome)))
+        }
+        _ => String::new(),
+    };
+    Reply {
+        json: format!(
+            "{{\"solution\":{}{}{},\"expanded\":{}{}{}}}",
+            solution, notation, steps, report.expanded, outcome, infeasible
+        ),
+        solved,
+        expanded: Some(report.expanded),
+    }
+}
+
+/// Replays a solution as a JSON array of steps, each with its action and the
+/// state it leads to. The initial state comes first with no action.
+fn json_steps<S: State>(initial_state: &S, data: &S::Data, solution: Vec<S::Action>) -> String
+where
+    S::Action: fmt::Display + PartialEq,
+{
+    let json_state = |state: &S| state.to_json().unwrap_or_else(|| "null".to_string());
+    let mut steps = vec![format!(
+        "{{\"action\":null,\"state\":{}}}",
+        json_state(initial_state)
+    )];
+    for (action, transition) in brutalize::replay(initial_state.clone(), data, solution)
+        .into_iter()
+        .flatten()
+    {
+        let state = match &transition {
+            brutalize::Transition::Indeterminate(state) => json_state(state),
+            brutalize::Transition::Success(_) => "null".to_string(),
+        };
+        steps.push(format!(
+            "{{\"action\":{},\"state\":{}}}",
+            json_string(&action.to_string()),
+            state
+        ));
+    }
+    format!("[{}]", steps.join(","))
+}
+
+/// Reads a client's puzzle, giving up once `deadline` passes even if the
+/// client keeps trickling in bytes, or once it sends more than
+/// `MAX_REQUEST_BYTES`.
+fn read_request(stream: &mut TcpStream, deadline: Instant) -> io::Result<String> {
+    let mut request = Vec::new();
+    let mut limited = (&*stream).take(MAX_REQUEST_BYTES + 1);
+    let mut chunk = [0; 4096];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "the puzzle took too long to send",
+            ));
+        }
+        stream.set_read_timeout(Some(remaining))?;
+        match limited.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => request.extend_from_slice(&chunk[..read]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    if request.len() as u64 > MAX_REQUEST_BYTES {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("the puzzle is larger than {} bytes", MAX_REQUEST_BYTES),
+        ));
+    }
+    String::from_utf8(request).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Answers connections one at a time. Each client sends a puzzle and closes
+/// its side of the connection, then gets back the response followed by a
+/// newline. Clients that take longer than `timeout` in all to send their
+/// puzzle, or longer than `timeout` to take the response, are given up on.
+pub(crate) fn serve(
+    listener: TcpListener,
+    timeout: Duration,
+    mut respond: impl FnMut(&str) -> Reply,
+) -> io::Result<()> {
+    for stream in listener.incoming() {
+        // A connection failing before it's accepted shouldn't stop the server
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("Error while accepting a connection: {}", e);
+                continue;
+            }
+        };
+        let deadline = Instant::now() + timeout;
+        let response = match stream
+            .set_write_timeout(Some(timeout))
+            .and_then(|_| read_request(&mut stream, deadline))
+        {
+            Ok(source) => respond(&source).json,
+            Err(e) => json_error(&Error::Io(e)),
+        };
+        // A client hanging up early shouldn't stop the server
+        if let Err(e) = writeln!(stream, "{}", response) {
+            eprintln!("Error while responding: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Binds to `address` and serves until binding or serving metrics fails. Metrics about the
+/// requests are served over HTTP on `metrics_address`, if given.
+pub(crate) fn run(
+    address: &str,
+    metrics_address: Option<&str>,
+    respond: impl FnMut(&str) -> Reply,
+) {
+    let result = TcpListener::bind(address).and_then(|listener| {
+        println!("Listening on {}", listener.local_addr()?);
+        match metrics_address {
+            #[cfg(feature = "metrics")]
+            Some(metrics_address) => serve(
+                listener,
+                CLIENT_TIMEOUT,
+                crate::metrics::instrument(metrics_address, respond)?,
+            ),
+            #[cfg(not(feature = "metrics"))]
+            Some(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "--metrics needs brutalize_cli's metrics feature",
+            )),
+            None => serve(listener, CLIENT_TIMEOUT, respond),
+        }
+    });
+    if let Err(e) = result {
+        eprintln!("Error while serving on '{}':\n{:?}", address, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn escape_json_strings() {
+        assert_eq!(json_string("a\"b\\c\nd\u{1}"), "\"a\\\"b\\\\c\\nd\\u0001\"");
+    }
+
+    #[test]
+    fn serve_round_trip() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            serve(listener, CLIENT_TIMEOUT, |source| Reply {
+                json: json_string(&source.to_uppercase()),
+                solved: true,
+                expanded: None,
+            })
+        });
+
+        for _ in 0..2 {
+            let mut stream = TcpStream::connect(address).unwrap();
+            stream.write_all(b"puzzle").unwrap();
+            stream.shutdown(std::net::Shutdown::Write).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            assert_eq!(response, "\"PUZZLE\"\n");
+        }
+    }
+
+    #[test]
+    fn serve_past_stalled_clients() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            serve(listener, Duration::from_millis(100), |source| Reply {
+                json: json_string(source),
+                solved: true,
+                expanded: None,
+            })
+        });
+
+        // This client never finishes sending its puzzle
+        let mut stalled = TcpStream::connect(address).unwrap();
+        stalled.write_all(b"puz").unwrap();
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(b"puzzle").unwrap();
+        stream.shutdown(std::net::Shutdown::Write).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert_eq!(response, "\"puzzle\"\n");
+
+        let mut response = String::new();
+        stalled.read_to_string(&mut response).unwrap();
+        assert!(response.contains("\"category\":\"io\""), "{}", response);
+    }
+
+    #[test]
+    fn serve_past_trickling_clients() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            serve(listener, Duration::from_millis(300), |source| Reply {
+                json: json_string(source),
+                solved: true,
+                expanded: None,
+            })
+        });
+
+        // This client sends a byte at a time, each well within the timeout,
+        // for far longer than the timeout
+        let mut trickling = TcpStream::connect(address).unwrap();
+        let mut writer = trickling.try_clone().unwrap();
+        thread::spawn(move || {
+            for _ in 0..100 {
+                if writer.write_all(b"p").is_err() {
+                    break;
+                }
+                thread::sleep(Duration::from_millis(50));
+            }
+        });
+
+        // Both clients would still be waiting without an overall deadline
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(b"puzzle").unwrap();
+        stream.shutdown(std::net::Shutdown::Write).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(2)))
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert_eq!(response, "\"puzzle\"\n");
+
+        // Closing with the trickled bytes unread can reset the connection
+        // once the response is in, so keep whatever arrived before that
+        trickling
+            .set_read_timeout(Some(Duration::from_secs(2)))
+            .unwrap();
+        let mut response = Vec::new();
+        let mut chunk = [0; 256];
+        while let Ok(read @ 1..) = trickling.read(&mut chunk) {
+            response.extend_from_slice(&chunk[..read]);
+        }
+        let response = String::from_utf8(response).unwrap();
+        assert!(response.contains("\"category\":\"io\""), "{}", response);
+    }
+
+    #[test]
+    fn serve_reject_oversized_requests() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            serve(listener, CLIENT_TIMEOUT, |source| Reply {
+                json: json_string(&source.len().to_string()),
+                solved: true,
+                expanded: None,
+            })
+        });
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        // The server stops reading partway, so the rest of the write may fail
+        let _ = stream.write_all(&vec![b'.'; MAX_REQUEST_BYTES as usize + 1]);
+        let _ = stream.shutdown(std::net::Shutdown::Write);
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.contains("larger than"), "{}", response);
+    }
+}
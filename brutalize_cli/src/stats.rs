@@ -0,0 +1,69 @@
+use crate::State;
+use std::{collections::BTreeMap, fmt};
+
+/// Counts how many moves each expanded state offered, for `--stats`.
+/// Games prune actions by leaving them out of a state's transitions, so an
+/// action offered less often than states were expanded was pruned the rest of
+/// the time.
+pub(crate) struct Stats<'a, S: State> {
+    data: &'a S::Data,
+    expanded: usize,
+    transitions: usize,
+    offered: BTreeMap<String, usize>,
+}
+
+impl<'a, S: State> Stats<'a, S>
+where
+    S::Action: fmt::Display,
+{
+    pub fn new(data: &'a S::Data) -> Self {
+        Self {
+            data,
+            expanded: 0,
+            transitions: 0,
+            offered: BTreeMap::new(),
+        }
+    }
+
+    /// Prints the average branching factor, then how often each action
+    /// appears in `solution` and how often it was pruned.
+    pub fn print(&self, solution: Option<&[S::Action]>) {
+        let branching_factor = self.transitions as f64 / self.expanded.max(1) as f64;
+        println!(
+            "Branching factor: {:.2} ({} transitions from {} states)",
+            branching_factor, self.transitions, self.expanded
+        );
+
+        let mut used = BTreeMap::new();
+        for action in solution.unwrap_or_default() {
+            *used.entry(action.to_string()).or_insert(0) += 1;
+        }
+
+        println!("{:<12} {:>8} {:>8}", "Action", "Used", "Pruned");
+        for (action, offered) in self.offered.iter() {
+            let pruned =
+                self.expanded.saturating_sub(*offered) as f64 / self.expanded.max(1) as f64;
+            println!(
+                "{:<12} {:>8} {:>7.1}%",
+                action,
+                used.get(action).unwrap_or(&0),
+                pruned * 100.0
+            );
+        }
+    }
+}
+
+impl<'a, S: State> brutalize::Observer<S> for Stats<'a, S>
+where
+    S::Action: fmt::Display,
+{
+    fn expand(&mut self, _: usize, _: &S::Heuristic, state: &S) {
+        self.expanded += 1;
+        // Searches don't hand transitions to observers, so they're generated
+        // again here. That doubles the work, but only while collecting stats.
+        for (action, _) in state.transitions(self.data) {
+            self.transitions += 1;
+            *self.offered.entry(action.to_string()).or_insert(0) += 1;
+        }
+    }
+}
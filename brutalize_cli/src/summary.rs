@@ -0,0 +1,146 @@
+use crate::{index, Error};
+use std::{collections::BTreeMap, fmt::Write, path::Path};
+
+/// Totals for the puzzles of one game, printed by `--summary`.
+#[derive(Debug, Default)]
+struct GameSummary {
+    puzzles: usize,
+    /// The optimal solution length of every solved puzzle.
+    lengths: Vec<usize>,
+    /// The longest solution and the puzzle it solves.
+    longest: Option<(usize, String)>,
+}
+
+impl GameSummary {
+    fn add(&mut self, puzzle: &str, length: Option<usize>) {
+        self.puzzles += 1;
+        if let Some(length) = length {
+            self.lengths.push(length);
+            if self.longest.as_ref().is_none_or(|(l, _)| length > *l) {
+                self.longest = Some((length, puzzle.to_string()));
+            }
+        }
+    }
+
+    fn average(&self) -> Option<f64> {
+        if self.lengths.is_empty() {
+            None
+        } else {
+            Some(self.lengths.iter().sum::<usize>() as f64 / self.lengths.len() as f64)
+        }
+    }
+}
+
+/// Aggregate stats for a collection of puzzles across every game.
+#[derive(Debug, Default)]
+struct Summary {
+    games: BTreeMap<String, GameSummary>,
+    /// Each puzzle without a solution, with `unsolvable` or the category of
+    /// the error that stopped it.
+    unsolved: Vec<(String, String)>,
+}
+
+impl Summary {
+    /// Records how solving `puzzle` went. Puzzles whose game couldn't be
+    /// told are counted under `?`.
+    fn add(&mut self, puzzle: &str, game: Option<&str>, result: Result<Option<usize>, Error>) {
+        let length = match result {
+            Ok(Some(length)) => Some(length),
+            Ok(None) => {
+                self.unsolved
+                    .push((puzzle.to_string(), "unsolvable".to_string()));
+                None
+            }
+            Err(e) => {
+                self.unsolved
+                    .push((puzzle.to_string(), e.category().to_string()));
+                None
+            }
+        };
+        self.games
+            .entry(game.unwrap_or("?").to_string())
+            .or_default()
+            .add(puzzle, length);
+    }
+
+    fn write(&self) -> String {
+        let mut result = String::new();
+        for (game, summary) in self.games.iter() {
+            write!(
+                result,
+                "{}: {} puzzle(s), {} solved",
+                game,
+                summary.puzzles,
+                summary.lengths.len()
+            )
+            .unwrap();
+            if let Some(average) = summary.average() {
+                write!(result, ", average length {:.1}", average).unwrap();
+            }
+            if let Some((length, puzzle)) = &summary.longest {
+                write!(result, ", longest {} ({})", length, puzzle).unwrap();
+            }
+            result.push('\n');
+        }
+        if !self.unsolved.is_empty() {
+            writeln!(result, "Unsolved:").unwrap();
+            for (puzzle, status) in self.unsolved.iter() {
+                writeln!(result, "  {}: {}", puzzle, status).unwrap();
+            }
+        }
+        result
+    }
+}
+
+/// Solves every puzzle under `paths` and prints stats for each game: how many
+/// puzzles it has, how many were solved, their average and longest solution,
+/// and which puzzles went unsolved. `solve` returns the game a puzzle was
+/// picked for along with how solving it went. Returns whether every puzzle
+/// was solved.
+pub(crate) fn print_summary(
+    paths: &[String],
+    mut solve: impl FnMut(&str) -> (Option<String>, Result<Option<usize>, Error>),
+) -> bool {
+    let mut puzzles = Vec::new();
+    for path in paths {
+        if let Err(e) = index::collect(Path::new(path), &mut puzzles) {
+            eprintln!("Error while reading '{}': {}", path, e);
+            return false;
+        }
+    }
+    puzzles.sort();
+
+    let mut summary = Summary::default();
+    for puzzle in puzzles.iter() {
+        let (game, result) = solve(puzzle);
+        summary.add(puzzle, game.as_deref(), result);
+    }
+    print!("{}", summary.write());
+    summary.unsolved.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aggregate_games() {
+        let mut summary = Summary::default();
+        summary.add("a/1.txt", Some("anima"), Ok(Some(4)));
+        summary.add("a/2.txt", Some("anima"), Ok(Some(9)));
+        summary.add("a/3.txt", Some("anima"), Ok(None));
+        summary.add("s/1.txt", Some("sausage"), Err(Error::Budget(10)));
+        summary.add("x.txt", None, Err(Error::MissingGame));
+
+        assert_eq!(
+            summary.write(),
+            "?: 1 puzzle(s), 0 solved\n\
+             anima: 3 puzzle(s), 2 solved, average length 6.5, longest 9 (a/2.txt)\n\
+             sausage: 1 puzzle(s), 0 solved\n\
+             Unsolved:\n  \
+             a/3.txt: unsolvable\n  \
+             s/1.txt: budget\n  \
+             x.txt: game\n"
+        );
+    }
+}
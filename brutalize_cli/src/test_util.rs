@@ -0,0 +1,163 @@
+use crate::{write_puzzle, DisplayState, State};
+pub use brutalize::Rng;
+use brutalize::Transition;
+use std::{
+    collections::hash_map::DefaultHasher,
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+/// Game-specific rules that every reachable state must obey.
+pub trait Invariants: State {
+    /// Returns a description of the first broken invariant, if any. This is
+    /// where games check things like entities never overlapping and entity
+    /// lists being kept in canonical order.
+    fn check_invariants(&self, data: &Self::Data) -> Result<(), String>;
+}
+
+pub struct Settings {
+    pub cases: usize,
+    pub seed: u64,
+    pub walk_length: usize,
+    pub solve: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            cases: 64,
+            seed: 0,
+            walk_length: 32,
+            solve: true,
+        }
+    }
+}
+
+fn hash_of<T: Hash>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Generates `settings.cases` puzzles with `generate` and panics with the
+/// offending puzzle if any of them breaks an invariant.
+///
+/// For each puzzle this checks that:
+/// - the initial state and every state along a random walk obey the game's
+///   invariants,
+/// - writing a state and parsing it back yields an equal state,
+/// - transitions are deterministic, producing equal states with equal hashes,
+/// - packing is lossless, so sibling states pack to equal keys exactly when
+///   they are equal,
+/// - display() succeeds on every visited state,
+/// - any solution returned by solve() replays to a success.
+pub fn check<S, G>(settings: &Settings, mut generate: G)
+where
+    S: Invariants + fmt::Debug,
+    S::Action: fmt::Debug + PartialEq,
+    G: FnMut(&mut Rng) -> String,
+{
+    let mut rng = Rng::new(settings.seed);
+
+    for case in 0..settings.cases {
+        let puzzle = generate(&mut rng);
+        let fail = |message: String| -> ! {
+            panic!("case {} failed: {}\npuzzle:\n{}", case, message, puzzle)
+        };
+
+        let (initial_state, data) =
+            S::parse(&puzzle).unwrap_or_else(|e| fail(format!("parse error {:?}", e)));
+        let check_state = |state: &S| {
+            state
+                .check_invariants(&data)
+                .unwrap_or_else(|e| fail(format!("{}\nstate: {:?}", e, state)));
+            DisplayState(state, &data).to_string();
+        };
+
+        check_state(&initial_state);
+
+        let written = write_puzzle(&initial_state, &data);
+        let (reparsed_state, reparsed_data) = S::parse(&written)
+            .unwrap_or_else(|e| fail(format!("written puzzle failed to parse {:?}", e)));
+        if reparsed_state != initial_state {
+            fail(format!(
+                "state changed after write round-trip\nwritten:\n{}",
+                written
+            ));
+        }
+        if write_puzzle(&reparsed_state, &reparsed_data) != written {
+            fail("written puzzle changed after write round-trip".to_string());
+        }
+
+        let mut state = initial_state.clone();
+        for _ in 0..settings.walk_length {
+            let mut next_states = Vec::new();
+            let transitions = state.transitions(&data).into_iter();
+            let repeated = state.transitions(&data).into_iter();
+            for ((action, transition), (repeated_action, repeated_transition)) in
+                transitions.zip(repeated)
+            {
+                if action != repeated_action {
+                    fail(format!(
+                        "transitions are not deterministic: {:?} vs {:?}",
+                        action, repeated_action
+                    ));
+                }
+                if let Transition::Indeterminate(next) = transition {
+                    match repeated_transition {
+                        Transition::Indeterminate(repeated_next)
+                            if repeated_next == next
+                                && hash_of(&repeated_next) == hash_of(&next) => {}
+                        _ => fail(format!("transition {:?} is not deterministic", action)),
+                    }
+                    check_state(&next);
+                    next_states.push(next);
+                }
+            }
+
+            for (i, a) in next_states.iter().enumerate() {
+                for b in next_states[i + 1..].iter() {
+                    if let (Some(pa), Some(pb)) = (a.pack(), b.pack()) {
+                        if (a == b) != (pa == pb) {
+                            fail(format!("packing is lossy\n{:?}\n{:?}", a, b));
+                        }
+                    }
+                }
+            }
+
+            if next_states.is_empty() {
+                break;
+            }
+            let index = rng.below(next_states.len());
+            state = next_states.swap_remove(index);
+        }
+
+        if settings.solve {
+            if let Some(solution) = brutalize::solve(initial_state.clone(), &data) {
+                let mut state = initial_state;
+                let mut succeeded = state.is_goal(&data).is_some();
+                for (i, action) in solution.iter().enumerate() {
+                    if succeeded {
+                        fail(format!("solution continues after success at step {}", i));
+                    }
+                    let transition = state
+                        .transitions(&data)
+                        .into_iter()
+                        .find(|(a, _)| a == action)
+                        .unwrap_or_else(|| fail(format!("solution action {:?} not found", action)))
+                        .1;
+                    match transition {
+                        Transition::Indeterminate(next) => state = next,
+                        Transition::Success(_) => succeeded = true,
+                    }
+                }
+                if !succeeded {
+                    fail(format!(
+                        "solution {:?} does not replay to success",
+                        solution
+                    ));
+                }
+            }
+        }
+    }
+}
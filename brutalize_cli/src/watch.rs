@@ -0,0 +1,62 @@
+use crate::Error;
+use std::{collections::HashMap, fs, thread, time::Duration, time::SystemTime};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+fn modified(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn describe(length: Option<usize>) -> String {
+    match length {
+        Some(length) => format!("{} moves", length),
+        None => "no solution".to_string(),
+    }
+}
+
+/// Solves each path in turn, printing any errors. With `watch` set, keeps
+/// polling the files afterwards and re-solves any that change, printing how
+/// the solution length changed. Otherwise returns whether every path was
+/// solved without an error.
+pub(crate) fn solve_paths(
+    paths: &[String],
+    watch: bool,
+    mut solve: impl FnMut(&str) -> Result<Option<usize>, Error>,
+) -> bool {
+    let mut solve = |path: &str| match solve(path) {
+        Ok(length) => Some(length),
+        Err(e) => {
+            eprintln!("Error while solving '{}': {}", path, e);
+            None
+        }
+    };
+
+    let mut succeeded = true;
+    let mut lengths = HashMap::new();
+    let mut times = HashMap::new();
+    for path in paths {
+        times.insert(path, modified(path));
+        let length = solve(path);
+        succeeded &= length.is_some();
+        lengths.insert(path, length.flatten());
+    }
+
+    if !watch {
+        return succeeded;
+    }
+
+    println!("Watching {} file(s) for changes", paths.len());
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        for path in paths {
+            let time = modified(path);
+            if times.insert(path, time) == Some(time) {
+                continue;
+            }
+
+            let length = solve(path).flatten();
+            let previous = lengths.insert(path, length).flatten();
+            println!("{}: {} -> {}", path, describe(previous), describe(length));
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Loads the puzzles bundled with the workspace in `puzzles/`, which has one
+//! directory of `.txt` puzzle files for each game.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The `puzzles/` directory at the root of the workspace.
+pub fn puzzles_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../puzzles")
+}
+
+/// The paths of every puzzle bundled for `game`, sorted by name. Empty if
+/// there are none.
+pub fn list_puzzles(game: &str) -> Vec<PathBuf> {
+    let mut paths = match fs::read_dir(puzzles_dir().join(game)) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|e| e == "txt"))
+            .collect::<Vec<_>>(),
+        Err(_) => Vec::new(),
+    };
+    paths.sort();
+    paths
+}
+
+/// Reads a bundled puzzle by its game and file name without the extension,
+/// like `"sausage/seafinger"`. Anima names have dots in them, so the extension
+/// is appended rather than swapped in.
+pub fn load(name: &str) -> io::Result<String> {
+    fs::read_to_string(puzzles_dir().join(format!("{}.txt", name)))
+}
+
+/// A small puzzle for `game` built into the binary, so `--demo` works without
+/// the workspace around.
+pub fn demo(game: &str) -> Option<&'static str> {
+    match game {
+        "anima" => Some(include_str!("../../puzzles/anima/1.3-spiral.txt")),
+        "sausage" => Some(include_str!("../../puzzles/sausage/seafinger.txt")),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn demos_are_bundled() {
+        for game in ["anima", "sausage"].iter() {
+            assert!(!list_puzzles(game).is_empty());
+            assert!(demo(game).is_some());
+        }
+        assert_eq!(load("sausage/seafinger").unwrap(), demo("sausage").unwrap());
+        assert!(list_puzzles("chess").is_empty());
+        assert!(demo("chess").is_none());
+    }
+}
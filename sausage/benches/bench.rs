@@ -0,0 +1,19 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use sausage::State;
+
+fn solve_bundled(c: &mut Criterion, name: &str) {
+    let source = brutalize_puzzles::load(&format!("sausage/{}", name)).unwrap();
+    let (initial_state, data) = <State as brutalize_cli::State>::parse(&source).unwrap();
+
+    c.bench_function(&format!("solve_{}", name), |b| {
+        b.iter(|| brutalize::solve(black_box(&initial_state).clone(), &data))
+    });
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    solve_bundled(c, "seafinger");
+    solve_bundled(c, "happy_pool");
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -1,4 +1,4 @@
-Get-ChildItem ".\puzzles" |
+Get-ChildItem "..\puzzles\sausage" |
 Foreach-Object {
     cargo run --release $_.FullName > ".\solutions\$($_.BaseName).txt"
 }
@@ -0,0 +1,102 @@
+use crate::{Data, Player, Sausage, SausageOrientation, State, Tile};
+use arrayvec::ArrayVec;
+use quickcheck::{Arbitrary, Gen};
+use solver_common::{Direction, Vec2};
+
+/// A randomly generated puzzle whose initial state is valid for its board: the
+/// player and fork start on ground, and the sausage starts on ground or grill
+/// without overlapping the player.
+#[derive(Clone, Debug)]
+pub struct Puzzle {
+    pub state: State,
+    pub data: Data,
+}
+
+fn range(g: &mut Gen, min: i32, max: i32) -> i32 {
+    min + (u32::arbitrary(g) % (max - min + 1) as u32) as i32
+}
+
+impl Arbitrary for Puzzle {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let size = Vec2::new(range(g, 3, 5), range(g, 3, 4));
+        let mut tiles = (0..size.x * size.y)
+            .map(|_| {
+                *g.choose(&[
+                    Tile::Ground,
+                    Tile::Ground,
+                    Tile::Ground,
+                    Tile::Ground,
+                    Tile::Grill,
+                    Tile::Grill,
+                    Tile::Wall,
+                    Tile::Mud,
+                    Tile::Empty,
+                ])
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let mut set_tile = |position: Vec2, tile: Tile| {
+            if position.x >= 0 && position.x < size.x && position.y >= 0 && position.y < size.y {
+                tiles[(position.x + position.y * size.x) as usize] = tile;
+            }
+        };
+
+        let player = Player {
+            position: Vec2::new(range(g, 0, size.x - 1), range(g, 0, size.y - 1)),
+            orientation: *g
+                .choose(&[
+                    Direction::Right,
+                    Direction::Up,
+                    Direction::Left,
+                    Direction::Down,
+                ])
+                .unwrap(),
+        };
+        let fork_length = range(g, 1, 2);
+        let forks = (1..=fork_length)
+            .map(|i| player.position + player.orientation.to_vec2() * i)
+            .collect::<Vec<_>>();
+        set_tile(player.position, Tile::Ground);
+        for &fork in forks.iter() {
+            set_tile(fork, Tile::Ground);
+        }
+
+        let sausage = loop {
+            let orientation = *g
+                .choose(&[SausageOrientation::Horizontal, SausageOrientation::Vertical])
+                .unwrap();
+            let max = size - Sausage::new(Vec2::zero(), orientation).end_offset();
+            let sausage = Sausage::new(
+                Vec2::new(range(g, 0, max.x - 1), range(g, 0, max.y - 1)),
+                orientation,
+            );
+            if !sausage.overlap(player.position) && !forks.iter().any(|&f| sausage.overlap(f)) {
+                break sausage;
+            }
+        };
+        for position in [sausage.position, sausage.end_position()].iter() {
+            set_tile(*position, *g.choose(&[Tile::Ground, Tile::Grill]).unwrap());
+        }
+
+        let data = Data {
+            size,
+            origin: Vec2::default(),
+            tiles,
+            goals: [(player.position, player.orientation)]
+                .iter()
+                .cloned()
+                .collect(),
+            alternate_goals: Vec::new(),
+            strict_start: bool::arbitrary(g),
+            strict_overhang: bool::arbitrary(g),
+            fork_length,
+        };
+        let mut sausages = ArrayVec::new();
+        sausages.push(sausage);
+
+        Puzzle {
+            state: State::initial(&data, sausages),
+            data,
+        }
+    }
+}
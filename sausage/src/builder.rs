@@ -0,0 +1,66 @@
+use crate::{Cooked, Player, Sausage, SausageOrientation, State};
+use arrayvec::ArrayVec;
+use solver_common::{Direction, Vec2};
+
+/// Puts together a state by hand, for tests which want to spell out the state
+/// a move should lead to without parsing a whole puzzle. Start with a player,
+/// add sausages and how cooked they are, then `build`.
+#[derive(Clone, Debug)]
+pub struct StateBuilder {
+    players: ArrayVec<Player, 2>,
+    sausages: ArrayVec<Sausage, 4>,
+}
+
+impl StateBuilder {
+    /// Starts a state with a player at `x y` facing `orientation`.
+    pub fn player(x: i32, y: i32, orientation: Direction) -> Self {
+        Self {
+            players: ArrayVec::new(),
+            sausages: ArrayVec::new(),
+        }
+        .and_player(x, y, orientation)
+    }
+
+    /// Adds another player, in the order the puzzle lists its starts.
+    ///
+    /// Panics if the state already has two players.
+    pub fn and_player(mut self, x: i32, y: i32, orientation: Direction) -> Self {
+        self.players.push(Player {
+            position: Vec2::new(x, y),
+            orientation,
+        });
+        self
+    }
+
+    /// Adds a raw sausage whose first half is at `x y`.
+    ///
+    /// Panics if the state already has four sausages.
+    pub fn sausage(mut self, x: i32, y: i32, orientation: SausageOrientation) -> Self {
+        self.sausages
+            .push(Sausage::new(Vec2::new(x, y), orientation));
+        self
+    }
+
+    /// Sets how cooked the last sausage added is, in the order the board
+    /// lists them: the top sides of its first and second halves, then the
+    /// bottom sides.
+    ///
+    /// Panics if no sausage has been added yet.
+    pub fn cooked(mut self, sides: [Cooked; 4]) -> Self {
+        self.sausages
+            .last_mut()
+            .expect("cooked() needs a sausage to apply to")
+            .cooked = sides;
+        self
+    }
+
+    /// Finishes the state, putting its sausages in canonical order.
+    pub fn build(self) -> State {
+        let mut state = State {
+            players: self.players,
+            sausages: self.sausages,
+        };
+        state.canonicalize();
+        state
+    }
+}
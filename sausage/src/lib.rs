@@ -1,90 +1,178 @@
 use arrayvec::ArrayVec;
-use solver_common::{Direction, ParseDirectionError, Vec2};
-use std::{fmt, num::ParseIntError, str::FromStr};
+use solver_common::{Direction, Notation, ParseDirectionError, Solution, Status, Vec2};
+use std::{convert::TryFrom, fmt, mem, num::ParseIntError, str::FromStr};
+
+#[cfg(any(test, feature = "arbitrary"))]
+mod arbitrary;
+#[cfg(any(test, feature = "arbitrary"))]
+pub use crate::arbitrary::Puzzle;
+#[cfg(any(test, feature = "test-util"))]
+mod builder;
+#[cfg(any(test, feature = "test-util"))]
+pub use crate::builder::StateBuilder;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
-enum Tile {
+pub enum Tile {
+    /// Water around the island, which anything not held up falls into.
     Empty,
     Ground,
     Grill,
     Wall,
+    /// Ground that sausages slide across without rolling.
+    Mud,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
-enum Status {
-    Solved,
-    Unsolved,
-    Failed,
-}
-
+#[derive(Clone, Debug)]
 pub struct Data {
     size: Vec2,
+    /// The position of the bottom left tile, which lets boards cut out of a
+    /// bigger level keep its coordinates.
+    origin: Vec2,
     tiles: Vec<Tile>,
-    goal_position: Vec2,
-    goal_orientation: Direction,
+    /// Where each player starts and has to return to.
+    goals: ArrayVec<(Vec2, Direction), 2>,
+    /// Other poses a lone player may finish in instead of its start.
+    alternate_goals: Vec<(Vec2, Direction)>,
+    /// Whether the player may only step back onto the start once every
+    /// sausage is cooked.
+    strict_start: bool,
+    /// Whether a sausage fails as soon as either end hangs over the water,
+    /// instead of only once neither end is held up.
+    strict_overhang: bool,
+    /// How many tiles the fork reaches out from each player.
+    fork_length: i32,
 }
 
 impl Data {
     #[inline]
-    fn size(&self) -> Vec2 {
+    pub fn size(&self) -> Vec2 {
         self.size
     }
 
     #[inline]
-    fn tile(&self, position: Vec2) -> Tile {
-        if position.x < 0
-            || position.x >= self.size.x
-            || position.y < 0
-            || position.y >= self.size.y
-        {
+    pub fn origin(&self) -> Vec2 {
+        self.origin
+    }
+
+    /// The tile at `position`, which is `Empty` anywhere off the puzzle.
+    #[inline]
+    pub fn tile(&self, position: Vec2) -> Tile {
+        let local = position - self.origin;
+        if local.x < 0 || local.x >= self.size.x || local.y < 0 || local.y >= self.size.y {
             Tile::Empty
         } else {
-            let index = position.x + position.y * self.size.x;
+            let index = local.x + local.y * self.size.x;
             self.tiles[index as usize]
         }
     }
 
+    /// Every tile in the puzzle along with its position, row by row from the
+    /// bottom.
+    pub fn tiles(&self) -> impl Iterator<Item = (Vec2, Tile)> + '_ {
+        let width = self.size.x;
+        let origin = self.origin;
+        self.tiles
+            .iter()
+            .enumerate()
+            .map(move |(i, &tile)| (origin + Vec2::new(i as i32 % width, i as i32 / width), tile))
+    }
+
+    /// Where each player starts and which way they face, which is also where
+    /// they have to finish.
     #[inline]
-    fn goal_position(&self) -> Vec2 {
-        self.goal_position
+    pub fn goals(&self) -> &[(Vec2, Direction)] {
+        &self.goals
     }
 
+    /// The poses besides its start that a lone player may finish in.
     #[inline]
-    fn goal_orientation(&self) -> Direction {
-        self.goal_orientation
+    pub fn alternate_goals(&self) -> &[(Vec2, Direction)] {
+        &self.alternate_goals
     }
 
+    /// Every pose player `i` may finish in, starting with its start.
     #[inline]
-    fn status_of(&self, state: &State) -> Status {
-        if self.tile(state.player.position) == Tile::Empty {
-            return Status::Failed;
-        }
+    fn goal_poses(&self, i: usize) -> impl Iterator<Item = (Vec2, Direction)> + '_ {
+        let alternates = if i == 0 {
+            &self.alternate_goals[..]
+        } else {
+            &[]
+        };
+        Some(self.goals[i])
+            .into_iter()
+            .chain(alternates.iter().copied())
+    }
 
-        let mut solved = true;
-        for sausage in state.sausages.iter() {
-            if !sausage.overlap(state.player.fork_position())
-                && self.tile(sausage.position) == Tile::Empty
-                && self.tile(sausage.end_position()) == Tile::Empty
-            {
-                return Status::Failed;
+    #[inline]
+    pub fn strict_start(&self) -> bool {
+        self.strict_start
+    }
+
+    #[inline]
+    pub fn strict_overhang(&self) -> bool {
+        self.strict_overhang
+    }
+
+    #[inline]
+    pub fn fork_length(&self) -> i32 {
+        self.fork_length
+    }
+
+    /// Finds a reason the state can no longer be solved, no matter where the
+    /// players are.
+    #[inline]
+    fn failure_of(&self, state: &State) -> Option<Failure> {
+        for (i, player) in state.players.iter().enumerate() {
+            if self.tile(player.position) == Tile::Empty {
+                return Some(Failure::PlayerFell(i));
             }
-            for cooked in &sausage.cooked {
-                match cooked {
-                    Cooked::Uncooked => solved = false,
-                    Cooked::Cooked => (),
-                    Cooked::Burned => return Status::Failed,
+        }
+
+        for (i, sausage) in state.sausages.iter().enumerate() {
+            if !state.players.iter().any(|p| p.forks(self, sausage)) {
+                let start_over_water = self.tile(sausage.position) == Tile::Empty;
+                let end_over_water = self.tile(sausage.end_position()) == Tile::Empty;
+                if start_over_water && end_over_water {
+                    return Some(Failure::SausageFell(i));
+                }
+                if self.strict_overhang && (start_over_water || end_over_water) {
+                    return Some(Failure::SausageOverhang(i));
                 }
             }
+            if sausage.cooked.contains(&Cooked::Burned) {
+                return Some(Failure::SausageBurned(i));
+            }
         }
 
-        if state.player.position != self.goal_position()
-            || state.player.orientation != self.goal_orientation()
-        {
-            solved = false
+        None
+    }
+
+    #[inline]
+    fn status_of(&self, state: &State) -> Status {
+        if self.failure_of(state).is_some() {
+            return Status::Failed;
+        }
+
+        let all_cooked = state
+            .sausages
+            .iter()
+            .all(|s| s.cooked.iter().all(|&c| c == Cooked::Cooked));
+
+        let mut any_on_goal = false;
+        let mut all_home = true;
+        for (i, (player, &(start, _))) in state.players.iter().zip(self.goals.iter()).enumerate() {
+            any_on_goal |= player.position == start;
+            all_home &= self.goal_poses(i).any(|(position, orientation)| {
+                player.position == position && player.orientation == orientation
+            });
+        }
+
+        if self.strict_start && any_on_goal && !all_cooked {
+            return Status::Failed;
         }
 
-        if solved {
-            Status::Solved
+        if all_cooked && all_home {
+            Status::Solved(())
         } else {
             Status::Unsolved
         }
@@ -92,20 +180,58 @@ impl Data {
 }
 
 #[derive(Debug, Clone, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 struct Player {
     position: Vec2,
     orientation: Direction,
 }
 
 impl Player {
+    /// The tiles covered by the fork, from the player outwards.
+    #[inline]
+    fn fork_positions(&self, data: &Data) -> impl Iterator<Item = Vec2> {
+        let position = self.position;
+        let step = self.orientation.to_vec2();
+        (1..=data.fork_length).map(move |i| position + step * i)
+    }
+
+    /// Whether the fork is stuck in `sausage`.
+    #[inline]
+    fn forks(&self, data: &Data, sausage: &Sausage) -> bool {
+        self.fork_positions(data).any(|f| sausage.overlap(f))
+    }
+
+    /// Whether the player or its fork covers `position`.
+    #[inline]
+    fn covers(&self, data: &Data, position: Vec2) -> bool {
+        self.position == position || self.fork_positions(data).any(|f| f == position)
+    }
+
+    /// The tiles the fork sweeps through when turning a quarter turn to face
+    /// `direction`: the square between the old and new fork positions, not
+    /// counting either of them. A fork one tile long only sweeps the tile
+    /// diagonally between them.
     #[inline]
-    fn fork_position(&self) -> Vec2 {
-        self.position + self.orientation.to_vec2()
+    fn sweep_positions(&self, data: &Data, direction: Direction) -> ArrayVec<Vec2, 16> {
+        debug_assert!(
+            direction.to_vec2() == self.orientation.to_vec2().rotate_cw()
+                || direction.to_vec2() == self.orientation.to_vec2().rotate_ccw()
+        );
+        let forward = self.orientation.to_vec2();
+        let side = direction.to_vec2();
+        let mut result = ArrayVec::new();
+        for i in 1..=data.fork_length {
+            for j in 1..=data.fork_length {
+                result.push(self.position + forward * i + side * j);
+            }
+        }
+        result
     }
 }
 
 #[derive(Debug, Copy, Clone, Eq, Hash, Ord, PartialEq, PartialOrd)]
-enum SausageOrientation {
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum SausageOrientation {
     Horizontal,
     Vertical,
 }
@@ -113,6 +239,12 @@ enum SausageOrientation {
 #[derive(Debug)]
 pub struct ParseSausageOrientationError(String);
 
+impl fmt::Display for ParseSausageOrientationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown orientation '{}'", self.0)
+    }
+}
+
 impl FromStr for SausageOrientation {
     type Err = ParseSausageOrientationError;
 
@@ -126,13 +258,108 @@ impl FromStr for SausageOrientation {
 }
 
 #[derive(Debug, Copy, Clone, Eq, Hash, Ord, PartialEq, PartialOrd)]
-enum Cooked {
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[allow(clippy::enum_variant_names)]
+pub enum Cooked {
     Uncooked,
     Cooked,
     Burned,
 }
 
+impl fmt::Display for Cooked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Cooked::Uncooked => write!(f, "raw"),
+            Cooked::Cooked => write!(f, "cooked"),
+            Cooked::Burned => write!(f, "burned"),
+        }
+    }
+}
+
+/// Why a state failed, reported for puzzles that start out failed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Failure {
+    PlayerFell(usize),
+    SausageFell(usize),
+    SausageOverhang(usize),
+    SausageBurned(usize),
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Failure::PlayerFell(i) => write!(f, "player {} off the island", i),
+            Failure::SausageFell(i) => write!(f, "sausage {} off the island", i),
+            Failure::SausageOverhang(i) => write!(f, "sausage {} hanging over the water", i),
+            Failure::SausageBurned(i) => write!(f, "sausage {} burned", i),
+        }
+    }
+}
+
+/// A move and what it does to the player making it, since a bare direction
+/// doesn't say whether the player stepped or turned. With several players,
+/// the kind is that of the first player to move.
+///
+/// Which kind of move a direction makes follows from the state it's made in,
+/// so actions compare by direction alone. That lets solutions written as
+/// plain directions be replayed.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum SausageAction {
+    /// Steps in the direction, keeping the fork pointed the same way.
+    Strafe(Direction),
+    /// Turns the fork to point in the direction.
+    Rotate(Direction),
+}
+
+impl SausageAction {
+    pub fn direction(self) -> Direction {
+        match self {
+            SausageAction::Strafe(direction) | SausageAction::Rotate(direction) => direction,
+        }
+    }
+}
+
+impl PartialEq for SausageAction {
+    fn eq(&self, other: &Self) -> bool {
+        self.direction() == other.direction()
+    }
+}
+
+impl Eq for SausageAction {}
+
+impl fmt::Display for SausageAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            SausageAction::Strafe(direction) => write!(f, "Strafe {}", direction),
+            SausageAction::Rotate(direction) => write!(f, "Rotate {}", direction),
+        }
+    }
+}
+
+/// Written as the direction alone. Actions read back are taken to be strafes
+/// until they're replayed.
+impl Notation for SausageAction {
+    fn to_char(&self) -> char {
+        self.direction().to_char()
+    }
+
+    fn from_char(c: char) -> Option<Self> {
+        Direction::from_char(c).map(SausageAction::Strafe)
+    }
+}
+
+/// The longest fork a puzzle may give its players.
+const MAX_FORK_LENGTH: i32 = 4;
+
+/// `Sausage::cooked` holds the top side followed by the bottom side, each as
+/// the half at `position` then the half at `end_position`. The bottom side is
+/// the one touching the ground.
+const TOP: usize = 0;
+const BOTTOM: usize = 2;
+
 #[derive(Debug, Clone, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 struct Sausage {
     position: Vec2,
     orientation: SausageOrientation,
@@ -149,10 +376,26 @@ impl Sausage {
         }
     }
 
+    /// Flips the sausage over. Rolling is always across its length, so each
+    /// half stays on its tile and only trades sides.
     #[inline]
     fn roll(&mut self) {
-        self.cooked.swap(0, 2);
-        self.cooked.swap(1, 3);
+        self.cooked.swap(TOP, BOTTOM);
+        self.cooked.swap(TOP + 1, BOTTOM + 1);
+    }
+
+    /// Whether pushing in `direction` rolls the sausage rather than sliding
+    /// it along its length.
+    #[inline]
+    fn rolls(&self, direction: Direction) -> bool {
+        match self.orientation {
+            SausageOrientation::Horizontal => {
+                direction == Direction::Up || direction == Direction::Down
+            }
+            SausageOrientation::Vertical => {
+                direction == Direction::Left || direction == Direction::Right
+            }
+        }
     }
 
     #[inline]
@@ -168,6 +411,12 @@ impl Sausage {
         self.position + self.end_offset()
     }
 
+    /// Whether either half is stuck in mud, which keeps it from rolling.
+    #[inline]
+    fn in_mud(&self, data: &Data) -> bool {
+        data.tile(self.position) == Tile::Mud || data.tile(self.end_position()) == Tile::Mud
+    }
+
     #[inline]
     fn overlap(&self, position: Vec2) -> bool {
         (position == self.position) || (position == self.end_position())
@@ -189,38 +438,29 @@ impl Sausage {
     #[inline]
     fn push(&mut self, direction: Direction, data: &Data, can_roll: bool) {
         self.position += direction.to_vec2();
-        if can_roll {
-            let rolled = match self.orientation {
-                SausageOrientation::Horizontal => {
-                    direction == Direction::Up || direction == Direction::Down
-                }
-                SausageOrientation::Vertical => {
-                    direction == Direction::Left || direction == Direction::Right
-                }
-            };
-
-            if rolled {
-                self.roll();
-            }
+        if can_roll && self.rolls(direction) {
+            self.roll();
         }
 
-        if data.tile(self.position) == Tile::Grill {
-            self.cook(2);
-        }
-        if data.tile(self.end_position()) == Tile::Grill {
-            self.cook(3);
+        // Only the bottom halves touch the tiles they land on, whichever way
+        // the sausage moved to get there
+        for (half, position) in [self.position, self.end_position()].iter().enumerate() {
+            if data.tile(*position) == Tile::Grill {
+                self.cook(BOTTOM + half);
+            }
         }
     }
 
-    #[inline]
+    #[cfg(test)]
     fn is_in_wall(&self, data: &Data) -> bool {
         data.tile(self.position) == Tile::Wall || data.tile(self.end_position()) == Tile::Wall
     }
 }
 
 #[derive(Debug, Clone, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct State {
-    player: Player,
+    players: ArrayVec<Player, 2>,
     sausages: ArrayVec<Sausage, 4>,
 }
 
@@ -228,58 +468,157 @@ impl State {
     #[inline]
     fn initial(data: &Data, sausages: ArrayVec<Sausage, 4>) -> State {
         let mut result = State {
-            player: Player {
-                position: data.goal_position(),
-                orientation: data.goal_orientation(),
-            },
+            players: data
+                .goals
+                .iter()
+                .map(|&(position, orientation)| Player {
+                    position,
+                    orientation,
+                })
+                .collect(),
             sausages,
         };
 
-        result.sausages.sort_unstable();
+        result.canonicalize();
         result
     }
 
+    /// Puts the sausages in a canonical order. Sausages have no identity, so
+    /// two states which differ only in which sausage is which must compare
+    /// and hash equal for the closed set to catch them. Sorting by the whole
+    /// sausage, cooked sides included, is enough since identical sausages
+    /// stay identical through any move.
     #[inline]
-    fn try_move_sausage(&mut self, sausage_index: usize, direction: Direction, data: &Data, can_roll: bool) -> bool {
-        self.sausages[sausage_index].push(direction, data, can_roll);
-        if self.sausages[sausage_index].is_in_wall(data) {
-            return false;
-        }
+    fn canonicalize(&mut self) {
+        self.sausages.sort_unstable();
+    }
 
-        for i in (0..self.sausages.len()).filter(|&i| i != sausage_index) {
-            if self.sausages[sausage_index].overlap_sausage(&self.sausages[i]) {
-                if !self.try_move_sausage(i, direction, data, true) {
-                    return false;
+    /// Whether player `p` is blocked by a wall or by another player's body or
+    /// fork at `position`.
+    #[inline]
+    fn is_wall(&self, data: &Data, p: usize, position: Vec2) -> bool {
+        data.tile(position) == Tile::Wall
+            || self
+                .players
+                .iter()
+                .enumerate()
+                .any(|(i, other)| i != p && other.covers(data, position))
+    }
+
+    /// Finds every sausage that moves when sausage `sausage_index` is pushed
+    /// in `direction`, as a bit mask. Returns `None` if any of them would be
+    /// pushed into a wall.
+    #[inline]
+    fn plan_push(
+        &self,
+        p: usize,
+        sausage_index: usize,
+        direction: Direction,
+        data: &Data,
+    ) -> Option<u8> {
+        let offset = direction.to_vec2();
+        let mut planned = 1 << sausage_index;
+        let mut stack = ArrayVec::<usize, 4>::new();
+        stack.push(sausage_index);
+
+        while let Some(i) = stack.pop() {
+            let moved = Sausage::new(
+                self.sausages[i].position + offset,
+                self.sausages[i].orientation,
+            );
+            if self.is_wall(data, p, moved.position) || self.is_wall(data, p, moved.end_position())
+            {
+                return None;
+            }
+
+            // Planned sausages all move together, so they can only run into
+            // ones which haven't been planned yet
+            for (j, sausage) in self.sausages.iter().enumerate() {
+                if planned & 1 << j == 0 && sausage.overlap_sausage(&moved) {
+                    planned |= 1 << j;
+                    stack.push(j);
                 }
             }
         }
 
+        Some(planned)
+    }
+
+    /// Pushes a sausage and every sausage in its way, returning whether it was
+    /// possible. Nothing moves unless the whole push succeeds. Only the first
+    /// sausage may be prevented from rolling, apart from sausages in mud which
+    /// never roll.
+    #[inline]
+    fn try_move_sausage(
+        &mut self,
+        p: usize,
+        sausage_index: usize,
+        direction: Direction,
+        data: &Data,
+        can_roll: bool,
+    ) -> bool {
+        let planned = match self.plan_push(p, sausage_index, direction, data) {
+            Some(planned) => planned,
+            None => return false,
+        };
+
+        for (i, sausage) in self.sausages.iter_mut().enumerate() {
+            if planned & 1 << i != 0 {
+                let can_roll = (can_roll || i != sausage_index) && !sausage.in_mud(data);
+                sausage.push(direction, data, can_roll);
+            }
+        }
+
+        true
+    }
+
+    /// Pushes each sausage overlapping `tiles` once in `direction`, returning
+    /// whether they could all move. Sausages already pushed out of the way by
+    /// an earlier push stay where they are.
+    #[inline]
+    fn try_clear_sausages(
+        &mut self,
+        p: usize,
+        tiles: &[Vec2],
+        direction: Direction,
+        data: &Data,
+    ) -> bool {
+        let overlaps = |sausage: &Sausage| tiles.iter().any(|&t| sausage.overlap(t));
+        let overlapping = (0..self.sausages.len())
+            .filter(|&i| overlaps(&self.sausages[i]))
+            .collect::<ArrayVec<usize, 4>>();
+        for i in overlapping {
+            if overlaps(&self.sausages[i]) && !self.try_move_sausage(p, i, direction, data, true) {
+                return false;
+            }
+        }
         true
     }
 
     #[inline]
-    fn try_strafe_player(&mut self, data: &Data, direction: Direction) -> bool {
-        let old_fork_position = self.player.fork_position();
+    fn try_strafe_player(&mut self, data: &Data, p: usize, direction: Direction) -> bool {
+        let old_player = self.players[p].clone();
 
         // Move player
         let forward = direction.to_vec2();
-        self.player.position += forward;
+        self.players[p].position += forward;
 
         // No invalid moves
-        let player_in_wall = data.tile(self.player.position) == Tile::Wall;
-        let fork_in_wall = data.tile(self.player.fork_position()) == Tile::Wall;
+        let player_in_wall = self.is_wall(data, p, self.players[p].position);
+        let fork_in_wall = self.players[p]
+            .fork_positions(data)
+            .any(|f| self.is_wall(data, p, f));
         if player_in_wall || fork_in_wall {
             return false;
         }
 
         // Push sausages
-        let mut impaled = None;
+        let mut impaled = ArrayVec::<usize, 4>::new();
         for i in 0..self.sausages.len() {
-            if self.sausages[i].overlap(old_fork_position) {
+            if old_player.forks(data, &self.sausages[i]) {
                 // Impaled sausages always move with the player
-                let original_sausages = self.sausages.clone();
-                if !self.try_move_sausage(i, direction, data, false) {
-                     if direction != self.player.orientation.reverse() {
+                if !self.try_move_sausage(p, i, direction, data, false) {
+                    if direction != self.players[p].orientation.reverse() {
                         // If the player isn't moving backwards and the impaled
                         // sausage cannot move, then the move cannot be done.
                         return false;
@@ -287,46 +626,39 @@ impl State {
                         // If the player is moving backwards and the impaled
                         // sausage cannot move, then the impaled sausage does
                         // not move.
-                        self.sausages = original_sausages;
-                        impaled = None;
                     }
                 } else {
-                    impaled = Some(i);
+                    impaled.push(i);
                 }
-            } else if self.sausages[i].overlap(self.player.position) {
-                if !self.try_move_sausage(i, direction, data, true) {
+            } else if self.sausages[i].overlap(self.players[p].position) {
+                if !self.try_move_sausage(p, i, direction, data, true) {
                     // If the player cannot push a sausage out of the way, then
                     // the move cannot be done.
                     return false;
                 }
-            } else if self.sausages[i].overlap(self.player.fork_position()) {
-                let original_sausages = self.sausages.clone();
-                if !self.try_move_sausage(i, direction, data, true) {
-                    if direction != self.player.orientation {
-                        // If the fork isn't moving forward and cannot push a
-                        // sausage out of the way, then the move cannot be done.
-                        return false;
-                    } else {
-                        // If the fork is moving forward and cannot push a
-                        // sausage out of the way, then the sausages don't move
-                        // and the fork impales a sausage.
-                        self.sausages = original_sausages;
-                        impaled = Some(i);
-                    }
+            } else if self.players[p].forks(data, &self.sausages[i])
+                && !self.try_move_sausage(p, i, direction, data, true)
+            {
+                if direction != self.players[p].orientation {
+                    // If the fork isn't moving forward and cannot push a
+                    // sausage out of the way, then the move cannot be done.
+                    return false;
+                } else {
+                    // If the fork is moving forward and cannot push a sausage
+                    // out of the way, then the sausages don't move and the
+                    // fork impales a sausage.
+                    impaled.push(i);
                 }
             }
         }
 
         // Get burned
-        if data.tile(self.player.position) == Tile::Grill {
-            self.player.position -= forward;
-            if let Some(impaled) = impaled {
-                let original_sausages = self.sausages.clone();
-                if !self.try_move_sausage(impaled, direction.reverse(), data, false) {
-                    // If the impaled sausage can't move back with us, then it
-                    // does not move.
-                    self.sausages = original_sausages;
-                }
+        if data.tile(self.players[p].position) == Tile::Grill {
+            self.players[p].position -= forward;
+            for &i in impaled.iter() {
+                // If an impaled sausage can't move back with us, then it
+                // does not move.
+                self.try_move_sausage(p, i, direction.reverse(), data, false);
             }
         }
 
@@ -334,81 +666,163 @@ impl State {
     }
 
     #[inline]
-    fn try_rotate_player(&mut self, data: &Data, direction: Direction) -> bool {
+    fn try_rotate_player(&mut self, data: &Data, p: usize, direction: Direction) -> bool {
         // Rotate player
-        let original_orientation = self.player.orientation;
-        self.player.orientation = direction;
+        let original_orientation = self.players[p].orientation;
+        let top = self.players[p].sweep_positions(data, direction);
+        self.players[p].orientation = direction;
 
-        let mid = self.player.fork_position();
-        let top = mid + original_orientation.to_vec2();
+        let mid = self.players[p]
+            .fork_positions(data)
+            .collect::<ArrayVec<Vec2, 4>>();
 
         // No invalid moves
-        if data.tile(top) == Tile::Wall {
+        if top.iter().any(|&t| self.is_wall(data, p, t)) {
             return false;
         }
 
         // Push top sausages
-        if let Some(i) = self.sausages.iter().position(|sausage| sausage.overlap(top)) {
-            let direction = self.player.orientation;
-            if !self.try_move_sausage(i, direction, data, true) {
-                // If the top sausage can't be moved then the move cannot be
-                // done.
-                return false;
-            }
+        if !self.try_clear_sausages(p, &top, direction, data) {
+            // If the top sausages can't be moved then the move cannot be
+            // done.
+            return false;
         }
 
-        // If the mid tile is a wall then we can't do a full turn but we can do
-        // a half turn.
-        if data.tile(mid) == Tile::Wall {
-            self.player.orientation = original_orientation;
+        // If the mid tiles are blocked then we can't do a full turn but we can
+        // do a half turn.
+        if mid.iter().any(|&m| self.is_wall(data, p, m)) {
+            self.players[p].orientation = original_orientation;
             return true;
         }
 
         // Push mid sausages
-        if let Some(i) = self.sausages.iter().position(|sausage| sausage.overlap(mid)) {
-            let original_sausages = self.sausages.clone();
-            let direction = original_orientation.reverse();
-            if !self.try_move_sausage(i, direction, data, true) {
-                // If the mid sausage can't be moved then the top sausage move
-                // still happens and the player unrotates.
-                self.player.orientation = original_orientation;
-                self.sausages = original_sausages;
-            }
+        if !self.try_clear_sausages(p, &mid, original_orientation.reverse(), data) {
+            // If the mid sausages can't be moved then the top sausage move
+            // still happens and the player unrotates.
+            self.players[p].orientation = original_orientation;
         }
 
         true
     }
 
     #[inline]
-    fn transition(&self, data: &Data, direction: Direction) -> Option<State> {
-        let mut result = self.clone();
+    fn is_strafe(&self, data: &Data, p: usize, direction: Direction) -> bool {
+        let is_impaled = self.sausages.iter().any(|s| self.players[p].forks(data, s));
+        let moving_forward = direction == self.players[p].orientation;
+        let moving_backward = direction == self.players[p].orientation.reverse();
+        is_impaled || moving_forward || moving_backward
+    }
 
-        let is_impaled = self.sausages.iter().any(|s| s.overlap(self.player.fork_position()));
-        let moving_forward = direction == self.player.orientation;
-        let moving_backward = direction == self.player.orientation.reverse();
-        if is_impaled || moving_forward || moving_backward {
-            if !result.try_strafe_player(data, direction) {
-                return None;
-            }
+    /// Checks the walls that would reject a move outright, so that the state
+    /// doesn't need to be cloned just to find out the move is impossible.
+    #[inline]
+    fn is_blocked(&self, data: &Data, p: usize, direction: Direction, strafe: bool) -> bool {
+        if strafe {
+            let moved = Player {
+                position: self.players[p].position + direction.to_vec2(),
+                orientation: self.players[p].orientation,
+            };
+            self.is_wall(data, p, moved.position)
+                || moved.fork_positions(data).any(|f| self.is_wall(data, p, f))
         } else {
-            if !result.try_rotate_player(data, direction) {
-                return None;
+            self.players[p]
+                .sweep_positions(data, direction)
+                .iter()
+                .any(|&t| self.is_wall(data, p, t))
+        }
+    }
+
+    /// Moves player `p`, returning the move it made if it was possible. If
+    /// it wasn't, the state is left partially modified and must be restored
+    /// before being used again.
+    #[inline]
+    fn apply_player(
+        &mut self,
+        data: &Data,
+        p: usize,
+        direction: Direction,
+    ) -> Option<SausageAction> {
+        let strafe = self.is_strafe(data, p, direction);
+        if self.is_blocked(data, p, direction, strafe) {
+            return None;
+        }
+
+        if strafe {
+            self.try_strafe_player(data, p, direction)
+                .then_some(SausageAction::Strafe(direction))
+        } else {
+            self.try_rotate_player(data, p, direction)
+                .then_some(SausageAction::Rotate(direction))
+        }
+    }
+
+    /// Applies a move in place, returning the move made if it was possible.
+    /// If it wasn't, the state is left partially modified and must be
+    /// restored before being used again.
+    ///
+    /// With several players, each one that can make the move does so and the
+    /// others stay put. Players are retried until none of them can move, so
+    /// a player can follow another one out of its way. The move counts as a
+    /// strafe if any player strafed, and as a rotation only if every player
+    /// that moved rotated.
+    #[inline]
+    fn apply(&mut self, data: &Data, direction: Direction) -> Option<SausageAction> {
+        let action = if self.players.len() == 1 {
+            self.apply_player(data, 0, direction)
+        } else {
+            let mut action = None;
+            let mut pending = (0..self.players.len()).collect::<ArrayVec<usize, 2>>();
+            // The state after the moves made so far, to go back to whenever a
+            // player can't move
+            let mut moved = self.clone();
+            let mut progress = true;
+            while progress && !pending.is_empty() {
+                progress = false;
+                let mut i = 0;
+                while i < pending.len() {
+                    match self.apply_player(data, pending[i], direction) {
+                        Some(player_action) => {
+                            if !matches!(action, Some(SausageAction::Strafe(_))) {
+                                action = Some(player_action);
+                            }
+                            moved.clone_from(self);
+                            pending.remove(i);
+                            progress = true;
+                        }
+                        None => {
+                            self.clone_from(&moved);
+                            i += 1;
+                        }
+                    }
+                }
             }
+            action
+        };
+        if action.is_some() {
+            self.canonicalize();
         }
+        action
+    }
 
-        result.sausages.sort_unstable();
-        Some(result)
+    #[cfg(test)]
+    fn transition(&self, data: &Data, direction: Direction) -> Option<State> {
+        let mut result = self.clone();
+        result.apply(data, direction).map(|_| result)
     }
 }
 
 impl brutalize::State for State {
     type Data = Data;
-    type Action = Direction;
+    type Action = SausageAction;
+    type Outcome = ();
     type Transitions = ArrayVec<(Self::Action, brutalize::Transition<Self>), 4>;
     type Heuristic = usize;
 
     fn transitions(&self, data: &Self::Data) -> Self::Transitions {
         let mut result = ArrayVec::new();
+        // Moves are applied to a scratch state which is only handed off when
+        // it becomes a new search state, and is otherwise restored in place.
+        let mut scratch = self.clone();
         for direction in [
             Direction::Right,
             Direction::Up,
@@ -418,25 +832,109 @@ impl brutalize::State for State {
         .iter()
         .cloned()
         {
-            if let Some(state) = self.transition(data, direction) {
-                match data.status_of(&state) {
-                    Status::Solved => result.push((direction, brutalize::Transition::Success)),
-                    Status::Unsolved => {
-                        result.push((direction, brutalize::Transition::Indeterminate(state)))
-                    }
-                    Status::Failed => (),
+            if let Some(action) = scratch.apply(data, direction) {
+                let status = data.status_of(&scratch);
+                let handed_off = status == Status::Unsolved;
+                let transition =
+                    status.into_transition(|| mem::replace(&mut scratch, self.clone()));
+                if let Some(transition) = transition {
+                    result.push((action, transition));
+                }
+                if handed_off {
+                    continue;
                 }
             }
+            scratch.clone_from(self);
         }
         result
     }
 
+    fn is_goal(&self, data: &Self::Data) -> Option<Self::Outcome> {
+        data.status_of(self).outcome()
+    }
+
     fn heuristic(&self, data: &Self::Data) -> Self::Heuristic {
-        let distance = (self.player.position - data.goal_position).abs();
-        distance.x as usize + distance.y as usize
+        // Players move at most one tile per move, so the furthest one from
+        // its nearest goal bounds the rest of the solution.
+        self.players
+            .iter()
+            .zip(0..data.goals.len())
+            .map(|(player, i)| {
+                data.goal_poses(i)
+                    .map(|(position, _)| {
+                        let distance = (player.position - position).abs();
+                        distance.x as usize + distance.y as usize
+                    })
+                    .min()
+                    .unwrap_or(0)
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn pack(&self) -> Option<u128> {
+        // 3 bits of sausage count, 18 bits per player and 25 bits per sausage
+        // fit in 121 bits for one player and four sausages as long as every
+        // coordinate fits in an i8. The player count never changes during a
+        // search, so it doesn't need to be packed.
+        fn coordinate(value: i32) -> Option<u128> {
+            i8::try_from(value).ok().map(|v| v as u8 as u128)
+        }
+
+        if 3 + 18 * self.players.len() + 25 * self.sausages.len() > 128 {
+            return None;
+        }
+
+        let mut packed = self.sausages.len() as u128;
+        for player in self.players.iter() {
+            packed = packed << 8 | coordinate(player.position.x)?;
+            packed = packed << 8 | coordinate(player.position.y)?;
+            packed = packed << 2 | player.orientation as u128;
+        }
+        for sausage in self.sausages.iter() {
+            packed = packed << 8 | coordinate(sausage.position.x)?;
+            packed = packed << 8 | coordinate(sausage.position.y)?;
+            packed = packed << 1 | sausage.orientation as u128;
+            for cooked in sausage.cooked.iter() {
+                packed = packed << 2 | *cooked as u128;
+            }
+        }
+        Some(packed)
     }
 }
 
+/// Reads a pose written as `x y direction`, as used by the start command.
+fn parse_start_pose<'a>(
+    line_number: usize,
+    pieces: &mut impl Iterator<Item = &'a str>,
+) -> Result<(Vec2, Direction), ParseError> {
+    let x = pieces
+        .next()
+        .ok_or(ParseError::MissingStartX { line_number })?
+        .parse()
+        .map_err(|parse_error| ParseError::InvalidStartX {
+            line_number,
+            parse_error,
+        })?;
+    let y = pieces
+        .next()
+        .ok_or(ParseError::MissingStartY { line_number })?
+        .parse()
+        .map_err(|parse_error| ParseError::InvalidStartY {
+            line_number,
+            parse_error,
+        })?;
+    let orientation = pieces
+        .next()
+        .ok_or(ParseError::MissingStartOrientation { line_number })?
+        .parse()
+        .map_err(|parse_error| ParseError::InvalidStartOrientation {
+            line_number,
+            parse_error,
+        })?;
+    Ok((Vec2::new(x, y), orientation))
+}
+
 #[derive(Debug)]
 pub enum ParseError {
     MissingCommand {
@@ -449,6 +947,23 @@ pub enum ParseError {
     PuzzleAlreadyDefined {
         line_number: usize,
     },
+    OriginAlreadyDefined {
+        line_number: usize,
+    },
+    MissingOriginX {
+        line_number: usize,
+    },
+    InvalidOriginX {
+        line_number: usize,
+        parse_error: ParseIntError,
+    },
+    MissingOriginY {
+        line_number: usize,
+    },
+    InvalidOriginY {
+        line_number: usize,
+        parse_error: ParseIntError,
+    },
     MissingPuzzleSizeX {
         line_number: usize,
     },
@@ -501,6 +1016,18 @@ pub enum ParseError {
         line_number: usize,
         parse_error: ParseDirectionError,
     },
+    InvalidForkLength {
+        line_number: usize,
+        parse_error: ParseIntError,
+    },
+    UnexpectedStartPiece {
+        line_number: usize,
+        piece: String,
+    },
+    ForkLengthOutOfRange {
+        line_number: usize,
+        length: i32,
+    },
     SausagesAlreadyDefined {
         line_number: usize,
     },
@@ -536,6 +1063,45 @@ pub enum ParseError {
         expected_lines: usize,
         found_lines: usize,
     },
+    PlayersAlreadyDefined {
+        line_number: usize,
+    },
+    MissingPlayersCount {
+        line_number: usize,
+    },
+    InvalidPlayersCount {
+        line_number: usize,
+        parse_error: ParseIntError,
+    },
+    TooManyPlayers {
+        line_number: usize,
+        count: usize,
+    },
+    UnexpectedEndOfPlayers {
+        expected_lines: usize,
+        found_lines: usize,
+    },
+    MissingPlayerX {
+        line_number: usize,
+    },
+    InvalidPlayerX {
+        line_number: usize,
+        parse_error: ParseIntError,
+    },
+    MissingPlayerY {
+        line_number: usize,
+    },
+    InvalidPlayerY {
+        line_number: usize,
+        parse_error: ParseIntError,
+    },
+    MissingPlayerOrientation {
+        line_number: usize,
+    },
+    InvalidPlayerOrientation {
+        line_number: usize,
+        parse_error: ParseDirectionError,
+    },
     MissingPuzzle,
     MissingStart,
     MissingSausages,
@@ -544,10 +1110,44 @@ pub enum ParseError {
 impl brutalize_cli::State for State {
     type ParseError = ParseError;
 
+    const LEGEND: &'static str = concat!(
+        ". ground    # grill    X wall    ~ mud\n",
+        "P player    F fork     S/s sausage start/end\n",
+    );
+
+    const GLYPHS: &'static [(char, &'static str)] = &[
+        (' ', "🌊"),
+        ('.', "░░"),
+        ('#', "🔥"),
+        ('X', "🧱"),
+        ('~', "▒▒"),
+        ('P', "🧑"),
+        ('F', "🍴"),
+        ('S', "🌭"),
+        ('s', "🌭"),
+    ];
+
+    const COLORS: &'static [(char, &'static str)] = &[
+        (' ', "#48c"),
+        ('.', "#9c6"),
+        ('#', "#e60"),
+        ('X', "#777"),
+        ('~', "#864"),
+        ('P', "#fc9"),
+        ('F', "#ccc"),
+        ('S', "#c64"),
+        ('s', "#c64"),
+    ];
+
     fn parse(s: &str) -> Result<(State, Data), ParseError> {
         let mut puzzle = None;
         let mut start = None;
         let mut sausages = None;
+        let mut strict_start = false;
+        let mut strict_overhang = false;
+        let mut origin = None;
+        let mut fork_length = 1;
+        let mut alternate_goals = Vec::new();
 
         let mut lines = s.lines().enumerate();
         while let Some((line_number, line)) = lines.next() {
@@ -600,6 +1200,7 @@ impl brutalize_cli::State for State {
                                 '.' => Ok(Tile::Ground),
                                 '#' => Ok(Tile::Grill),
                                 'X' => Ok(Tile::Wall),
+                                '~' => Ok(Tile::Mud),
                                 _ => Err(ParseError::UnexpectedCharacter {
                                     line_number,
                                     column_number: x,
@@ -612,48 +1213,135 @@ impl brutalize_cli::State for State {
 
                     puzzle = Some((Vec2::new(size_x as i32, size_y as i32), tiles));
                 }
-                "start" => {
-                    if start.is_some() {
-                        return Err(ParseError::StartAlreadyDefined { line_number });
+                "origin" => {
+                    if origin.is_some() {
+                        return Err(ParseError::OriginAlreadyDefined { line_number });
                     }
 
-                    let start_x = pieces
-                        .next()
-                        .ok_or(ParseError::MissingStartX { line_number })?
-                        .parse()
-                        .map_err(|parse_error| ParseError::InvalidStartX {
-                            line_number,
-                            parse_error,
-                        })?;
-                    let start_y = pieces
+                    let x = pieces
                         .next()
-                        .ok_or(ParseError::MissingStartY { line_number })?
+                        .ok_or(ParseError::MissingOriginX { line_number })?
                         .parse()
-                        .map_err(|parse_error| ParseError::InvalidStartY {
+                        .map_err(|parse_error| ParseError::InvalidOriginX {
                             line_number,
                             parse_error,
                         })?;
-                    let orientation = pieces
+                    let y = pieces
                         .next()
-                        .ok_or(ParseError::MissingStartOrientation { line_number })?
+                        .ok_or(ParseError::MissingOriginY { line_number })?
                         .parse()
-                        .map_err(|parse_error| ParseError::InvalidStartOrientation {
+                        .map_err(|parse_error| ParseError::InvalidOriginY {
                             line_number,
                             parse_error,
                         })?;
-
-                    start = Some((Vec2::new(start_x, start_y), orientation));
+                    origin = Some(Vec2::new(x, y));
                 }
-                "sausages" => {
-                    if sausages.is_some() {
-                        return Err(ParseError::SausagesAlreadyDefined { line_number });
+                "start" => {
+                    if start.is_some() {
+                        return Err(ParseError::StartAlreadyDefined { line_number });
                     }
 
-                    let size = pieces
-                        .next()
-                        .ok_or(ParseError::MissingSausagesCount { line_number })?
-                        .parse()
-                        .map_err(|parse_error| ParseError::InvalidSausagesCount {
+                    let pose = parse_start_pose(line_number, &mut pieces)?;
+                    let mut pieces = pieces.by_ref().peekable();
+                    if let Some(length) = pieces.next_if(|&piece| piece != "or") {
+                        fork_length = length.parse().map_err(|parse_error| {
+                            ParseError::InvalidForkLength {
+                                line_number,
+                                parse_error,
+                            }
+                        })?;
+                        if !(1..=MAX_FORK_LENGTH).contains(&fork_length) {
+                            return Err(ParseError::ForkLengthOutOfRange {
+                                line_number,
+                                length: fork_length,
+                            });
+                        }
+                    }
+
+                    // Any other poses the player may finish in follow, each
+                    // after an "or"
+                    while let Some(piece) = pieces.next() {
+                        if piece != "or" {
+                            return Err(ParseError::UnexpectedStartPiece {
+                                line_number,
+                                piece: piece.to_string(),
+                            });
+                        }
+                        alternate_goals.push(parse_start_pose(line_number, &mut pieces)?);
+                    }
+
+                    let mut goals = ArrayVec::new();
+                    goals.push(pose);
+                    start = Some(goals);
+                }
+                "players" => {
+                    if start.is_some() {
+                        return Err(ParseError::PlayersAlreadyDefined { line_number });
+                    }
+
+                    let count = pieces
+                        .next()
+                        .ok_or(ParseError::MissingPlayersCount { line_number })?
+                        .parse()
+                        .map_err(|parse_error| ParseError::InvalidPlayersCount {
+                            line_number,
+                            parse_error,
+                        })?;
+                    if count == 0 || count > 2 {
+                        return Err(ParseError::TooManyPlayers { line_number, count });
+                    }
+
+                    let mut goals = ArrayVec::new();
+                    for i in 0..count {
+                        let (line_number, line) =
+                            lines.next().ok_or(ParseError::UnexpectedEndOfPlayers {
+                                expected_lines: count,
+                                found_lines: i,
+                            })?;
+
+                        let mut pieces = line.split(' ');
+                        let x = pieces
+                            .next()
+                            .ok_or(ParseError::MissingPlayerX { line_number })?
+                            .parse()
+                            .map_err(|parse_error| ParseError::InvalidPlayerX {
+                                line_number,
+                                parse_error,
+                            })?;
+                        let y = pieces
+                            .next()
+                            .ok_or(ParseError::MissingPlayerY { line_number })?
+                            .parse()
+                            .map_err(|parse_error| ParseError::InvalidPlayerY {
+                                line_number,
+                                parse_error,
+                            })?;
+                        let orientation = pieces
+                            .next()
+                            .ok_or(ParseError::MissingPlayerOrientation { line_number })?
+                            .parse()
+                            .map_err(|parse_error| ParseError::InvalidPlayerOrientation {
+                                line_number,
+                                parse_error,
+                            })?;
+
+                        goals.push((Vec2::new(x, y), orientation));
+                    }
+
+                    start = Some(goals);
+                }
+                "strict_start" => strict_start = true,
+                "strict_overhang" => strict_overhang = true,
+                "sausages" => {
+                    if sausages.is_some() {
+                        return Err(ParseError::SausagesAlreadyDefined { line_number });
+                    }
+
+                    let size = pieces
+                        .next()
+                        .ok_or(ParseError::MissingSausagesCount { line_number })?
+                        .parse()
+                        .map_err(|parse_error| ParseError::InvalidSausagesCount {
                             line_number,
                             parse_error,
                         })?;
@@ -707,69 +1395,291 @@ impl brutalize_cli::State for State {
         }
 
         let (size, tiles) = puzzle.ok_or(ParseError::MissingPuzzle)?;
-        let (goal_position, goal_orientation) = start.ok_or(ParseError::MissingStart)?;
+        let goals = start.ok_or(ParseError::MissingStart)?;
         let sausages = sausages.ok_or(ParseError::MissingSausages)?;
 
         let data = Data {
             size,
+            origin: origin.unwrap_or_default(),
             tiles,
-            goal_position,
-            goal_orientation,
+            goals,
+            alternate_goals,
+            strict_start,
+            strict_overhang,
+            fork_length,
         };
 
         Ok((State::initial(&data, sausages), data))
     }
 
+    fn validate(&self, data: &Self::Data) -> Result<(), String> {
+        // Players start on their goals, so strict_start is only checked once
+        // they move and isn't a failure here
+        if let Some(failure) = data.failure_of(self) {
+            return Err(failure.to_string());
+        }
+        // Players can't stay on a grill, in a wall or in the water
+        for &(position, _) in data.alternate_goals.iter() {
+            if !matches!(data.tile(position), Tile::Ground | Tile::Mud) {
+                return Err(format!(
+                    "the player can't finish at {} {}, which isn't ground",
+                    position.x, position.y
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn demo() -> Option<&'static str> {
+        brutalize_puzzles::demo("sausage")
+    }
+
+    fn write_solution(solution: &[Self::Action]) -> Option<String> {
+        Some(Solution(solution.to_vec()).to_string())
+    }
+
+    fn parse_solution(s: &str) -> Result<Vec<Self::Action>, String> {
+        s.parse::<Solution<_>>()
+            .map(|s| s.0)
+            .map_err(|e| e.to_string())
+    }
+
+    fn write(&self, data: &Self::Data, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The puzzle format has no way to express cooked sides or a player
+        // that isn't standing on the start, so only those parts of the state
+        // that can be parsed back are written.
+        writeln!(f, "puzzle {} {}", data.size().x, data.size().y)?;
+        for y in (0..data.size().y).rev() {
+            for x in 0..data.size().x {
+                let c = match data.tile(data.origin() + Vec2::new(x, y)) {
+                    Tile::Empty => ' ',
+                    Tile::Ground => '.',
+                    Tile::Grill => '#',
+                    Tile::Wall => 'X',
+                    Tile::Mud => '~',
+                };
+                write!(f, "{}", c)?;
+            }
+            writeln!(f)?;
+        }
+        if data.origin() != Vec2::default() {
+            writeln!(f, "origin {} {}", data.origin().x, data.origin().y)?;
+        }
+
+        fn orientation(direction: Direction) -> &'static str {
+            match direction {
+                Direction::Right => "right",
+                Direction::Up => "up",
+                Direction::Left => "left",
+                Direction::Down => "down",
+            }
+        }
+
+        if let [(position, direction)] = data.goals.as_slice() {
+            write!(
+                f,
+                "start {} {} {}",
+                position.x,
+                position.y,
+                orientation(*direction)
+            )?;
+            if data.fork_length != 1 {
+                write!(f, " {}", data.fork_length)?;
+            }
+            for (position, direction) in data.alternate_goals.iter() {
+                write!(
+                    f,
+                    " or {} {} {}",
+                    position.x,
+                    position.y,
+                    orientation(*direction)
+                )?;
+            }
+            writeln!(f)?;
+        } else {
+            writeln!(f, "players {}", data.goals.len())?;
+            for (position, direction) in data.goals.iter() {
+                writeln!(
+                    f,
+                    "{} {} {}",
+                    position.x,
+                    position.y,
+                    orientation(*direction)
+                )?;
+            }
+        }
+        if data.strict_start {
+            writeln!(f, "strict_start")?;
+        }
+        if data.strict_overhang {
+            writeln!(f, "strict_overhang")?;
+        }
+
+        writeln!(f, "sausages {}", self.sausages.len())?;
+        for sausage in self.sausages.iter() {
+            writeln!(
+                f,
+                "{} {} {}",
+                sausage.position.x,
+                sausage.position.y,
+                match sausage.orientation {
+                    SausageOrientation::Horizontal => "horizontal",
+                    SausageOrientation::Vertical => "vertical",
+                }
+            )?;
+        }
+
+        Ok(())
+    }
+
     fn display(&self, data: &Self::Data, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let board_width = data.size().x + 2;
-        let board_height = data.size().y + 2;
+        for row in self.board(data) {
+            writeln!(f, "{}", row.into_iter().collect::<String>())?;
+        }
+
+        // Sides are listed for the 'S' half then the 's' half. The bottom
+        // sides are the ones facing the grill.
+        for (i, sausage) in self.sausages.iter().enumerate() {
+            writeln!(
+                f,
+                "sausage {} at {} {}: top {}/{}, bottom {}/{}",
+                i,
+                sausage.position.x,
+                sausage.position.y,
+                sausage.cooked[TOP],
+                sausage.cooked[TOP + 1],
+                sausage.cooked[BOTTOM],
+                sausage.cooked[BOTTOM + 1],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn cells(&self, data: &Self::Data) -> Option<Vec<Vec<char>>> {
+        Some(self.board(data))
+    }
+
+    #[cfg(feature = "json")]
+    fn to_json(&self) -> Option<String> {
+        serde_json::to_string(self).ok()
+    }
+}
+
+impl State {
+    /// The characters `display` draws for each tile, as rows from the top.
+    fn board(&self, data: &Data) -> Vec<Vec<char>> {
+        // The board has a border around the puzzle as wide as the fork can
+        // reach past its edge
+        let border = data.fork_length();
+        let board_width = data.size().x + 2 * border;
+        let board_height = data.size().y + 2 * border;
         let size = board_width * board_height;
         let mut board = vec![' '; size as usize];
+        let corner = data.origin() - Vec2::new(border, border);
+        let index_of = |position: Vec2| {
+            let position = position - corner;
+            (position.x + position.y * board_width) as usize
+        };
 
         for y in 0..board_height {
             for x in 0..board_width {
                 let index = x + y * board_width;
-                board[index as usize] = match data.tile(Vec2::new(x - 1, y - 1)) {
+                board[index as usize] = match data.tile(corner + Vec2::new(x, y)) {
                     Tile::Empty => ' ',
                     Tile::Ground => '.',
                     Tile::Grill => '#',
                     Tile::Wall => 'X',
+                    Tile::Mud => '~',
                 }
             }
         }
 
         for sausage in self.sausages.iter() {
-            let index = (sausage.position.x + 1) + (sausage.position.y + 1) * board_width;
-            board[index as usize] = 'S';
-            let end_position = sausage.end_position();
-            let index = (end_position.x + 1) + (end_position.y + 1) * board_width;
-            board[index as usize] = 's';
-        }
-
-        let index = (self.player.position.x + 1) + (self.player.position.y + 1) * board_width;
-        board[index as usize] = 'P';
-        let fork_position = self.player.fork_position();
-        let index = (fork_position.x + 1) + (fork_position.y + 1) * board_width;
-        board[index as usize] = 'F';
-
-        for y in (0..board_height).rev() {
-            let begin = y * board_width;
-            let end = (y + 1) * board_width;
-            for c in &board[begin as usize..end as usize] {
-                write!(f, "{}", c)?;
+            board[index_of(sausage.position)] = 'S';
+            board[index_of(sausage.end_position())] = 's';
+        }
+
+        for player in self.players.iter() {
+            board[index_of(player.position)] = 'P';
+            for fork_position in player.fork_positions(data) {
+                board[index_of(fork_position)] = 'F';
             }
-            writeln!(f)?;
         }
 
-        Ok(())
+        board
+            .chunks(board_width as usize)
+            .rev()
+            .map(|row| row.to_vec())
+            .collect()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use brutalize_cli::State as _;
+    use crate::{
+        Cooked, Data, ParseError, Player, Puzzle, Sausage, SausageAction, SausageOrientation,
+        State, StateBuilder, Status, Tile, TOP,
+    };
+    use brutalize_cli::{
+        test_util::{self, Invariants, Rng},
+        State as _,
+    };
+    use quickcheck::{Arbitrary, Gen};
     use solver_common::{Direction, Vec2};
-    use crate::{State, Sausage, SausageOrientation, Cooked, Player};
+
+    impl Invariants for State {
+        fn check_invariants(&self, data: &Data) -> Result<(), String> {
+            for (i, player) in self.players.iter().enumerate() {
+                if data.tile(player.position) == Tile::Wall {
+                    return Err(format!("player {} is in a wall", i));
+                }
+                if player
+                    .fork_positions(data)
+                    .any(|f| data.tile(f) == Tile::Wall)
+                {
+                    return Err(format!("fork {} is in a wall", i));
+                }
+                for (j, other) in self.players.iter().enumerate().skip(i + 1) {
+                    let overlap = other.covers(data, player.position)
+                        || player.fork_positions(data).any(|f| other.covers(data, f));
+                    if overlap {
+                        return Err(format!("players {} and {} overlap", i, j));
+                    }
+                }
+            }
+            for (i, sausage) in self.sausages.iter().enumerate() {
+                if sausage.is_in_wall(data) {
+                    return Err(format!("sausage {} is in a wall", i));
+                }
+                for (j, player) in self.players.iter().enumerate() {
+                    if sausage.overlap(player.position) {
+                        return Err(format!("sausage {} overlaps player {}", i, j));
+                    }
+                }
+                for (j, other) in self.sausages.iter().enumerate().skip(i + 1) {
+                    if sausage.overlap_sausage(other) {
+                        return Err(format!("sausages {} and {} overlap", i, j));
+                    }
+                }
+            }
+            if self.sausages.windows(2).any(|w| w[0] > w[1]) {
+                return Err("sausages are not sorted".to_string());
+            }
+            Ok(())
+        }
+    }
+
+    fn generate_puzzle(rng: &mut Rng) -> String {
+        let mut g = Gen::from_size_and_seed(8, rng.next_u64());
+        let puzzle = Puzzle::arbitrary(&mut g);
+        brutalize_cli::write_puzzle(&puzzle.state, &puzzle.data)
+    }
+
+    #[test]
+    fn random_puzzles_keep_invariants() {
+        test_util::check::<State, _>(&test_util::Settings::default(), generate_puzzle);
+    }
 
     macro_rules! lines {
         ($($line:expr)*) => {
@@ -787,9 +1697,490 @@ mod tests {
         }}
     }
 
+    #[test]
+    fn write_round_trip() {
+        const PUZZLE: &str = lines![
+            "puzzle 5 3"
+            ".X#. "
+            "..#.."
+            " ...."
+            "start 0 1 right"
+            "sausages 2"
+            "1 0 horizontal"
+            "3 1 vertical"
+        ];
+
+        let (state, data) = State::parse(PUZZLE).unwrap();
+        let written = brutalize_cli::write_puzzle(&state, &data);
+        assert_eq!(written, PUZZLE);
+
+        let (reparsed_state, _) = State::parse(&written).unwrap();
+        assert_eq!(reparsed_state, state);
+    }
+
+    #[test]
+    fn name_bad_orientations() {
+        let puzzle = lines![
+            "puzzle 2 1"
+            ".."
+            "start 0 0 sideways"
+            "sausages 1"
+            "0 0 diagonal"
+        ];
+        match State::parse(puzzle) {
+            Err(ParseError::InvalidStartOrientation { parse_error, .. }) => {
+                assert_eq!(parse_error.to_string(), "unknown direction 'sideways'")
+            }
+            _ => panic!("the start orientation should be invalid"),
+        }
+
+        let puzzle = puzzle.replace("sideways", "right");
+        match State::parse(&puzzle) {
+            Err(ParseError::InvalidSausageOrientation { parse_error, .. }) => {
+                assert_eq!(parse_error.to_string(), "unknown orientation 'diagonal'")
+            }
+            _ => panic!("the sausage orientation should be invalid"),
+        }
+    }
+
+    #[test]
+    fn bundled_puzzles_are_valid() {
+        for path in brutalize_puzzles::list_puzzles("sausage") {
+            let source = std::fs::read_to_string(&path).unwrap();
+            let (state, data) = State::parse(&source).unwrap();
+            assert_eq!(state.validate(&data), Ok(()), "{}", path.display());
+        }
+    }
+
+    #[test]
+    fn actions_name_their_kind() {
+        const PUZZLE: &str = lines![
+            "puzzle 3 3"
+            "..."
+            "..."
+            "..."
+            "start 0 0 right"
+            "sausages 1"
+            "1 2 horizontal"
+        ];
+
+        let (state, data) = State::parse(PUZZLE).unwrap();
+        let actions = brutalize::State::transitions(&state, &data)
+            .into_iter()
+            .map(|(action, _)| action.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(actions, vec!["Strafe Right", "Rotate Up", "Rotate Down"]);
+
+        // Solutions are written as bare directions and still replay
+        let solution = [
+            SausageAction::Strafe(Direction::Right),
+            SausageAction::Rotate(Direction::Up),
+        ];
+        let written = State::write_solution(&solution).unwrap();
+        assert_eq!(written, "RU");
+        assert_eq!(State::parse_solution(&written).unwrap(), solution);
+    }
+
+    #[test]
+    fn negative_origin() {
+        const PUZZLE: &str = lines![
+            "puzzle 4 3"
+            "...."
+            ".##."
+            "...."
+            "start 0 0 right"
+            "sausages 1"
+            "1 1 horizontal"
+        ];
+        const SHIFTED: &str = lines![
+            "puzzle 4 3"
+            "...."
+            ".##."
+            "...."
+            "origin -3 -2"
+            "start -3 -2 right"
+            "sausages 1"
+            "-2 -1 horizontal"
+        ];
+
+        let (state, data) = State::parse(SHIFTED).unwrap();
+        assert_eq!(brutalize_cli::write_puzzle(&state, &data), SHIFTED);
+        assert_eq!(data.tile(Vec2::new(-3, -2)), Tile::Ground);
+        assert_eq!(data.tile(Vec2::new(-2, -1)), Tile::Grill);
+        assert_eq!(data.tile(Vec2::new(1, 0)), Tile::Empty);
+        assert_eq!(data.tiles().next(), Some((Vec2::new(-3, -2), Tile::Ground)));
+
+        let (original_state, original_data) = State::parse(PUZZLE).unwrap();
+        assert_eq!(
+            brutalize_cli::display_state(&state, &data),
+            brutalize_cli::display_state(&original_state, &original_data)
+                .replace("sausage 0 at 1 1", "sausage 0 at -2 -1")
+        );
+        assert_eq!(
+            brutalize::solve(state, &data),
+            brutalize::solve(original_state, &original_data)
+        );
+    }
+
+    #[test]
+    fn data_accessors() {
+        const PUZZLE: &str = lines![
+            "puzzle 3 2"
+            "X#."
+            " .."
+            "start 1 0 up"
+            "strict_start"
+            "sausages 0"
+        ];
+
+        let (_, data) = State::parse(PUZZLE).unwrap();
+        assert_eq!(data.size(), Vec2::new(3, 2));
+        assert_eq!(data.goals(), &[(Vec2::new(1, 0), Direction::Up)]);
+        assert!(data.strict_start());
+
+        let tiles = data.tiles().collect::<Vec<_>>();
+        assert_eq!(tiles.len(), 6);
+        assert_eq!(tiles[0], (Vec2::new(0, 0), Tile::Empty));
+        assert_eq!(tiles[3], (Vec2::new(0, 1), Tile::Wall));
+        assert!(tiles
+            .iter()
+            .all(|&(position, tile)| data.tile(position) == tile));
+    }
+
+    #[test]
+    fn display_cooked_sides() {
+        const PUZZLE: &str = lines![
+            "puzzle 4 1"
+            ".#.."
+            "start 0 0 right"
+            "sausages 1"
+            "2 0 horizontal"
+        ];
+
+        let (_, data) = State::parse(PUZZLE).unwrap();
+        let state = StateBuilder::player(0, 0, Direction::Right)
+            .sausage(2, 0, SausageOrientation::Horizontal)
+            .cooked([
+                Cooked::Uncooked,
+                Cooked::Cooked,
+                Cooked::Burned,
+                Cooked::Uncooked,
+            ])
+            .build();
+        let displayed = brutalize_cli::display_state(&state, &data);
+        assert!(displayed.ends_with("sausage 0 at 2 0: top raw/cooked, bottom burned/raw\n"));
+    }
+
+    #[test]
+    fn strict_start() {
+        const PUZZLE: &str = lines![
+            "puzzle 5 1"
+            "....#"
+            "start 0 0 right"
+            "strict_start"
+            "sausages 1"
+            "3 0 horizontal"
+        ];
+
+        let (state, data) = State::parse(PUZZLE).unwrap();
+        assert_eq!(brutalize_cli::write_puzzle(&state, &data), PUZZLE);
+
+        // Standing on the start is only fatal with the flag
+        let away = state.transition(&data, Direction::Right).unwrap();
+        let lenient = Data {
+            strict_start: false,
+            fork_length: 1,
+            ..data.clone()
+        };
+        assert!(lenient.status_of(&state) == Status::Unsolved);
+        assert!(data.status_of(&state) == Status::Failed);
+        assert!(data.status_of(&away) == Status::Unsolved);
+    }
+
+    #[test]
+    fn strict_overhang() {
+        const PUZZLE: &str = lines![
+            "puzzle 5 1"
+            ".... "
+            "start 0 0 right"
+            "strict_overhang"
+            "sausages 1"
+            "2 0 horizontal"
+        ];
+
+        let (state, data) = State::parse(PUZZLE).unwrap();
+        assert_eq!(brutalize_cli::write_puzzle(&state, &data), PUZZLE);
+        assert!(data.strict_overhang());
+
+        // Pushing the sausage halfway off the edge is only fatal with the flag
+        let pushed = state.transition(&data, Direction::Right).unwrap();
+        let lenient = Data {
+            strict_overhang: false,
+            ..data.clone()
+        };
+        assert!(lenient.status_of(&pushed) == Status::Unsolved);
+        assert!(data.status_of(&pushed) == Status::Failed);
+        assert_eq!(
+            data.failure_of(&pushed).unwrap().to_string(),
+            "sausage 0 hanging over the water"
+        );
+    }
+
+    #[test]
+    fn co_op() {
+        const PUZZLE: &str = lines![
+            "puzzle 5 2"
+            "....."
+            "....."
+            "players 2"
+            "0 0 right"
+            "0 1 right"
+            "sausages 1"
+            "2 0 vertical"
+        ];
+
+        let (state, data) = State::parse(PUZZLE).unwrap();
+        assert_eq!(brutalize_cli::write_puzzle(&state, &data), PUZZLE);
+
+        // Both players step together, and the pair pushes the sausage
+        let moved = state.transition(&data, Direction::Right).unwrap();
+        assert_eq!(moved.players[0].position, Vec2::new(1, 0));
+        assert_eq!(moved.players[1].position, Vec2::new(1, 1));
+        assert_eq!(moved.sausages[0].position, Vec2::new(3, 0));
+
+        // Only the second player may leave, the first would hit its partner
+        let turned = state.transition(&data, Direction::Up).unwrap();
+        assert_eq!(turned.players[0], state.players[0]);
+        assert_eq!(turned.players[1].position, Vec2::new(0, 1));
+        assert_eq!(turned.players[1].orientation, Direction::Up);
+    }
+
+    #[test]
+    fn validate_initial_state() {
+        const PUZZLE: &str = lines![
+            "puzzle 4 2"
+            "...."
+            ".   "
+            "start 0 0 right"
+            "strict_start"
+            "sausages 2"
+            "2 0 horizontal"
+            "2 1 horizontal"
+        ];
+
+        let (state, data) = State::parse(PUZZLE).unwrap();
+        assert_eq!(
+            state.validate(&data),
+            Err("sausage 0 off the island".to_string())
+        );
+
+        let mut state = state;
+        state.sausages.remove(0);
+        assert_eq!(state.validate(&data), Ok(()));
+    }
+
+    #[test]
+    fn mud_stops_rolling() {
+        // The sausage is rolled onto the first grill, then pushed from the
+        // middle tile onto the second grill
+        let cook = |middle: char| {
+            let row = format!("...#{}#", middle);
+            let puzzle = format!(
+                "puzzle 6 2\n{}\n{}\nstart 0 0 right\nsausages 1\n2 0 vertical\n",
+                row, row
+            );
+            let (mut state, data) = State::parse(&puzzle).unwrap();
+            for _ in 0..3 {
+                state = state.transition(&data, Direction::Right).unwrap();
+            }
+            assert_eq!(state.sausages[0].position, Vec2::new(5, 0));
+            state.sausages[0].cooked
+        };
+
+        // Rolling off plain ground puts the cooked side back down on the grill
+        assert_eq!(
+            cook('.'),
+            [
+                Cooked::Uncooked,
+                Cooked::Uncooked,
+                Cooked::Burned,
+                Cooked::Burned
+            ]
+        );
+        // Sliding out of mud keeps the cooked side up and cooks the other one
+        assert_eq!(cook('~'), [Cooked::Cooked; 4]);
+    }
+
+    #[test]
+    fn impaled_chain_blocked_forward() {
+        const PUZZLE: &str = lines![
+            "puzzle 4 2"
+            "...X"
+            "...."
+            "start 0 0 right"
+            "sausages 2"
+            "1 0 vertical"
+            "2 0 vertical"
+        ];
+
+        // The impaled sausage pushes the other into a wall, so nothing moves
+        let (state, data) = State::parse(PUZZLE).unwrap();
+        assert_eq!(state.transition(&data, Direction::Right), None);
+    }
+
+    #[test]
+    fn impaled_chain_blocked_backward() {
+        const PUZZLE: &str = lines![
+            "puzzle 3 3"
+            "X.."
+            "..."
+            "..."
+            "start 1 0 right"
+            "sausages 2"
+            "1 1 vertical"
+            "2 0 vertical"
+        ];
+
+        // Backing off a sausage whose push is blocked further down the chain
+        // leaves the whole chain where it was
+        let (state, data) = State::parse(PUZZLE).unwrap();
+        let moved = state.transition(&data, Direction::Left).unwrap();
+        assert_eq!(
+            moved.players[0],
+            Player {
+                position: Vec2::new(0, 0),
+                orientation: Direction::Right,
+            }
+        );
+        assert_eq!(moved.sausages, state.sausages);
+    }
+
+    #[test]
+    fn push_cooks_bottom_halves() {
+        // A 4x4 board with a single grill tile in the middle
+        let mut tiles = vec![Tile::Ground; 16];
+        tiles[1 + 2 * 4] = Tile::Grill;
+        let data = Data {
+            size: Vec2::new(4, 4),
+            origin: Vec2::default(),
+            tiles,
+            goals: arrayvec![(Vec2::new(0, 0), Direction::Right)],
+            alternate_goals: Vec::new(),
+            strict_start: false,
+            strict_overhang: false,
+            fork_length: 1,
+        };
+
+        // Each push lands only the start half on the grill. The top start
+        // half is already cooked so rolls show up as it burning.
+        let cases = [
+            (
+                SausageOrientation::Horizontal,
+                Direction::Right,
+                Vec2::new(0, 2),
+                false,
+            ),
+            (
+                SausageOrientation::Horizontal,
+                Direction::Up,
+                Vec2::new(1, 1),
+                true,
+            ),
+            (
+                SausageOrientation::Horizontal,
+                Direction::Left,
+                Vec2::new(2, 2),
+                false,
+            ),
+            (
+                SausageOrientation::Horizontal,
+                Direction::Down,
+                Vec2::new(1, 3),
+                true,
+            ),
+            (
+                SausageOrientation::Vertical,
+                Direction::Right,
+                Vec2::new(0, 2),
+                true,
+            ),
+            (
+                SausageOrientation::Vertical,
+                Direction::Up,
+                Vec2::new(1, 1),
+                false,
+            ),
+            (
+                SausageOrientation::Vertical,
+                Direction::Left,
+                Vec2::new(2, 2),
+                true,
+            ),
+            (
+                SausageOrientation::Vertical,
+                Direction::Down,
+                Vec2::new(1, 3),
+                false,
+            ),
+        ];
+        for &(orientation, direction, position, rolls) in cases.iter() {
+            let mut sausage = Sausage::new(position, orientation);
+            sausage.cooked[TOP] = Cooked::Cooked;
+            sausage.push(direction, &data, true);
+
+            assert_eq!(sausage.position, Vec2::new(1, 2));
+            let expected = if rolls {
+                [
+                    Cooked::Uncooked,
+                    Cooked::Uncooked,
+                    Cooked::Burned,
+                    Cooked::Uncooked,
+                ]
+            } else {
+                [
+                    Cooked::Cooked,
+                    Cooked::Uncooked,
+                    Cooked::Cooked,
+                    Cooked::Uncooked,
+                ]
+            };
+            assert_eq!(
+                sausage.cooked, expected,
+                "{:?} pushed {:?}",
+                orientation, direction
+            );
+        }
+    }
+
+    #[test]
+    fn sausages_are_interchangeable() {
+        const PUZZLE: &str = lines![
+            "puzzle 5 3"
+            "....."
+            "....."
+            "....."
+            "start 0 0 right"
+            "sausages 2"
+            "3 2 horizontal"
+            "1 1 vertical"
+        ];
+
+        let (state, _) = State::parse(PUZZLE).unwrap();
+        let mut swapped = state.clone();
+        swapped.sausages.swap(0, 1);
+        assert_ne!(swapped, state);
+
+        swapped.canonicalize();
+        assert_eq!(swapped, state);
+        assert_eq!(
+            brutalize::State::pack(&swapped),
+            brutalize::State::pack(&state)
+        );
+    }
+
     #[test]
     fn strafe_roll_two() {
-        const PUZZLE: &'static str = lines![
+        const PUZZLE: &str = lines![
             "puzzle 5 5"
             "....."
             "....."
@@ -805,30 +2196,18 @@ mod tests {
         let (state, data) = State::parse(PUZZLE).unwrap();
         assert_eq!(
             state.transition(&data, Direction::Right),
-            Some(State {
-                player: Player {
-                    position: Vec2::new(1, 0),
-                    orientation: Direction::Right,
-                },
-                sausages: arrayvec![
-                    Sausage {
-                        position: Vec2::new(3, 0),
-                        orientation: SausageOrientation::Vertical,
-                        cooked: [Cooked::Uncooked; 4],
-                    },
-                    Sausage {
-                        position: Vec2::new(4, 1),
-                        orientation: SausageOrientation::Vertical,
-                        cooked: [Cooked::Uncooked; 4],
-                    },
-                ],
-            })
+            Some(
+                StateBuilder::player(1, 0, Direction::Right)
+                    .sausage(3, 0, SausageOrientation::Vertical)
+                    .sausage(4, 1, SausageOrientation::Vertical)
+                    .build(),
+            )
         )
     }
 
     #[test]
     fn turn_roll_two() {
-        const PUZZLE: &'static str = lines![
+        const PUZZLE: &str = lines![
             "puzzle 5 5"
             "....."
             "....."
@@ -844,30 +2223,18 @@ mod tests {
         let (state, data) = State::parse(PUZZLE).unwrap();
         assert_eq!(
             state.transition(&data, Direction::Right),
-            Some(State {
-                player: Player {
-                    position: Vec2::new(0, 1),
-                    orientation: Direction::Right,
-                },
-                sausages: arrayvec![
-                    Sausage {
-                        position: Vec2::new(1, 0),
-                        orientation: SausageOrientation::Horizontal,
-                        cooked: [Cooked::Uncooked; 4],
-                    },
-                    Sausage {
-                        position: Vec2::new(2, 2),
-                        orientation: SausageOrientation::Vertical,
-                        cooked: [Cooked::Uncooked; 4],
-                    },
-                ],
-            })
+            Some(
+                StateBuilder::player(0, 1, Direction::Right)
+                    .sausage(1, 0, SausageOrientation::Horizontal)
+                    .sausage(2, 2, SausageOrientation::Vertical)
+                    .build(),
+            )
         )
     }
 
     #[test]
     fn half_turn_roll() {
-        const PUZZLE: &'static str = lines![
+        const PUZZLE: &str = lines![
             "puzzle 3 3"
             "..."
             "..."
@@ -880,19 +2247,106 @@ mod tests {
         let (state, data) = State::parse(PUZZLE).unwrap();
         assert_eq!(
             state.transition(&data, Direction::Right),
-            Some(State {
-                player: Player {
-                    position: Vec2::new(0, 0),
-                    orientation: Direction::Up,
-                },
-                sausages: arrayvec![
-                    Sausage {
-                        position: Vec2::new(2, 1),
-                        orientation: SausageOrientation::Vertical,
-                        cooked: [Cooked::Uncooked; 4],
-                    },
-                ],
-            })
+            Some(
+                StateBuilder::player(0, 0, Direction::Up)
+                    .sausage(2, 1, SausageOrientation::Vertical)
+                    .build(),
+            )
         )
     }
+    #[test]
+    fn long_fork() {
+        const PUZZLE: &str = lines![
+            "puzzle 5 2"
+            "....."
+            "....."
+            "start 0 0 right 2"
+            "sausages 1"
+            "3 0 vertical"
+        ];
+
+        let (state, data) = State::parse(PUZZLE).unwrap();
+        assert_eq!(data.fork_length(), 2);
+        assert_eq!(brutalize_cli::write_puzzle(&state, &data), PUZZLE);
+
+        // The tip of the fork reaches the sausage two tiles away
+        assert_eq!(
+            state.transition(&data, Direction::Right),
+            Some(
+                StateBuilder::player(1, 0, Direction::Right)
+                    .sausage(4, 0, SausageOrientation::Vertical)
+                    .build(),
+            )
+        );
+
+        let too_long = PUZZLE.replace("right 2", "right 5");
+        assert!(matches!(
+            State::parse(&too_long),
+            Err(ParseError::ForkLengthOutOfRange { length: 5, .. })
+        ));
+    }
+
+    #[test]
+    fn alternate_goals() {
+        const PUZZLE: &str = lines![
+            "puzzle 5 1"
+            "....#"
+            "start 0 0 right or 2 0 right or 1 0 left"
+            "sausages 1"
+            "3 0 horizontal"
+        ];
+
+        let (state, data) = State::parse(PUZZLE).unwrap();
+        assert_eq!(brutalize_cli::write_puzzle(&state, &data), PUZZLE);
+        assert_eq!(
+            data.alternate_goals(),
+            &[
+                (Vec2::new(2, 0), Direction::Right),
+                (Vec2::new(1, 0), Direction::Left)
+            ]
+        );
+        assert_eq!(brutalize_cli::State::validate(&state, &data), Ok(()));
+
+        // Finishing beside the start saves a couple of moves on seafinger
+        let source = brutalize_puzzles::load("sausage/seafinger").unwrap();
+        let (state, data) = State::parse(&source).unwrap();
+        assert_eq!(brutalize::solve(state, &data).map(|s| s.len()), Some(18));
+        let nearby = source.replace("start 4 1 up", "start 4 1 up or 3 1 left");
+        let (state, data) = State::parse(&nearby).unwrap();
+        assert_eq!(brutalize::solve(state, &data).map(|s| s.len()), Some(16));
+
+        let stranded = PUZZLE.replace("1 0 left", "4 0 left");
+        let (state, data) = State::parse(&stranded).unwrap();
+        assert_eq!(
+            brutalize_cli::State::validate(&state, &data),
+            Err("the player can't finish at 4 0, which isn't ground".to_string())
+        );
+
+        let garbled = PUZZLE.replace("or 1", "and 1");
+        assert!(matches!(
+            State::parse(&garbled),
+            Err(ParseError::UnexpectedStartPiece { line_number: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn fancy_board() {
+        const PUZZLE: &str = lines![
+            "puzzle 5 1"
+            "....#"
+            "start 0 0 right"
+            "sausages 1"
+            "3 0 horizontal"
+        ];
+
+        let (state, data) = State::parse(PUZZLE).unwrap();
+        let fancy = brutalize_cli::fancy_state(&state, &data).unwrap();
+        let lines = fancy.lines().collect::<Vec<_>>();
+        assert!(lines[0].starts_with('┌') && lines[0].ends_with('┐'));
+        assert!(lines.iter().any(|line| line.contains("🧑🍴░░🌭🌭")));
+        assert_eq!(
+            lines.last(),
+            Some(&"sausage 0 at 3 0: top raw/raw, bottom raw/raw")
+        );
+    }
 }
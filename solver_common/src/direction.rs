@@ -2,6 +2,7 @@ use crate::vec2::Vec2;
 use std::{fmt, str::FromStr};
 
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub enum Direction {
     Right,
     Up,
@@ -65,6 +66,12 @@ impl fmt::Display for Direction {
 #[derive(Debug)]
 pub struct ParseDirectionError(String);
 
+impl fmt::Display for ParseDirectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown direction '{}'", self.0)
+    }
+}
+
 impl FromStr for Direction {
     type Err = ParseDirectionError;
 
@@ -0,0 +1,115 @@
+use crate::{direction::Direction, vec3::Vec3};
+use std::{fmt, str::FromStr};
+
+/// A direction in 3D: one of the four planar directions, or straight up or
+/// down a level. Games without height keep using `Direction`.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum Direction3 {
+    Right,
+    Up,
+    Left,
+    Down,
+    Above,
+    Below,
+}
+
+impl Direction3 {
+    #[inline]
+    pub fn reverse(self) -> Direction3 {
+        match self {
+            Direction3::Right => Direction3::Left,
+            Direction3::Up => Direction3::Down,
+            Direction3::Left => Direction3::Right,
+            Direction3::Down => Direction3::Up,
+            Direction3::Above => Direction3::Below,
+            Direction3::Below => Direction3::Above,
+        }
+    }
+
+    #[inline]
+    pub fn to_vec3(self) -> Vec3 {
+        match self {
+            Direction3::Right => Vec3::right(),
+            Direction3::Up => Vec3::up(),
+            Direction3::Left => Vec3::left(),
+            Direction3::Down => Vec3::down(),
+            Direction3::Above => Vec3::above(),
+            Direction3::Below => Vec3::below(),
+        }
+    }
+
+    /// The planar direction, if this isn't a vertical one.
+    #[inline]
+    pub fn to_planar(self) -> Option<Direction> {
+        match self {
+            Direction3::Right => Some(Direction::Right),
+            Direction3::Up => Some(Direction::Up),
+            Direction3::Left => Some(Direction::Left),
+            Direction3::Down => Some(Direction::Down),
+            Direction3::Above | Direction3::Below => None,
+        }
+    }
+}
+
+impl From<Direction> for Direction3 {
+    #[inline]
+    fn from(direction: Direction) -> Direction3 {
+        match direction {
+            Direction::Right => Direction3::Right,
+            Direction::Up => Direction3::Up,
+            Direction::Left => Direction3::Left,
+            Direction::Down => Direction3::Down,
+        }
+    }
+}
+
+impl fmt::Display for Direction3 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Direction3::Right => write!(f, "Right"),
+            Direction3::Up => write!(f, "Up"),
+            Direction3::Left => write!(f, "Left"),
+            Direction3::Down => write!(f, "Down"),
+            Direction3::Above => write!(f, "Above"),
+            Direction3::Below => write!(f, "Below"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseDirection3Error(String);
+
+impl fmt::Display for ParseDirection3Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown direction '{}'", self.0)
+    }
+}
+
+impl FromStr for Direction3 {
+    type Err = ParseDirection3Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "right" => Ok(Direction3::Right),
+            "up" => Ok(Direction3::Up),
+            "left" => Ok(Direction3::Left),
+            "down" => Ok(Direction3::Down),
+            "above" => Ok(Direction3::Above),
+            "below" => Ok(Direction3::Below),
+            _ => Err(ParseDirection3Error(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Direction3;
+
+    #[test]
+    fn parse_directions() {
+        assert_eq!("above".parse::<Direction3>().unwrap(), Direction3::Above);
+        let error = "sideways".parse::<Direction3>().unwrap_err();
+        assert_eq!(error.to_string(), "unknown direction 'sideways'");
+    }
+}
@@ -1,5 +1,15 @@
 mod direction;
+mod direction3;
+mod moves;
+mod notation;
+mod status;
 mod vec2;
+mod vec3;
 
 pub use crate::direction::*;
+pub use crate::direction3::*;
+pub use crate::moves::*;
+pub use crate::notation::*;
+pub use crate::status::*;
 pub use crate::vec2::*;
+pub use crate::vec3::*;
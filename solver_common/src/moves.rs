@@ -0,0 +1,151 @@
+use crate::{direction::Direction, vec2::Vec2};
+use std::{fmt, str::FromStr};
+
+/// A single input in games that allow more than the four directions.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum Move {
+    Right,
+    Up,
+    Left,
+    Down,
+    UpRight,
+    UpLeft,
+    DownLeft,
+    DownRight,
+    Wait,
+}
+
+impl Move {
+    #[inline]
+    pub fn to_vec2(self) -> Vec2 {
+        match self {
+            Move::Right => Vec2::right(),
+            Move::Up => Vec2::up(),
+            Move::Left => Vec2::left(),
+            Move::Down => Vec2::down(),
+            Move::UpRight => Vec2::up() + Vec2::right(),
+            Move::UpLeft => Vec2::up() + Vec2::left(),
+            Move::DownLeft => Vec2::down() + Vec2::left(),
+            Move::DownRight => Vec2::down() + Vec2::right(),
+            Move::Wait => Vec2::zero(),
+        }
+    }
+}
+
+impl From<Direction> for Move {
+    #[inline]
+    fn from(direction: Direction) -> Move {
+        match direction {
+            Direction::Right => Move::Right,
+            Direction::Up => Move::Up,
+            Direction::Left => Move::Left,
+            Direction::Down => Move::Down,
+        }
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Move::Right => write!(f, "Right"),
+            Move::Up => write!(f, "Up"),
+            Move::Left => write!(f, "Left"),
+            Move::Down => write!(f, "Down"),
+            Move::UpRight => write!(f, "UpRight"),
+            Move::UpLeft => write!(f, "UpLeft"),
+            Move::DownLeft => write!(f, "DownLeft"),
+            Move::DownRight => write!(f, "DownRight"),
+            Move::Wait => write!(f, "Wait"),
+        }
+    }
+}
+
+/// The optional moves a puzzle allows on top of the four directions.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct MoveSet {
+    pub wait: bool,
+    pub diagonal: bool,
+}
+
+impl MoveSet {
+    /// Iterates over every move allowed by the set, directions first.
+    pub fn moves(self) -> impl Iterator<Item = Move> {
+        [
+            Move::Right,
+            Move::Up,
+            Move::Left,
+            Move::Down,
+            Move::UpRight,
+            Move::UpLeft,
+            Move::DownLeft,
+            Move::DownRight,
+            Move::Wait,
+        ]
+        .iter()
+        .cloned()
+        .filter(move |m| match m {
+            Move::UpRight | Move::UpLeft | Move::DownLeft | Move::DownRight => self.diagonal,
+            Move::Wait => self.wait,
+            _ => true,
+        })
+    }
+
+    /// The fewest moves needed to travel by `offset` on an open board. This
+    /// is the Manhattan distance, or the Chebyshev distance with diagonals.
+    #[inline]
+    pub fn distance(self, offset: Vec2) -> usize {
+        let offset = offset.abs();
+        if self.diagonal {
+            i32::max(offset.x, offset.y) as usize
+        } else {
+            (offset.x + offset.y) as usize
+        }
+    }
+
+    /// Whether the set only contains the four directions.
+    #[inline]
+    pub fn is_default(self) -> bool {
+        self == MoveSet::default()
+    }
+}
+
+impl fmt::Display for MoveSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut flags = Vec::new();
+        if self.wait {
+            flags.push("wait");
+        }
+        if self.diagonal {
+            flags.push("diagonal");
+        }
+        write!(f, "{}", flags.join(" "))
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseMoveSetError(String);
+
+impl fmt::Display for ParseMoveSetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown action flag '{}'", self.0)
+    }
+}
+
+impl FromStr for MoveSet {
+    type Err = ParseMoveSetError;
+
+    /// Parses a space-separated list of `wait` and `diagonal` flags.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut result = MoveSet::default();
+        for flag in s.split(' ').filter(|f| !f.is_empty()) {
+            match flag {
+                "wait" => result.wait = true,
+                "diagonal" => result.diagonal = true,
+                _ => return Err(ParseMoveSetError(flag.to_string())),
+            }
+        }
+        Ok(result)
+    }
+}
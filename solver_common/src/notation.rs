@@ -0,0 +1,116 @@
+use crate::{direction::Direction, moves::Move};
+use std::{fmt, str::FromStr};
+
+/// A compact notation for actions, with one character each, so that
+/// solutions can be written down and read back.
+pub trait Notation: Sized {
+    fn to_char(&self) -> char;
+    fn from_char(c: char) -> Option<Self>;
+}
+
+impl Notation for Direction {
+    fn to_char(&self) -> char {
+        match *self {
+            Direction::Right => 'R',
+            Direction::Up => 'U',
+            Direction::Left => 'L',
+            Direction::Down => 'D',
+        }
+    }
+
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            'R' => Some(Direction::Right),
+            'U' => Some(Direction::Up),
+            'L' => Some(Direction::Left),
+            'D' => Some(Direction::Down),
+            _ => None,
+        }
+    }
+}
+
+/// Diagonals use the digits where they sit on a numeric keypad.
+impl Notation for Move {
+    fn to_char(&self) -> char {
+        match *self {
+            Move::Right => 'R',
+            Move::Up => 'U',
+            Move::Left => 'L',
+            Move::Down => 'D',
+            Move::UpRight => '9',
+            Move::UpLeft => '7',
+            Move::DownLeft => '1',
+            Move::DownRight => '3',
+            Move::Wait => '.',
+        }
+    }
+
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            '9' => Some(Move::UpRight),
+            '7' => Some(Move::UpLeft),
+            '1' => Some(Move::DownLeft),
+            '3' => Some(Move::DownRight),
+            '.' => Some(Move::Wait),
+            c => Direction::from_char(c).map(Move::from),
+        }
+    }
+}
+
+/// A solution written in its actions' notation, such as `RRULD`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Solution<A>(pub Vec<A>);
+
+impl<A: Notation> fmt::Display for Solution<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for action in self.0.iter() {
+            write!(f, "{}", action.to_char())?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseSolutionError(char);
+
+impl fmt::Display for ParseSolutionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown action '{}'", self.0)
+    }
+}
+
+impl<A: Notation> FromStr for Solution<A> {
+    type Err = ParseSolutionError;
+
+    /// Parses a solution, ignoring any whitespace between actions.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| A::from_char(c).ok_or(ParseSolutionError(c)))
+            .collect::<Result<_, _>>()
+            .map(Solution)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solution_round_trip() {
+        let solution = "RU 7.3".parse::<Solution<Move>>().unwrap();
+        assert_eq!(
+            solution.0,
+            vec![
+                Move::Right,
+                Move::Up,
+                Move::UpLeft,
+                Move::Wait,
+                Move::DownRight
+            ]
+        );
+        assert_eq!(solution.to_string(), "RU7.3");
+        let error = "RX".parse::<Solution<Direction>>().unwrap_err();
+        assert_eq!(error.to_string(), "unknown action 'X'");
+    }
+}
@@ -0,0 +1,36 @@
+use brutalize::{State, Transition};
+
+/// How a puzzle stands after a move. Games report a status for each new state
+/// and let `into_transition` decide what the search sees.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Status<O = ()> {
+    Solved(O),
+    Unsolved,
+    Failed,
+}
+
+impl<O> Status<O> {
+    /// Converts the status of a state into the transition to hand to the
+    /// search. Failed states are pruned, so `state` is only called to produce
+    /// the state when the puzzle is unsolved.
+    #[inline]
+    pub fn into_transition<S: State<Outcome = O>>(
+        self,
+        state: impl FnOnce() -> S,
+    ) -> Option<Transition<S>> {
+        match self {
+            Status::Solved(outcome) => Some(Transition::Success(outcome)),
+            Status::Unsolved => Some(Transition::Indeterminate(state())),
+            Status::Failed => None,
+        }
+    }
+
+    /// The outcome of a solved puzzle, for implementing `State::is_goal`.
+    #[inline]
+    pub fn outcome(self) -> Option<O> {
+        match self {
+            Status::Solved(outcome) => Some(outcome),
+            Status::Unsolved | Status::Failed => None,
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use std::ops::{Add, AddAssign, Mul, MulAssign, Sub, SubAssign};
 
 #[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct Vec2 {
     pub x: i32,
     pub y: i32,
@@ -39,7 +40,28 @@ impl Vec2 {
 
     #[inline]
     pub fn abs(self) -> Vec2 {
-        Vec2 { x: self.x.abs(), y: self.y.abs() }
+        Vec2 {
+            x: self.x.abs(),
+            y: self.y.abs(),
+        }
+    }
+
+    /// Rotates a quarter turn clockwise about the origin.
+    #[inline]
+    pub fn rotate_cw(self) -> Vec2 {
+        Vec2 {
+            x: self.y,
+            y: -self.x,
+        }
+    }
+
+    /// Rotates a quarter turn counterclockwise about the origin.
+    #[inline]
+    pub fn rotate_ccw(self) -> Vec2 {
+        Vec2 {
+            x: -self.y,
+            y: self.x,
+        }
     }
 }
 
@@ -99,3 +121,24 @@ impl MulAssign<i32> for Vec2 {
         *self = *self * other;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{Direction, Vec2};
+
+    #[test]
+    fn rotations_match_directions() {
+        for &d in [
+            Direction::Right,
+            Direction::Up,
+            Direction::Left,
+            Direction::Down,
+        ]
+        .iter()
+        {
+            assert_eq!(d.to_vec2().rotate_cw(), d.rotate_cw().to_vec2());
+            assert_eq!(d.to_vec2().rotate_ccw(), d.rotate_ccw().to_vec2());
+        }
+        assert_eq!(Vec2::new(2, 1).rotate_cw().rotate_ccw(), Vec2::new(2, 1));
+    }
+}
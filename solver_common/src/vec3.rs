@@ -0,0 +1,170 @@
+use crate::vec2::Vec2;
+use std::ops::{Add, AddAssign, Mul, MulAssign, Sub, SubAssign};
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Vec3 {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl Vec3 {
+    #[inline]
+    pub fn right() -> Vec3 {
+        Vec3 { x: 1, y: 0, z: 0 }
+    }
+
+    #[inline]
+    pub fn up() -> Vec3 {
+        Vec3 { x: 0, y: 1, z: 0 }
+    }
+
+    #[inline]
+    pub fn left() -> Vec3 {
+        Vec3 { x: -1, y: 0, z: 0 }
+    }
+
+    #[inline]
+    pub fn down() -> Vec3 {
+        Vec3 { x: 0, y: -1, z: 0 }
+    }
+
+    #[inline]
+    pub fn above() -> Vec3 {
+        Vec3 { x: 0, y: 0, z: 1 }
+    }
+
+    #[inline]
+    pub fn below() -> Vec3 {
+        Vec3 { x: 0, y: 0, z: -1 }
+    }
+
+    #[inline]
+    pub fn zero() -> Vec3 {
+        Vec3 { x: 0, y: 0, z: 0 }
+    }
+
+    #[inline]
+    pub fn new(x: i32, y: i32, z: i32) -> Vec3 {
+        Vec3 { x, y, z }
+    }
+
+    #[inline]
+    pub fn abs(self) -> Vec3 {
+        Vec3 {
+            x: self.x.abs(),
+            y: self.y.abs(),
+            z: self.z.abs(),
+        }
+    }
+
+    /// Rotates a quarter turn clockwise about the x axis, looking along it
+    /// from the positive side.
+    #[inline]
+    pub fn rotate_x_cw(self) -> Vec3 {
+        Vec3::new(self.x, self.z, -self.y)
+    }
+
+    #[inline]
+    pub fn rotate_x_ccw(self) -> Vec3 {
+        Vec3::new(self.x, -self.z, self.y)
+    }
+
+    /// Rotates a quarter turn clockwise about the y axis, looking along it
+    /// from the positive side.
+    #[inline]
+    pub fn rotate_y_cw(self) -> Vec3 {
+        Vec3::new(-self.z, self.y, self.x)
+    }
+
+    #[inline]
+    pub fn rotate_y_ccw(self) -> Vec3 {
+        Vec3::new(self.z, self.y, -self.x)
+    }
+
+    /// Rotates a quarter turn clockwise about the z axis, matching
+    /// `Vec2::rotate_cw` on the planar part.
+    #[inline]
+    pub fn rotate_z_cw(self) -> Vec3 {
+        Vec3::new(self.y, -self.x, self.z)
+    }
+
+    #[inline]
+    pub fn rotate_z_ccw(self) -> Vec3 {
+        Vec3::new(-self.y, self.x, self.z)
+    }
+
+    /// The planar part of the vector, dropping the height.
+    #[inline]
+    pub fn xy(self) -> Vec2 {
+        Vec2::new(self.x, self.y)
+    }
+}
+
+impl From<Vec2> for Vec3 {
+    #[inline]
+    fn from(v: Vec2) -> Vec3 {
+        Vec3::new(v.x, v.y, 0)
+    }
+}
+
+impl Add for Vec3 {
+    type Output = Vec3;
+
+    #[inline]
+    fn add(self, other: Vec3) -> Vec3 {
+        Vec3 {
+            x: self.x + other.x,
+            y: self.y + other.y,
+            z: self.z + other.z,
+        }
+    }
+}
+
+impl AddAssign for Vec3 {
+    #[inline]
+    fn add_assign(&mut self, other: Vec3) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Vec3 {
+    type Output = Vec3;
+
+    #[inline]
+    fn sub(self, other: Vec3) -> Vec3 {
+        Vec3 {
+            x: self.x - other.x,
+            y: self.y - other.y,
+            z: self.z - other.z,
+        }
+    }
+}
+
+impl SubAssign for Vec3 {
+    #[inline]
+    fn sub_assign(&mut self, other: Vec3) {
+        *self = *self - other;
+    }
+}
+
+impl Mul<i32> for Vec3 {
+    type Output = Vec3;
+
+    #[inline]
+    fn mul(self, other: i32) -> Vec3 {
+        Vec3 {
+            x: self.x * other,
+            y: self.y * other,
+            z: self.z * other,
+        }
+    }
+}
+
+impl MulAssign<i32> for Vec3 {
+    #[inline]
+    fn mul_assign(&mut self, other: i32) {
+        *self = *self * other;
+    }
+}